@@ -0,0 +1,3060 @@
+// Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tauri::{
+    menu::{Menu, MenuItem},
+    tray::{TrayIconBuilder, TrayIconEvent},
+    AppHandle, Emitter, Manager, WindowEvent, Window,
+};
+use tokio::io::{AsyncBufReadExt, AsyncSeekExt, BufReader};
+use tokio::process::Command as TokioCommand;
+use tokio::sync::mpsc;
+use tokio::time::{sleep, Duration};
+
+use crate::{
+    archive, checksum, config_validation, download, fatal_rules, liveness, log_parser, platform,
+    port_check, process_control, proxy, registry, release_sources, restart, runtime_state,
+    system_proxy, update_check,
+};
+use crate::{GitHubAsset, GitHubRelease, ProxySettings};
+use crate::config_validation::FieldError;
+use crate::fatal_rules::{FatalRule, FatalRuleStore, RuleAction, RuleHit, RuleTestResult};
+use crate::liveness::ProcessLiveness;
+use crate::nodepass_config::NodePassConfig;
+use crate::process_control::{StopMethod, StopOutcome, StopRequest, TunnelProcess};
+use crate::release_sources::{ReleaseSource, ReleaseSourceStore};
+use crate::registry::{TunnelDefinition, TunnelRegistry, TunnelSnapshot, TunnelStatus};
+use crate::restart::{RestartPolicy, RestartState, RestartStats};
+use crate::runtime_state::RuntimeRecord;
+use crate::config_store::ConfigStore;
+use crate::start_error::StartError;
+use crate::tunnel_logs::{LogHistoryEntry, LogSettings, TunnelLogStore};
+use tauri_plugin_notification::NotificationExt;
+use crate::update_check::{UpdateCheckSettings, UpdateChecker};
+use crate::version_probe::VersionProbe;
+use crate::versions::{InstalledVersion, VersionStore};
+
+#[derive(Debug, Serialize, Deserialize)]
+struct NodePassStatus {
+    installed: bool,
+    version: Option<String>,
+    // 版本行中的系统、架构和其它构建信息
+    os: Option<String>,
+    arch: Option<String>,
+    build: Option<String>,
+    path: Option<String>,
+    error: Option<String>,
+}
+
+
+// 读取隧道输出文件的轮询间隔
+const LOG_TAIL_INTERVAL: Duration = Duration::from_millis(200);
+
+// 后台更新检查：启动后的首次检查延迟，以及检查是否到期的间隔
+const UPDATE_CHECK_STARTUP_DELAY: Duration = Duration::from_secs(30);
+const UPDATE_CHECK_TICK: Duration = Duration::from_secs(60);
+
+// 升级后滚动重启时，等待隧道稳定运行的时间
+const UPGRADE_HEALTH_CHECK_DELAY: Duration = Duration::from_secs(3);
+
+// 全局下载取消标志
+static DOWNLOAD_CANCELLED: AtomicBool = AtomicBool::new(false);
+// 全局下载暂停标志，暂停时保留已下载的部分
+static DOWNLOAD_PAUSED: AtomicBool = AtomicBool::new(false);
+
+struct AppState {
+    registry: TunnelRegistry,
+    liveness: Arc<dyn ProcessLiveness>,
+    // 前端的完整应用配置
+    app_config: ConfigStore,
+    // 隧道进程输出文件所在目录
+    capture_dir: PathBuf,
+    tunnel_logs: TunnelLogStore,
+    fatal_rules: FatalRuleStore,
+    // 已安装的 NodePass 版本
+    versions: VersionStore,
+    version_probe: VersionProbe,
+    update_checker: UpdateChecker,
+    release_sources: ReleaseSourceStore,
+}
+
+impl AppState {
+    fn new() -> Self {
+        let config_dir = dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("nodepass-gui");
+
+        if !config_dir.exists() {
+            let _ = fs::create_dir_all(&config_dir);
+        }
+
+        let state = Self {
+            registry: TunnelRegistry::load(
+                config_dir.join("tunnels.json"),
+                config_dir.join("runtime.json"),
+            ),
+            liveness: liveness::platform(),
+            app_config: ConfigStore::load(config_dir.join("app_config.json")),
+            capture_dir: config_dir.join("capture"),
+            tunnel_logs: TunnelLogStore::new(
+                config_dir.join("logs"),
+                config_dir.join("log_settings.json"),
+            ),
+            fatal_rules: FatalRuleStore::load(config_dir.join("fatal_rules.json")),
+            release_sources: ReleaseSourceStore::load(config_dir.join("release_sources.json")),
+            update_checker: UpdateChecker::load(
+                config_dir.join("update_check.json"),
+                config_dir.join("release_cache.json"),
+            ),
+            versions: VersionStore::new(
+                dirs::data_dir()
+                    .unwrap_or_else(|| PathBuf::from("."))
+                    .join("nodepass-gui"),
+            ),
+            version_probe: VersionProbe::new(),
+        };
+
+        // 隧道定义只保存在 tunnels.json，导入旧版本保存在其他文件中的隧道
+        import_saved_configs(&state.registry, &config_dir.join("configs.json"));
+        import_app_config_tunnels(&state);
+        state
+    }
+}
+
+// 旧版本把隧道列表保存在应用配置中，导入注册表后从应用配置中移除
+fn import_app_config_tunnels(state: &AppState) {
+    let tunnels = state.app_config.legacy_tunnels();
+    if tunnels.is_empty() {
+        return;
+    }
+    // 前端保存的状态和进程ID等运行时字段会被忽略
+    let definitions = tunnels
+        .into_iter()
+        .filter_map(|value| {
+            serde_json::from_value::<TunnelDefinition>(value)
+                .map_err(|e| println!("跳过无法解析的旧隧道配置: {}", e))
+                .ok()
+        })
+        .collect();
+    match state.registry.import(definitions) {
+        Ok(count) => {
+            println!("已从应用配置导入 {} 个隧道", count);
+            if let Err(e) = state.app_config.remove_tunnels() {
+                println!("移除应用配置中的隧道列表失败: {}", e);
+            }
+        }
+        Err(e) => println!("导入应用配置中的隧道失败: {}", e),
+    }
+}
+
+// 旧版本 save_config 保存在 configs.json 中的配置，导入后改名保留原文件
+fn import_saved_configs(registry: &TunnelRegistry, config_file: &Path) {
+    if !config_file.exists() {
+        return;
+    }
+    let configs: Vec<NodePassConfig> = match fs::read_to_string(config_file)
+        .map_err(|e| e.to_string())
+        .and_then(|content| serde_json::from_str(&content).map_err(|e| e.to_string()))
+    {
+        Ok(configs) => configs,
+        Err(e) => {
+            println!("读取旧配置文件失败: {}", e);
+            return;
+        }
+    };
+
+    let created_at = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+    let definitions = configs
+        .into_iter()
+        .map(|config| TunnelDefinition {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: format!("{} {}", config.mode, config.tunnel_addr),
+            config,
+            restart_policy: RestartPolicy::default(),
+            stop_timeout_ms: process_control::DEFAULT_STOP_TIMEOUT_MS,
+            created_at: Some(created_at.clone()),
+            nodepass_version: None,
+        })
+        .collect();
+    match registry.import(definitions) {
+        Ok(count) => {
+            println!("已从 configs.json 导入 {} 个隧道", count);
+            if let Err(e) = fs::rename(config_file, config_file.with_extension("json.migrated")) {
+                println!("重命名旧配置文件失败: {}", e);
+            }
+        }
+        Err(e) => println!("导入 configs.json 中的隧道失败: {}", e),
+    }
+}
+
+// 按隧道ID启动注册表中的隧道
+async fn start_registered_tunnel(
+    app_handle: &AppHandle,
+    registry: &TunnelRegistry,
+    tunnel_id: &str,
+) -> Result<u32, StartError> {
+    if let Some(pid) = registry.pid_of(tunnel_id) {
+        return Err(format!("隧道 {} 已在运行，进程ID: {}", tunnel_id, pid).into());
+    }
+
+    let (definition, nodepass_path) = prepare_launch(app_handle, registry, tunnel_id).await?;
+
+    let (process, child_id, stop_rx) = spawn_nodepass_process(
+        app_handle,
+        registry,
+        &nodepass_path,
+        &definition.config,
+        tunnel_id.to_string(),
+    )?;
+
+    // 重置该隧道的重启状态，递增代数使旧的监控任务放弃重启
+    registry.with_restart(tunnel_id, |restart_state| {
+        *restart_state = RestartState {
+            generation: restart_state.generation + 1,
+            ..RestartState::default()
+        };
+    });
+
+    // 监控进程状态，按重启策略自动拉起
+    tokio::spawn(supervise_tunnel(
+        app_handle.clone(),
+        registry.clone(),
+        nodepass_path,
+        tunnel_id.to_string(),
+        process,
+        child_id,
+        stop_rx,
+    ));
+
+    // 发送状态更新事件
+    let _ = app_handle.emit(
+        "tunnel-status-changed",
+        serde_json::json!({
+            "tunnel_id": tunnel_id,
+            "status": "running",
+            "pid": child_id
+        }),
+    );
+
+    Ok(child_id)
+}
+
+// 启动前的检查：配置校验和端口占用，并确定使用的NodePass可执行文件
+async fn prepare_launch(
+    app_handle: &AppHandle,
+    registry: &TunnelRegistry,
+    tunnel_id: &str,
+) -> Result<(TunnelDefinition, String), StartError> {
+    let definition = registry
+        .definition(tunnel_id)
+        .ok_or_else(|| format!("隧道 {} 不存在", tunnel_id))?;
+
+    // 启动前检查配置，按字段通知前端
+    let errors = config_validation::validate_config(&definition.config);
+    if !errors.is_empty() {
+        let _ = app_handle.emit(
+            "tunnel-config-invalid",
+            serde_json::json!({
+                "tunnel_id": tunnel_id,
+                "errors": errors
+            }),
+        );
+        return Err(StartError::InvalidConfig {
+            message: format!("隧道配置无效: {}", config_validation::summarize(&errors)),
+            errors,
+        });
+    }
+
+    // 检查监听端口是否可用
+    port_check::preflight(registry, tunnel_id, &definition.config).await?;
+
+    // 优先使用隧道固定的版本，其次是当前版本
+    let versions = &app_handle.state::<AppState>().versions;
+    let nodepass_path = match versions.resolve(definition.nodepass_version.as_deref())? {
+        Some(path) => path.to_string_lossy().to_string(),
+        None => find_nodepass_executable_with_handle(app_handle).ok_or_else(|| {
+            format!(
+                "未找到NodePass可执行文件，请确保{}在PATH中或当前目录下",
+                platform::executable_name()
+            )
+        })?,
+    };
+
+    Ok((definition, nodepass_path))
+}
+
+// 启动NodePass进程，挂接日志读取任务并登记到注册表
+fn spawn_nodepass_process(
+    app_handle: &AppHandle,
+    registry: &TunnelRegistry,
+    nodepass_path: &str,
+    config: &NodePassConfig,
+    tunnel_id: String,
+) -> Result<SpawnedProcess, String> {
+    let logs = registry
+        .logs(&tunnel_id)
+        .ok_or_else(|| format!("隧道 {} 不存在", tunnel_id))?;
+
+    let command_args = build_nodepass_command(config)?;
+
+    println!("启动NodePass: {} {}", nodepass_path, command_args.join(" "));
+
+    // 输出写入文件而不是管道：GUI 退出后隧道进程仍能正常写日志，重新接管时从文件继续读取
+    let capture_dir = app_handle.state::<AppState>().capture_dir.clone();
+    let (stdout_file, stderr_file) = runtime_state::capture_paths(&capture_dir, &tunnel_id);
+    let stdout = runtime_state::create_capture_file(&stdout_file)?;
+    let stderr = runtime_state::create_capture_file(&stderr_file)?;
+
+    let mut cmd = TokioCommand::new(nodepass_path);
+    cmd.args(&command_args);
+    cmd.stdout(stdout);
+    cmd.stderr(stderr);
+
+    // 在Windows上隐藏终端窗口，并放入独立进程组以便发送CTRL_BREAK
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000 | 0x00000200); // CREATE_NO_WINDOW | CREATE_NEW_PROCESS_GROUP
+    }
+
+    // 其它平台同样放入独立进程组，避免随GUI一起收到终端信号
+    #[cfg(unix)]
+    cmd.process_group(0);
+
+    let child = cmd.spawn().map_err(|e| format!("启动进程失败: {}", e))?;
+    let child_id = child.id().unwrap_or(0);
+
+    let record = RuntimeRecord {
+        tunnel_id: tunnel_id.clone(),
+        pid: child_id,
+        executable: nodepass_path.to_string(),
+        args: command_args,
+        started_at: restart::now_millis(),
+        stdout_file,
+        stderr_file,
+    };
+    spawn_log_tailers(app_handle, &record, logs, false);
+
+    // 更新隧道运行时状态
+    let (stop_tx, stop_rx) = mpsc::unbounded_channel();
+    registry.mark_running(&tunnel_id, record, stop_tx);
+
+    Ok((TunnelProcess::Spawned(child), child_id, stop_rx))
+}
+
+type SpawnedProcess = (TunnelProcess, u32, mpsc::UnboundedReceiver<StopRequest>);
+
+// 为隧道的 stdout/stderr 输出文件各启动一个读取任务
+fn spawn_log_tailers(
+    app_handle: &AppHandle,
+    record: &RuntimeRecord,
+    logs: Arc<std::sync::Mutex<Vec<String>>>,
+    replay_history: bool,
+) {
+    for (path, from_stderr) in [
+        (record.stdout_file.clone(), false),
+        (record.stderr_file.clone(), true),
+    ] {
+        tokio::spawn(tail_capture_file(
+            app_handle.clone(),
+            record.tunnel_id.clone(),
+            record.pid,
+            logs.clone(),
+            path,
+            from_stderr,
+            replay_history,
+        ));
+    }
+}
+
+// 持续读取输出文件中的新行，直到进程退出且文件读完。
+// replay_history 为 true 时（重新接管的进程），已有内容只恢复到日志缓存中
+async fn tail_capture_file(
+    app_handle: AppHandle,
+    tunnel_id: String,
+    pid: u32,
+    logs: Arc<std::sync::Mutex<Vec<String>>>,
+    path: PathBuf,
+    from_stderr: bool,
+    replay_history: bool,
+) {
+    let liveness = app_handle.state::<AppState>().liveness.clone();
+    let file = match tokio::fs::File::open(&path).await {
+        Ok(file) => file,
+        Err(e) => {
+            println!("打开日志文件 {:?} 失败: {}", path, e);
+            return;
+        }
+    };
+
+    let mut reader = BufReader::new(file);
+    let mut pending = Vec::new();
+    let mut replaying = replay_history;
+    let mut finished = false;
+    // 已读取的字节数，超过上限后清空文件
+    let mut consumed: u64 = 0;
+
+    loop {
+        let result = reader.read_until(b'\n', &mut pending).await;
+        if let Ok(n) = result {
+            consumed += n as u64;
+        }
+        match result {
+            Ok(n) if n > 0 && pending.ends_with(b"\n") => {
+                let line = String::from_utf8_lossy(&pending).trim_end().to_string();
+                pending.clear();
+
+                if replaying {
+                    let record = log_parser::parse_line(&line, from_stderr);
+                    registry::push_log(&logs, record.display_line());
+                } else {
+                    forward_log_line(&app_handle, &tunnel_id, pid, &logs, from_stderr, &line);
+                }
+            }
+            // 已读到文件末尾（可能留有未写完的半行）
+            Ok(_) => {
+                replaying = false;
+                if finished {
+                    if !pending.is_empty() {
+                        let line = String::from_utf8_lossy(&pending).trim_end().to_string();
+                        forward_log_line(&app_handle, &tunnel_id, pid, &logs, from_stderr, &line);
+                    }
+                    break;
+                }
+                // 进程退出后再读一轮，确保最后写入的内容不丢失
+                if !liveness.is_alive(pid) {
+                    finished = true;
+                    continue;
+                }
+                // 读完一整行后清空文件，进程以追加方式写入，之后从文件开头继续写
+                if pending.is_empty() && consumed >= runtime_state::CAPTURE_TRUNCATE_BYTES {
+                    // 失败时同样重新计数，避免每次轮询都重试
+                    consumed = 0;
+                    match runtime_state::truncate_capture_file(&path) {
+                        Ok(()) => {
+                            if let Err(e) = reader.seek(std::io::SeekFrom::Start(0)).await {
+                                println!("重置日志文件 {:?} 读取位置失败: {}", path, e);
+                            }
+                        }
+                        Err(e) => println!("清空日志文件 {:?} 失败: {}", path, e),
+                    }
+                }
+                sleep(LOG_TAIL_INTERVAL).await;
+            }
+            Err(e) => {
+                println!("读取日志文件 {:?} 失败: {}", path, e);
+                break;
+            }
+        }
+    }
+}
+
+// 处理一行隧道输出：写入缓存并推送到前端，再按致命错误规则检查
+fn forward_log_line(
+    app_handle: &AppHandle,
+    tunnel_id: &str,
+    pid: u32,
+    logs: &Arc<std::sync::Mutex<Vec<String>>>,
+    from_stderr: bool,
+    raw_line: &str,
+) {
+    let state = app_handle.state::<AppState>();
+    let record = log_parser::parse_line(raw_line, from_stderr);
+    let log_message = record.display_line();
+    let line = log_parser::strip_ansi(raw_line);
+
+    // 写入持久化日志文件
+    state.tunnel_logs.append(tunnel_id, &log_message);
+
+    // 存储到日志
+    registry::push_log(logs, log_message.clone());
+
+    // 发送日志到前端
+    let _ = app_handle.emit(
+        "tunnel-log",
+        serde_json::json!({
+            "tunnel_id": tunnel_id,
+            "message": log_message,
+            "record": record
+        }),
+    );
+
+    for hit in state.fatal_rules.evaluate(tunnel_id, &record, &line) {
+        handle_rule_hit(app_handle, tunnel_id, pid, hit, &line);
+    }
+}
+
+// 执行触发的致命错误规则；日志读取不会因此中断
+fn handle_rule_hit(app_handle: &AppHandle, tunnel_id: &str, pid: u32, hit: RuleHit, line: &str) {
+    println!(
+        "隧道 {} 触发规则 {} ({} 次)，动作: {:?}",
+        tunnel_id, hit.rule_id, hit.occurrences, hit.action
+    );
+
+    let _ = app_handle.emit(
+        "app-log",
+        serde_json::json!({
+            "level": hit.severity,
+            "message": format!("隧道 {} 触发规则「{}」: {}", tunnel_id, hit.rule_name, line),
+            "source": "LogMonitor"
+        }),
+    );
+
+    let _ = app_handle.emit(
+        "fatal-rule-triggered",
+        serde_json::json!({
+            "tunnel_id": tunnel_id,
+            "pid": pid,
+            "rule_id": hit.rule_id,
+            "rule_name": hit.rule_name,
+            "action": hit.action,
+            "severity": hit.severity,
+            "occurrences": hit.occurrences,
+            "line": line
+        }),
+    );
+
+    match hit.action {
+        // 由后端直接停止隧道，不依赖前端页面是否打开
+        RuleAction::Stop => {
+            let app_handle = app_handle.clone();
+            let tunnel_id = tunnel_id.to_string();
+            let error = format!("检测到致命错误: {}", line);
+            tokio::spawn(async move {
+                let registry = app_handle.state::<AppState>().registry.clone();
+                if let Err(e) = stop_registered_tunnel(&registry, &tunnel_id, None).await {
+                    println!("停止隧道 {} 失败: {}", tunnel_id, e);
+                    return;
+                }
+                registry.set_error(&tunnel_id, error.clone());
+                let _ = app_handle.emit(
+                    "fatal-error-detected",
+                    serde_json::json!({
+                        "tunnel_id": tunnel_id,
+                        "pid": pid,
+                        "error": error
+                    }),
+                );
+                let _ = app_handle.emit(
+                    "tunnel-status-changed",
+                    serde_json::json!({
+                        "tunnel_id": tunnel_id,
+                        "status": "error",
+                        "pid": null,
+                        "error": error
+                    }),
+                );
+            });
+        }
+        // 交给隧道的监控任务重启，避免与自动重启同时拉起两个进程
+        RuleAction::Restart => {
+            let app_handle = app_handle.clone();
+            let tunnel_id = tunnel_id.to_string();
+            tokio::spawn(async move {
+                let registry = app_handle.state::<AppState>().registry.clone();
+                if let Err(e) = restart_registered_tunnel(&app_handle, &registry, &tunnel_id).await {
+                    let _ = app_handle.emit(
+                        "app-log",
+                        serde_json::json!({
+                            "level": "error",
+                            "message": format!("隧道 {} 按规则重启失败: {}", tunnel_id, e),
+                            "source": "LogMonitor"
+                        }),
+                    );
+                }
+            });
+        }
+        RuleAction::Notify => {}
+    }
+}
+
+// 等待隧道进程退出，并根据重启策略决定是否重新拉起
+async fn supervise_tunnel(
+    app_handle: AppHandle,
+    registry: TunnelRegistry,
+    mut nodepass_path: String,
+    tunnel_id: String,
+    process: TunnelProcess,
+    child_id: u32,
+    stop_rx: mpsc::UnboundedReceiver<StopRequest>,
+) {
+    let liveness = app_handle.state::<AppState>().liveness.clone();
+    // 隧道被重新手动启动后代数会变化，本任务不再拉起进程
+    let generation = registry
+        .with_restart(&tunnel_id, |restart_state| restart_state.generation)
+        .unwrap_or_default();
+    let mut current = Some((process, child_id, stop_rx));
+
+    loop {
+        let started_at = std::time::Instant::now();
+
+        // 进程启动失败视为一次失败退出
+        let (failed, stop) = match current.take() {
+            Some((mut process, child_id, mut stop_rx)) => {
+                // 等待进程自行退出，或收到停止请求后优雅停止
+                let (status, stop) = tokio::select! {
+                    status = process.wait(liveness.as_ref()) => {
+                        // 进程退出的同时收到的停止请求也在这里答复
+                        let stop = stop_rx.try_recv().ok().map(|request| (request, StopMethod::Graceful));
+                        (status, stop)
+                    }
+                    Some(request) = stop_rx.recv() => {
+                        registry.set_status(&tunnel_id, TunnelStatus::Stopping);
+                        let (status, method) =
+                            process.stop(liveness.as_ref(), request.grace_period).await;
+                        (status, Some((request, method)))
+                    }
+                };
+                let stop_method = stop.as_ref().map(|(_, method)| method.as_str());
+
+                println!("隧道进程 {} (PID: {}) 已退出", tunnel_id, child_id);
+
+                let failed = match status {
+                    // 重新接管的进程拿不到退出码，按非正常退出处理
+                    Ok(None) => {
+                        println!("隧道 {} 已退出，退出码未知", tunnel_id);
+
+                        registry.mark_exited(&tunnel_id, None, None);
+
+                        let _ = app_handle.emit(
+                            "app-log",
+                            serde_json::json!({
+                                "level": "info",
+                                "message": format!("隧道 {} 进程已退出", tunnel_id),
+                                "source": "ProcessMonitor"
+                            }),
+                        );
+
+                        let _ = app_handle.emit(
+                            "tunnel-status-changed",
+                            serde_json::json!({
+                                "tunnel_id": tunnel_id,
+                                "status": "stopped",
+                                "pid": null,
+                                "exit_code": null,
+                                "stop_method": stop_method
+                            }),
+                        );
+
+                        true
+                    }
+                    Ok(Some(exit_status)) => {
+                        let exit_code = exit_status.code().unwrap_or(-1);
+                        println!("隧道 {} 正常退出，退出码: {}", tunnel_id, exit_code);
+
+                        registry.mark_exited(&tunnel_id, Some(exit_code), None);
+
+                        // 发送应用日志
+                        let _ = app_handle.emit(
+                            "app-log",
+                            serde_json::json!({
+                                "level": "info",
+                                "message": format!("隧道 {} 进程已退出，退出码: {}", tunnel_id, exit_code),
+                                "source": "ProcessMonitor"
+                            }),
+                        );
+
+                        // 发送隧道状态变化事件
+                        let _ = app_handle.emit(
+                            "tunnel-status-changed",
+                            serde_json::json!({
+                                "tunnel_id": tunnel_id,
+                                "status": "stopped",
+                                "pid": null,
+                                "exit_code": exit_code,
+                                "stop_method": stop_method
+                            }),
+                        );
+
+                        !exit_status.success()
+                    }
+                    Err(e) => {
+                        println!("隧道 {} 异常退出: {}", tunnel_id, e);
+
+                        registry.mark_exited(&tunnel_id, None, Some(e.to_string()));
+
+                        // 发送应用日志
+                        let _ = app_handle.emit(
+                            "app-log",
+                            serde_json::json!({
+                                "level": "error",
+                                "message": format!("隧道 {} 进程异常退出: {}", tunnel_id, e),
+                                "source": "ProcessMonitor"
+                            }),
+                        );
+
+                        // 发送隧道状态变化事件
+                        let _ = app_handle.emit(
+                            "tunnel-status-changed",
+                            serde_json::json!({
+                                "tunnel_id": tunnel_id,
+                                "status": "error",
+                                "pid": null,
+                                "error": e.to_string(),
+                                "stop_method": stop_method
+                            }),
+                        );
+
+                        true
+                    }
+                };
+                (failed, stop)
+            }
+            None => (true, None),
+        };
+
+        // 停止请求在注册表更新为已退出之后才答复；普通停止到此结束监控，
+        // 重启请求由本任务立即拉起新进程，不会与其它启动流程产生两个进程
+        if let Some((request, method)) = stop {
+            if !request.restart {
+                let _ = request.done.send(StopOutcome {
+                    method,
+                    restarted: None,
+                });
+                break;
+            }
+
+            let restarted = relaunch_tunnel(&app_handle, &registry, &tunnel_id).await;
+            let result = match restarted {
+                Ok((path, (process, child_id, stop_rx))) => {
+                    nodepass_path = path;
+                    current = Some((process, child_id, stop_rx));
+                    Ok(child_id)
+                }
+                Err(e) => Err(e),
+            };
+            let relaunched = result.is_ok();
+            let _ = request.done.send(StopOutcome {
+                method,
+                restarted: Some(result),
+            });
+            if relaunched {
+                continue;
+            }
+            break;
+        }
+
+        // 隧道定义可能已被删除
+        let definition = match registry.definition(&tunnel_id) {
+            Some(definition) => definition,
+            None => break,
+        };
+        let policy = definition.restart_policy;
+
+        // 根据重启策略计算下一次重启的等待时间
+        let restart = registry
+            .with_restart(&tunnel_id, |restart_state| {
+                if restart_state.stop_requested
+                    || restart_state.generation != generation
+                    || !policy.should_restart(failed)
+                {
+                    return None;
+                }
+
+                // 稳定运行足够长时间后重新计数
+                if started_at.elapsed() >= Duration::from_secs(policy.reset_after_secs) {
+                    restart_state.attempts = 0;
+                }
+
+                if policy.retries_exhausted(restart_state.attempts) {
+                    Some(Err(restart_state.attempts))
+                } else {
+                    restart_state.attempts += 1;
+                    Some(Ok((
+                        restart_state.attempts,
+                        policy.backoff_delay(restart_state.attempts),
+                    )))
+                }
+            })
+            .flatten();
+
+        let (attempt, delay) = match restart {
+            Some(Ok(next)) => next,
+            Some(Err(attempts)) => {
+                println!("隧道 {} 已连续重启 {} 次，放弃自动重启", tunnel_id, attempts);
+                let _ = app_handle.emit(
+                    "app-log",
+                    serde_json::json!({
+                        "level": "error",
+                        "message": format!("隧道 {} 已连续重启 {} 次仍失败，停止自动重启", tunnel_id, attempts),
+                        "source": "RestartSupervisor"
+                    }),
+                );
+                break;
+            }
+            None => break,
+        };
+
+        let retries_label = if policy.max_retries > 0 {
+            format!("{}/{}", attempt, policy.max_retries)
+        } else {
+            attempt.to_string()
+        };
+
+        println!(
+            "隧道 {} 将在 {} ms 后重启 (第 {} 次)",
+            tunnel_id,
+            delay.as_millis(),
+            retries_label
+        );
+        registry.set_status(&tunnel_id, TunnelStatus::Restarting);
+        let _ = app_handle.emit(
+            "app-log",
+            serde_json::json!({
+                "level": "warn",
+                "message": format!("隧道 {} 将在 {:.1} 秒后自动重启 (第 {} 次)", tunnel_id, delay.as_secs_f64(), retries_label),
+                "source": "RestartSupervisor"
+            }),
+        );
+        let _ = app_handle.emit(
+            "tunnel-status-changed",
+            serde_json::json!({
+                "tunnel_id": tunnel_id,
+                "status": "restarting",
+                "pid": null,
+                "attempt": attempt,
+                "delay_ms": delay.as_millis() as u64
+            }),
+        );
+
+        sleep(delay).await;
+
+        // 等待期间用户可能已手动停止或重新启动隧道
+        let (stop_requested, superseded) = registry
+            .with_restart(&tunnel_id, |restart_state| {
+                (
+                    restart_state.stop_requested,
+                    restart_state.generation != generation,
+                )
+            })
+            .unwrap_or((true, false));
+        if superseded {
+            // 新进程由新的监控任务负责
+            break;
+        }
+        if stop_requested {
+            registry.set_status(&tunnel_id, TunnelStatus::Stopped);
+            let _ = app_handle.emit(
+                "tunnel-status-changed",
+                serde_json::json!({
+                    "tunnel_id": tunnel_id,
+                    "status": "stopped",
+                    "pid": null
+                }),
+            );
+            break;
+        }
+
+        match spawn_nodepass_process(
+            &app_handle,
+            &registry,
+            &nodepass_path,
+            &definition.config,
+            tunnel_id.clone(),
+        ) {
+            Ok((process, child_id, stop_rx)) => {
+                registry.with_restart(&tunnel_id, |restart_state| {
+                    restart_state.total_restarts += 1;
+                    restart_state.last_restart_at = Some(restart::now_millis());
+                });
+
+                let _ = app_handle.emit(
+                    "app-log",
+                    serde_json::json!({
+                        "level": "info",
+                        "message": format!("隧道 {} 已自动重启，新进程ID: {}", tunnel_id, child_id),
+                        "source": "RestartSupervisor"
+                    }),
+                );
+                let _ = app_handle.emit(
+                    "tunnel-status-changed",
+                    serde_json::json!({
+                        "tunnel_id": tunnel_id,
+                        "status": "running",
+                        "pid": child_id,
+                        "process_id": child_id
+                    }),
+                );
+
+                current = Some((process, child_id, stop_rx));
+            }
+            Err(e) => {
+                println!("隧道 {} 自动重启失败: {}", tunnel_id, e);
+                registry.mark_exited(&tunnel_id, None, Some(e.clone()));
+                let _ = app_handle.emit(
+                    "app-log",
+                    serde_json::json!({
+                        "level": "error",
+                        "message": format!("隧道 {} 自动重启失败: {}", tunnel_id, e),
+                        "source": "RestartSupervisor"
+                    }),
+                );
+            }
+        }
+    }
+}
+
+// 监控任务处理重启请求时重新启动隧道，使用最新的隧道配置和NodePass版本
+async fn relaunch_tunnel(
+    app_handle: &AppHandle,
+    registry: &TunnelRegistry,
+    tunnel_id: &str,
+) -> Result<(String, SpawnedProcess), String> {
+    let launched = match prepare_launch(app_handle, registry, tunnel_id).await {
+        Ok((definition, nodepass_path)) => spawn_nodepass_process(
+            app_handle,
+            registry,
+            &nodepass_path,
+            &definition.config,
+            tunnel_id.to_string(),
+        )
+        .map(|spawned| (nodepass_path, spawned))
+        .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+
+    match launched {
+        Ok((nodepass_path, spawned)) => {
+            let child_id = spawned.1;
+            registry.with_restart(tunnel_id, |restart_state| {
+                restart_state.attempts = 0;
+                restart_state.total_restarts += 1;
+                restart_state.last_restart_at = Some(restart::now_millis());
+            });
+            let _ = app_handle.emit(
+                "app-log",
+                serde_json::json!({
+                    "level": "info",
+                    "message": format!("隧道 {} 已重启，新进程ID: {}", tunnel_id, child_id),
+                    "source": "ProcessMonitor"
+                }),
+            );
+            let _ = app_handle.emit(
+                "tunnel-status-changed",
+                serde_json::json!({
+                    "tunnel_id": tunnel_id,
+                    "status": "running",
+                    "pid": child_id,
+                    "process_id": child_id
+                }),
+            );
+            Ok((nodepass_path, spawned))
+        }
+        Err(e) => {
+            println!("隧道 {} 重启失败: {}", tunnel_id, e);
+            registry.mark_exited(tunnel_id, None, Some(e.clone()));
+            let _ = app_handle.emit(
+                "tunnel-status-changed",
+                serde_json::json!({
+                    "tunnel_id": tunnel_id,
+                    "status": "error",
+                    "pid": null,
+                    "error": e
+                }),
+            );
+            Err(e)
+        }
+    }
+}
+
+// 启动时检查上次运行留下的隧道进程：仍在运行且命令行一致的重新接管，
+// 无法对应到隧道的记为遗留进程，由用户决定是否结束
+async fn adopt_surviving_tunnels(app_handle: AppHandle) {
+    let state = app_handle.state::<AppState>();
+    let registry = state.registry.clone();
+    let liveness = state.liveness.clone();
+
+    let mut adopted = 0;
+    let mut orphans = Vec::new();
+    for record in registry.previous_runtime() {
+        if !liveness.is_alive(record.pid) {
+            println!("隧道 {} 的进程 {} 已退出", record.tunnel_id, record.pid);
+            continue;
+        }
+
+        // PID 可能已被其他程序复用
+        match runtime_state::query_command_line(record.pid).await {
+            Some(command_line) if runtime_state::command_line_matches(&command_line, &record) => {}
+            _ => {
+                println!(
+                    "进程 {} 的命令行与记录不一致，忽略: {}",
+                    record.pid,
+                    record.command_line()
+                );
+                continue;
+            }
+        }
+
+        let can_adopt = registry.definition(&record.tunnel_id).is_some()
+            && registry.pid_of(&record.tunnel_id).is_none();
+        if can_adopt {
+            adopt_tunnel(&app_handle, &registry, record);
+            adopted += 1;
+        } else {
+            orphans.push(record);
+        }
+    }
+
+    if adopted > 0 {
+        let _ = app_handle.emit(
+            "app-log",
+            serde_json::json!({
+                "level": "info",
+                "message": format!("已重新接管 {} 个仍在运行的隧道进程", adopted),
+                "source": "ProcessMonitor"
+            }),
+        );
+    }
+
+    if !orphans.is_empty() {
+        println!("发现 {} 个无法对应到隧道的遗留进程", orphans.len());
+        let _ = app_handle.emit(
+            "orphan-processes-detected",
+            serde_json::json!({
+                "processes": orphans
+            }),
+        );
+    }
+    registry.set_orphans(orphans);
+}
+
+// 接管已在运行的隧道进程，恢复日志读取与状态监控
+fn adopt_tunnel(app_handle: &AppHandle, registry: &TunnelRegistry, record: RuntimeRecord) {
+    let logs = match registry.logs(&record.tunnel_id) {
+        Some(logs) => logs,
+        None => return,
+    };
+    let tunnel_id = record.tunnel_id.clone();
+    let pid = record.pid;
+    let nodepass_path = record.executable.clone();
+
+    println!("重新接管隧道 {} (PID: {})", tunnel_id, pid);
+
+    spawn_log_tailers(app_handle, &record, logs, true);
+
+    let (stop_tx, stop_rx) = mpsc::unbounded_channel();
+    registry.mark_running(&tunnel_id, record, stop_tx);
+
+    tokio::spawn(supervise_tunnel(
+        app_handle.clone(),
+        registry.clone(),
+        nodepass_path,
+        tunnel_id.clone(),
+        TunnelProcess::Adopted(pid),
+        pid,
+        stop_rx,
+    ));
+
+    let _ = app_handle.emit(
+        "tunnel-status-changed",
+        serde_json::json!({
+            "tunnel_id": tunnel_id,
+            "status": "running",
+            "pid": pid,
+            "process_id": pid,
+            "adopted": true
+        }),
+    );
+}
+
+#[tauri::command]
+async fn get_orphan_processes(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<RuntimeRecord>, String> {
+    Ok(state.registry.orphans())
+}
+
+#[tauri::command]
+async fn kill_orphan_process(
+    state: tauri::State<'_, AppState>,
+    process_id: u32,
+) -> Result<Option<StopMethod>, String> {
+    let record = state
+        .registry
+        .remove_orphan(process_id)
+        .ok_or_else(|| format!("进程 {} 不是遗留的隧道进程", process_id))?;
+
+    // 结束前再次确认PID仍属于该隧道进程
+    let still_ours = match runtime_state::query_command_line(process_id).await {
+        Some(command_line) => runtime_state::command_line_matches(&command_line, &record),
+        None => false,
+    };
+    if !still_ours {
+        println!("遗留进程 {} 已退出", process_id);
+        return Ok(None);
+    }
+
+    let method = process_control::stop_pid(
+        state.liveness.as_ref(),
+        process_id,
+        Duration::from_millis(process_control::DEFAULT_STOP_TIMEOUT_MS),
+    )
+    .await;
+    println!("已结束遗留进程 {} ({})", process_id, method.as_str());
+
+    Ok(Some(method))
+}
+
+#[tauri::command]
+async fn get_restart_stats(
+    state: tauri::State<'_, AppState>,
+    tunnel_id: String,
+) -> Result<RestartStats, String> {
+    state
+        .registry
+        .get(&tunnel_id)
+        .map(|snapshot| snapshot.restarts)
+        .ok_or_else(|| format!("隧道 {} 不存在", tunnel_id))
+}
+
+// 加载隧道定义时遇到的问题，例如文件损坏
+#[tauri::command]
+async fn get_tunnel_store_error(state: tauri::State<'_, AppState>) -> Result<Option<String>, String> {
+    Ok(state.registry.load_error())
+}
+
+#[tauri::command]
+async fn list_tunnels(state: tauri::State<'_, AppState>) -> Result<Vec<TunnelSnapshot>, String> {
+    Ok(state.registry.list())
+}
+
+#[tauri::command]
+async fn get_tunnel(
+    state: tauri::State<'_, AppState>,
+    tunnel_id: String,
+) -> Result<TunnelSnapshot, String> {
+    state
+        .registry
+        .get(&tunnel_id)
+        .ok_or_else(|| format!("隧道 {} 不存在", tunnel_id))
+}
+
+#[tauri::command]
+async fn save_tunnel(
+    state: tauri::State<'_, AppState>,
+    tunnel: TunnelDefinition,
+) -> Result<TunnelSnapshot, String> {
+    state.registry.upsert(tunnel)
+}
+
+#[tauri::command]
+async fn delete_tunnel(
+    state: tauri::State<'_, AppState>,
+    tunnel_id: String,
+) -> Result<(), String> {
+    state.registry.remove(&tunnel_id)
+}
+
+#[tauri::command]
+async fn start_tunnel(
+    app_handle: AppHandle,
+    state: tauri::State<'_, AppState>,
+    tunnel_id: String,
+) -> Result<u32, StartError> {
+    let child_id = start_registered_tunnel(&app_handle, &state.registry, &tunnel_id).await?;
+
+    // 更新托盘tooltip
+    let _ = update_tray_tooltip(app_handle.clone(), state.clone()).await;
+
+    Ok(child_id)
+}
+
+#[tauri::command]
+async fn stop_tunnel(
+    app_handle: AppHandle,
+    state: tauri::State<'_, AppState>,
+    tunnel_id: String,
+    grace_period_ms: Option<u64>,
+) -> Result<Option<StopMethod>, String> {
+    if state.registry.definition(&tunnel_id).is_none() {
+        return Err(format!("隧道 {} 不存在", tunnel_id));
+    }
+
+    let stop_method = stop_registered_tunnel(
+        &state.registry,
+        &tunnel_id,
+        grace_period_ms.map(Duration::from_millis),
+    )
+    .await?;
+
+    // 更新托盘tooltip
+    let _ = update_tray_tooltip(app_handle, state).await;
+
+    Ok(stop_method)
+}
+
+// 停止注册表中的隧道，并阻止其被自动重启；未指定宽限期时使用隧道配置的值
+async fn stop_registered_tunnel(
+    registry: &TunnelRegistry,
+    tunnel_id: &str,
+    grace_period: Option<Duration>,
+) -> Result<Option<StopMethod>, String> {
+    // 标记为用户主动停止，避免被自动重启
+    registry.with_restart(tunnel_id, |restart_state| {
+        restart_state.stop_requested = true;
+    });
+
+    let stop_tx = match registry.stop_handle(tunnel_id) {
+        Some(stop_tx) => stop_tx,
+        None => return Ok(None),
+    };
+
+    let (done_tx, done_rx) = tokio::sync::oneshot::channel();
+    stop_tx
+        .send(StopRequest {
+            grace_period: grace_period
+                .unwrap_or_else(|| stop_grace_period(registry, tunnel_id)),
+            restart: false,
+            done: done_tx,
+        })
+        .map_err(|_| format!("隧道 {} 的监控任务已结束", tunnel_id))?;
+
+    done_rx
+        .await
+        .map(|outcome| Some(outcome.method))
+        .map_err(|_| format!("等待隧道 {} 停止失败", tunnel_id))
+}
+
+// 重启注册表中的隧道。运行中的隧道交给其监控任务停止并立即重新拉起，
+// 不会与监控任务的自动重启竞争；没有运行中的进程时直接启动
+async fn restart_registered_tunnel(
+    app_handle: &AppHandle,
+    registry: &TunnelRegistry,
+    tunnel_id: &str,
+) -> Result<u32, String> {
+    let stop_tx = match registry.stop_handle(tunnel_id) {
+        Some(stop_tx) => stop_tx,
+        None => {
+            return start_registered_tunnel(app_handle, registry, tunnel_id)
+                .await
+                .map_err(|e| e.to_string())
+        }
+    };
+
+    let (done_tx, done_rx) = tokio::sync::oneshot::channel();
+    stop_tx
+        .send(StopRequest {
+            grace_period: stop_grace_period(registry, tunnel_id),
+            restart: true,
+            done: done_tx,
+        })
+        .map_err(|_| format!("隧道 {} 的监控任务已结束", tunnel_id))?;
+
+    done_rx
+        .await
+        .map_err(|_| format!("等待隧道 {} 重启失败", tunnel_id))?
+        .restarted
+        .unwrap_or_else(|| Err(format!("隧道 {} 未能重启", tunnel_id)))
+}
+
+// 隧道配置的优雅退出等待时间
+fn stop_grace_period(registry: &TunnelRegistry, tunnel_id: &str) -> Duration {
+    Duration::from_millis(
+        registry
+            .definition(tunnel_id)
+            .map(|d| d.stop_timeout_ms)
+            .unwrap_or(process_control::DEFAULT_STOP_TIMEOUT_MS),
+    )
+}
+
+#[tauri::command]
+async fn stop_nodepass_by_pid(
+    app_handle: AppHandle,
+    state: tauri::State<'_, AppState>,
+    process_id: u32,
+) -> Result<(), String> {
+    match state.registry.find_by_pid(process_id) {
+        Some(tunnel_id) => {
+            stop_registered_tunnel(&state.registry, &tunnel_id, None).await?;
+        }
+        // 前端可能持有已不在注册表中的旧PID
+        None if state.liveness.is_alive(process_id) => {
+            let method = process_control::stop_pid(
+                state.liveness.as_ref(),
+                process_id,
+                Duration::from_millis(process_control::DEFAULT_STOP_TIMEOUT_MS),
+            )
+            .await;
+            println!("已停止未登记的进程 {} ({})", process_id, method.as_str());
+        }
+        None => {
+            println!("进程 {} 已不存在", process_id);
+        }
+    }
+
+    // 更新托盘tooltip
+    let _ = update_tray_tooltip(app_handle, state).await;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn stop_all_nodepass(
+    app_handle: AppHandle,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    // 包括正在等待自动重启的隧道
+    let tunnel_ids: Vec<String> = state
+        .registry
+        .list()
+        .into_iter()
+        .filter(|t| t.runtime.pid.is_some() || t.runtime.status == TunnelStatus::Restarting)
+        .map(|t| t.definition.id)
+        .collect();
+
+    if !tunnel_ids.is_empty() {
+        let process_count = tunnel_ids.len();
+        println!("正在停止所有 {} 个隧道进程...", process_count);
+        
+        // 发送日志事件到前端
+        let _ = app_handle.emit(
+            "app-log",
+            serde_json::json!({
+                "level": "info",
+                "message": format!("正在停止所有 {} 个隧道进程...", process_count),
+                "source": "StopAllHandler"
+            }),
+        );
+
+        stop_tunnels_concurrently(&state.registry, tunnel_ids).await;
+
+        // 更新托盘tooltip
+        let _ = update_tray_tooltip(app_handle.clone(), state.clone()).await;
+
+        // 发送所有隧道已停止的事件
+        let _ = app_handle.emit(
+            "all-tunnels-stopped",
+            serde_json::json!({
+                "message": "所有隧道已停止",
+                "stopped_count": process_count
+            }),
+        );
+
+        println!("已停止所有 {} 个隧道进程", process_count);
+    } else {
+        println!("没有运行中的隧道进程需要停止");
+        
+        // 发送日志事件到前端
+        let _ = app_handle.emit(
+            "app-log",
+            serde_json::json!({
+                "level": "info",
+                "message": "没有运行中的隧道进程需要停止",
+                "source": "StopAllHandler"
+            }),
+        );
+    }
+
+    Ok(())
+}
+
+// 并行停止多个隧道，等待全部完成
+async fn stop_tunnels_concurrently(registry: &TunnelRegistry, tunnel_ids: Vec<String>) {
+    let mut tasks = tokio::task::JoinSet::new();
+    for tunnel_id in tunnel_ids {
+        let registry = registry.clone();
+        tasks.spawn(async move {
+            let result = stop_registered_tunnel(&registry, &tunnel_id, None).await;
+            (tunnel_id, result)
+        });
+    }
+
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((tunnel_id, Ok(Some(method)))) => {
+                println!("隧道 {} 已停止 ({})", tunnel_id, method.as_str());
+            }
+            Ok((tunnel_id, Ok(None))) => {
+                println!("隧道 {} 未在运行", tunnel_id);
+            }
+            Ok((tunnel_id, Err(e))) => {
+                println!("停止隧道 {} 失败: {}", tunnel_id, e);
+            }
+            Err(e) => {
+                println!("停止隧道任务异常: {}", e);
+            }
+        }
+    }
+}
+
+#[tauri::command]
+async fn get_tunnel_logs(
+    state: tauri::State<'_, AppState>,
+    process_id: Option<u32>,
+    tunnel_id: Option<String>,
+) -> Result<Vec<String>, String> {
+    let tunnel_id = tunnel_id.or_else(|| process_id.and_then(|pid| state.registry.find_by_pid(pid)));
+
+    if let Some(logs) = tunnel_id.and_then(|id| state.registry.logs(&id)) {
+        if let Ok(logs) = logs.lock() {
+            return Ok(logs.clone());
+        }
+    }
+    Ok(Vec::new())
+}
+
+#[tauri::command]
+async fn get_tunnel_log_history(
+    state: tauri::State<'_, AppState>,
+    tunnel_id: String,
+    since: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<LogHistoryEntry>, String> {
+    state
+        .tunnel_logs
+        .history(&tunnel_id, since.as_deref(), limit)
+}
+
+#[tauri::command]
+async fn get_log_settings(state: tauri::State<'_, AppState>) -> Result<LogSettings, String> {
+    Ok(state.tunnel_logs.settings())
+}
+
+#[tauri::command]
+async fn update_log_settings(
+    state: tauri::State<'_, AppState>,
+    settings: LogSettings,
+) -> Result<(), String> {
+    state.tunnel_logs.update_settings(settings)
+}
+
+#[tauri::command]
+async fn get_fatal_rules(state: tauri::State<'_, AppState>) -> Result<Vec<FatalRule>, String> {
+    Ok(state.fatal_rules.rules())
+}
+
+#[tauri::command]
+async fn update_fatal_rules(
+    state: tauri::State<'_, AppState>,
+    rules: Vec<FatalRule>,
+) -> Result<(), String> {
+    state.fatal_rules.update(rules)
+}
+
+// 用样例日志测试规则；未传入规则时使用当前保存的规则
+#[tauri::command]
+async fn test_fatal_rules(
+    state: tauri::State<'_, AppState>,
+    lines: Vec<String>,
+    rules: Option<Vec<FatalRule>>,
+) -> Result<Vec<RuleTestResult>, String> {
+    fatal_rules::test_rules(rules.unwrap_or_else(|| state.fatal_rules.rules()), lines)
+}
+
+// 检查隧道配置，返回字段级错误列表，为空表示配置有效
+#[tauri::command]
+async fn validate_config(config: NodePassConfig) -> Result<Vec<FieldError>, String> {
+    Ok(config_validation::validate_config(&config))
+}
+
+// 把分享的 NodePass 地址解析为隧道配置
+#[tauri::command]
+async fn parse_nodepass_url(url: String) -> Result<NodePassConfig, String> {
+    NodePassConfig::from_url(&url)
+}
+
+// 应用配置，尚未保存过时返回 null
+#[tauri::command]
+async fn get_app_config(
+    state: tauri::State<'_, AppState>,
+) -> Result<Option<serde_json::Value>, String> {
+    Ok(state.app_config.get())
+}
+
+#[tauri::command]
+async fn update_app_config(
+    state: tauri::State<'_, AppState>,
+    config: serde_json::Value,
+) -> Result<(), String> {
+    state.app_config.update(config)?;
+    import_app_config_tunnels(&state);
+    Ok(())
+}
+
+// 一次性导入前端 localStorage 中保存的旧配置
+#[tauri::command]
+async fn import_legacy_app_config(
+    state: tauri::State<'_, AppState>,
+    content: String,
+) -> Result<serde_json::Value, String> {
+    let config = state.app_config.import_legacy(&content)?;
+    import_app_config_tunnels(&state);
+    Ok(state.app_config.get().unwrap_or(config))
+}
+
+#[tauri::command]
+async fn check_nodepass_status(
+    state: tauri::State<'_, AppState>,
+) -> Result<NodePassStatus, String> {
+    let nodepass_path = state
+        .versions
+        .active_executable()
+        .map(|path| path.to_string_lossy().to_string())
+        .or_else(find_nodepass_executable);
+
+    let path = match nodepass_path {
+        Some(path) => path,
+        None => {
+            return Ok(NodePassStatus {
+                installed: false,
+                version: None,
+                os: None,
+                arch: None,
+                build: None,
+                path: None,
+                error: Some("未找到NodePass可执行文件".to_string()),
+            })
+        }
+    };
+
+    match state.version_probe.probe(Path::new(&path)).await {
+        Ok(build) => Ok(NodePassStatus {
+            installed: true,
+            version: build.version,
+            os: build.os,
+            arch: build.arch,
+            build: build.build,
+            path: Some(path),
+            error: None,
+        }),
+        Err(e) => Ok(NodePassStatus {
+            installed: false,
+            version: None,
+            os: None,
+            arch: None,
+            build: None,
+            path: Some(path),
+            error: Some(e),
+        }),
+    }
+}
+
+#[tauri::command]
+async fn get_latest_release(state: tauri::State<'_, AppState>) -> Result<GitHubRelease, String> {
+    let client = reqwest::Client::new();
+    let sources = state.release_sources.enabled();
+    match release_sources::fetch_latest(&client, &sources, &state.update_checker).await {
+        Ok(release) => Ok(release),
+        // 请求失败（例如触发频率限制）时使用缓存的发布信息
+        Err(e) => state.update_checker.cached_release().ok_or(e),
+    }
+}
+
+#[tauri::command]
+async fn get_release_sources(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<ReleaseSource>, String> {
+    Ok(state.release_sources.sources())
+}
+
+#[tauri::command]
+async fn update_release_sources(
+    state: tauri::State<'_, AppState>,
+    sources: Vec<ReleaseSource>,
+) -> Result<(), String> {
+    state.release_sources.update(sources)
+}
+
+#[tauri::command]
+async fn get_update_check_settings(
+    state: tauri::State<'_, AppState>,
+) -> Result<UpdateCheckSettings, String> {
+    Ok(state.update_checker.settings())
+}
+
+#[tauri::command]
+async fn update_update_check_settings(
+    state: tauri::State<'_, AppState>,
+    settings: UpdateCheckSettings,
+) -> Result<(), String> {
+    state.update_checker.update(settings)
+}
+
+// 立即检查一次更新，返回是否有新版本
+#[tauri::command]
+async fn check_for_updates(app_handle: AppHandle) -> Result<bool, String> {
+    check_for_update(&app_handle).await
+}
+
+// 后台定时检查更新，设置修改后在下一次检查时生效
+async fn update_check_loop(app_handle: AppHandle) {
+    sleep(UPDATE_CHECK_STARTUP_DELAY).await;
+
+    let mut last_check: Option<std::time::Instant> = None;
+    loop {
+        let settings = app_handle.state::<AppState>().update_checker.settings();
+        let interval = Duration::from_secs(settings.interval_hours * 3600);
+        let due = last_check.is_none_or(|last| last.elapsed() >= interval);
+
+        if settings.check_updates && due {
+            last_check = Some(std::time::Instant::now());
+            if let Err(e) = check_for_update(&app_handle).await {
+                println!("检查更新失败: {}", e);
+            }
+        }
+        sleep(UPDATE_CHECK_TICK).await;
+    }
+}
+
+async fn check_for_update(app_handle: &AppHandle) -> Result<bool, String> {
+    let state = app_handle.state::<AppState>();
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|e| format!("创建HTTP客户端失败: {}", e))?;
+    let sources = state.release_sources.enabled();
+    let release = release_sources::fetch_latest(&client, &sources, &state.update_checker).await?;
+
+    // 未安装时不提示更新
+    let nodepass_path = match state
+        .versions
+        .active_executable()
+        .map(|path| path.to_string_lossy().to_string())
+        .or_else(find_nodepass_executable)
+    {
+        Some(path) => path,
+        None => return Ok(false),
+    };
+    let installed = state
+        .version_probe
+        .probe(Path::new(&nodepass_path))
+        .await?
+        .version;
+    let installed = match installed {
+        Some(installed) => installed,
+        None => return Ok(false),
+    };
+
+    if !update_check::is_newer(&release.tag_name, &installed) {
+        return Ok(false);
+    }
+
+    println!("发现 NodePass 新版本: {} (当前 {})", release.tag_name, installed);
+    let _ = app_handle.emit(
+        "update-available",
+        serde_json::json!({
+            "current": installed,
+            "latest": release.tag_name,
+            "name": release.name,
+            "html_url": release.html_url,
+            "published_at": release.published_at
+        }),
+    );
+
+    // 同一版本只弹一次桌面通知
+    if state.update_checker.mark_notified(&release.tag_name) {
+        let _ = app_handle
+            .notification()
+            .builder()
+            .title("NodePass 有新版本")
+            .body(format!(
+                "{} 已发布，当前版本 {}",
+                release.tag_name, installed
+            ))
+            .show();
+    }
+
+    Ok(true)
+}
+
+// 发送下载进度事件，消息中的代理或下载地址认证信息会被去掉
+fn emit_download_progress(app_handle: &AppHandle, mut payload: serde_json::Value) {
+    if let Some(message) = payload.get_mut("message") {
+        if let Some(text) = message.as_str() {
+            *message = serde_json::Value::String(proxy::redact(text));
+        }
+    }
+    let _ = app_handle.emit("download-progress", payload);
+}
+
+// 通过指定的代理请求测试地址，返回延迟或失败原因
+#[tauri::command]
+async fn test_proxy(proxy_settings: ProxySettings) -> Result<proxy::ProxyTestResult, String> {
+    println!("测试代理: {}", proxy::describe(&proxy_settings));
+    let result = proxy::test(&proxy_settings).await;
+    match &result.error {
+        Some(e) => println!("代理测试失败: {}", e),
+        None => println!("代理测试完成: {:?} ms", result.latency_ms),
+    }
+    Ok(result)
+}
+
+#[tauri::command]
+async fn download_nodepass(
+    app_handle: AppHandle,
+    download_url: String,
+    filename: String,
+    proxy_settings: Option<ProxySettings>,
+    release: Option<GitHubRelease>,
+    rolling_restart: Option<bool>,
+    // 为 true 时获取不到校验文件就终止下载
+    require_checksum: Option<bool>,
+) -> Result<String, String> {
+    println!("开始下载: {} -> {}", proxy::redact(&download_url), filename);
+
+    // 重置取消和暂停标志
+    DOWNLOAD_CANCELLED.store(false, Ordering::Relaxed);
+    DOWNLOAD_PAUSED.store(false, Ordering::Relaxed);
+
+    // 获取系统临时目录
+    let temp_dir = std::env::temp_dir();
+    let target_path = temp_dir.join(&filename);
+    println!("临时下载路径: {:?}", target_path);
+
+    // 每个版本安装到独立目录，安装完成后切换为当前版本
+    let version_tag = match release
+        .as_ref()
+        .map(|release| release.tag_name.clone())
+        .or_else(|| checksum::release_tag_from_url(&download_url))
+    {
+        Some(tag) => tag,
+        None => {
+            let error_msg = format!("无法确定下载文件的版本: {}", proxy::redact(&download_url));
+            println!("错误: {}", error_msg);
+            emit_download_progress(&app_handle, serde_json::json!({
+                "status": "error",
+                "message": error_msg
+            }));
+            return Err(error_msg);
+        }
+    };
+    let versions = app_handle.state::<AppState>().versions.clone();
+    let install_dir = match versions.version_dir(&version_tag) {
+        Ok(dir) => dir,
+        Err(error_msg) => {
+            println!("错误: {}", error_msg);
+            emit_download_progress(&app_handle, serde_json::json!({
+                "status": "error",
+                "message": error_msg
+            }));
+            return Err(error_msg);
+        }
+    };
+    println!("安装目录: {:?}", install_dir);
+
+    // 发送开始下载事件
+    emit_download_progress(&app_handle, serde_json::json!({
+        "status": "started",
+        "message": "正在初始化下载..."
+    }));
+
+    // 创建HTTP客户端，支持用户配置的代理
+    let mut client_builder = reqwest::Client::builder()
+        // 只限制连接时间，下载中的停顿由 download 模块按数据块检测
+        .connect_timeout(std::time::Duration::from_secs(15))
+        .user_agent("NodePass-GUI/1.0");
+
+    // 处理代理设置
+    let mut proxy_info = String::new();
+    if let Some(proxy) = proxy_settings {
+        if proxy.enabled && !proxy.host.is_empty() && !proxy.port.is_empty() {
+            proxy_info = format!("使用{}", proxy::describe(&proxy));
+            println!("使用用户配置的代理: {}", proxy_info);
+
+            match proxy::build_proxy(&proxy) {
+                Ok(proxy_config) => {
+                    client_builder = client_builder.proxy(proxy_config);
+                    emit_download_progress(&app_handle, serde_json::json!({
+                        "status": "started",
+                        "message": format!("已配置代理: {}:{}", proxy.host, proxy.port)
+                    }));
+                }
+                Err(error_msg) => {
+                    println!("错误: {}", error_msg);
+                    emit_download_progress(&app_handle, serde_json::json!({
+                        "status": "error",
+                        "message": error_msg
+                    }));
+                    return Err(error_msg);
+                }
+            }
+        } else {
+            println!("代理已禁用或配置不完整，使用直连");
+            proxy_info = "直连".to_string();
+        }
+    } else {
+        // 如果没有提供代理设置，尝试检测系统代理
+        if let Some(system_proxy) = system_proxy::resolve(&download_url).await {
+            // 系统代理地址中可能带有认证信息
+            let system_proxy_url = proxy::redact(&system_proxy);
+            println!("检测到系统代理: {}", system_proxy_url);
+            proxy_info = format!("系统代理: {}", system_proxy_url);
+
+            match reqwest::Proxy::all(&system_proxy) {
+                Ok(proxy_config) => {
+                    client_builder = client_builder.proxy(proxy_config);
+                    emit_download_progress(&app_handle, serde_json::json!({
+                        "status": "started",
+                        "message": format!("使用系统代理: {}", system_proxy_url)
+                    }));
+                }
+                Err(e) => {
+                    println!("配置系统代理失败: {}, 使用直连", proxy::redact(&e.to_string()));
+                    proxy_info = "直连".to_string();
+                }
+            }
+        } else {
+            println!("未检测到代理设置，使用直连");
+            proxy_info = "直连".to_string();
+        }
+    }
+
+    let client = match client_builder.build() {
+        Ok(client) => {
+            println!("HTTP客户端创建成功");
+            client
+        }
+        Err(e) => {
+            let error_msg = format!("创建HTTP客户端失败: {}", e);
+            println!("错误: {}", error_msg);
+            emit_download_progress(&app_handle, serde_json::json!({
+                "status": "error",
+                "message": error_msg
+            }));
+            return Err(error_msg);
+        }
+    };
+
+    // 获取发布附带的校验文件中该文件的摘要
+    let require_checksum = require_checksum.unwrap_or(false);
+    let sources = app_handle.state::<AppState>().release_sources.enabled();
+    let checksum_result =
+        fetch_expected_checksum(&client, &sources, release.as_ref(), &download_url, &filename)
+            .await;
+    // 未要求校验时，没有校验文件或获取失败只给出警告
+    let skip_verification = |warning: String| {
+        println!("警告: {}", warning);
+        emit_download_progress(&app_handle, serde_json::json!({
+            "status": "started",
+            "message": warning
+        }));
+        None
+    };
+    let expected_sha256 = match checksum_result {
+        Ok(Some(digest)) => {
+            println!("期望的SHA-256: {}", digest);
+            Some(digest)
+        }
+        Ok(None) if !require_checksum => {
+            skip_verification("该版本未发布校验文件，将跳过完整性校验".to_string())
+        }
+        Err(e) if !require_checksum => {
+            skip_verification(format!("获取校验文件失败，将跳过完整性校验: {}", e))
+        }
+        Ok(None) => {
+            let error_msg = "该版本未发布校验文件，无法进行完整性校验".to_string();
+            println!("错误: {}", error_msg);
+            emit_download_progress(&app_handle, serde_json::json!({
+                "status": "error",
+                "message": error_msg
+            }));
+            return Err(error_msg);
+        }
+        Err(e) => {
+            let error_msg = format!("获取校验文件失败: {}", e);
+            println!("错误: {}", error_msg);
+            emit_download_progress(&app_handle, serde_json::json!({
+                "status": "error",
+                "message": error_msg
+            }));
+            return Err(error_msg);
+        }
+    };
+
+    // 发送连接测试事件
+    emit_download_progress(&app_handle, serde_json::json!({
+        "status": "started",
+        "message": format!("正在连接服务器... ({})", proxy_info)
+    }));
+
+    println!("开始下载文件内容...");
+    let mut last_progress = 0u64;
+    // 依次尝试当前发布源和其它发布源的下载地址
+    let candidates = download_candidates(
+        &app_handle.state::<AppState>().release_sources.enabled(),
+        release.as_ref(),
+        &version_tag,
+        &filename,
+        &download_url,
+    );
+    // 发布信息中的文件大小，用于判断未完成的下载能否续传
+    let expected_size = release
+        .as_ref()
+        .and_then(|release| release.assets.iter().find(|asset| asset.name == filename))
+        .map(|asset| asset.size);
+    let mut outcome = Err("没有可用的下载地址".to_string());
+    for (index, (source_label, url)) in candidates.iter().enumerate() {
+        if index > 0 {
+            emit_download_progress(&app_handle, serde_json::json!({
+                "status": "started",
+                "source": source_label,
+                "message": format!("切换到下载源: {}", source_label)
+            }));
+        }
+        println!("从下载源 {} 下载: {}", source_label, proxy::redact(url));
+
+        let result = download::download_resumable(
+                &client,
+                url,
+                &target_path,
+                expected_size,
+                download::DownloadControl {
+                    paused: &DOWNLOAD_PAUSED,
+                    cancelled: &DOWNLOAD_CANCELLED,
+                },
+                |event| match event {
+                    download::DownloadEvent::Started { resumed_from, total } => {
+                        let total_size = total.unwrap_or(0);
+                        println!("文件大小: {} bytes，从 {} 字节开始", total_size, resumed_from);
+                        last_progress = (resumed_from * 100).checked_div(total_size).unwrap_or(0);
+                        let message = if resumed_from > 0 {
+                            format!(
+                                "继续下载，已完成 {:.1} MB ({})",
+                                resumed_from as f64 / 1024.0 / 1024.0,
+                                proxy_info
+                            )
+                        } else {
+                            format!("开始下载... ({})", proxy_info)
+                        };
+                        emit_download_progress(&app_handle, serde_json::json!({
+                            "status": "downloading",
+                            "source": source_label,
+                            "progress": last_progress,
+                            "downloaded": resumed_from,
+                            "total": total_size,
+                            "resumed": resumed_from > 0,
+                            "message": message
+                        }));
+                    }
+                    download::DownloadEvent::Progress { downloaded, total } => {
+                        let total_size = total.unwrap_or(0);
+                        // 更频繁地发送进度更新，提供更好的用户体验
+                        if total_size > 0 {
+                            let progress = (downloaded * 100) / total_size;
+                            // 每1%或每512KB更新一次进度
+                            if progress != last_progress
+                                || downloaded - (last_progress * total_size / 100) >= 512 * 1024
+                            {
+                                last_progress = progress;
+                                println!("下载进度: {}% ({}/{})", progress, downloaded, total_size);
+
+                                let speed_info = format!("{:.1} MB", downloaded as f64 / 1024.0 / 1024.0);
+                                emit_download_progress(&app_handle, serde_json::json!({
+                                        "status": "downloading",
+                                    "source": source_label,
+                                        "progress": progress,
+                                        "downloaded": downloaded,
+                                        "total": total_size,
+                                        "message": format!("下载中... {}% ({}) - {}", progress, speed_info, proxy_info)
+                                }));
+                            }
+                        } else if downloaded % (1024 * 1024) == 0 {
+                            // 如果无法获取总大小，每1MB更新一次
+                            let mb_downloaded = downloaded as f64 / 1024.0 / 1024.0;
+                            println!("已下载: {:.1} MB", mb_downloaded);
+                            emit_download_progress(&app_handle, serde_json::json!({
+                                    "status": "downloading",
+                                "source": source_label,
+                                    "downloaded": downloaded,
+                                    "message": format!("下载中... {:.1} MB - {}", mb_downloaded, proxy_info)
+                            }));
+                        }
+                    }
+                    download::DownloadEvent::Retrying { attempt, delay, error } => {
+                        emit_download_progress(&app_handle, serde_json::json!({
+                            "status": "downloading",
+                            "source": source_label,
+                            "retrying": true,
+                            "attempt": attempt,
+                            "message": format!("下载中断: {}，{} 秒后重试 (第 {} 次)", error, delay.as_secs(), attempt)
+                        }));
+                    }
+                },
+            )
+            .await;
+
+        match result {
+            Err(e) => {
+                let e = proxy::redact(&e);
+                println!("下载源 {} 失败: {}", source_label, e);
+                outcome = Err(format!("{}: {}", source_label, e));
+            }
+            result => {
+                outcome = result;
+                break;
+            }
+        }
+    }
+
+    let actual_sha256 = match outcome {
+        Ok(download::DownloadOutcome::Completed { sha256, size }) => {
+            println!("文件下载完成: {} bytes", size);
+            sha256
+        }
+        Ok(download::DownloadOutcome::Paused { downloaded }) => {
+            println!("下载已暂停，已下载 {} bytes", downloaded);
+            emit_download_progress(&app_handle, serde_json::json!({
+                "status": "paused",
+                "downloaded": downloaded,
+                "message": "下载已暂停，重新下载时将从断点继续"
+            }));
+            return Err("下载已暂停".to_string());
+        }
+        Ok(download::DownloadOutcome::Cancelled) => {
+            println!("下载被用户取消");
+            emit_download_progress(&app_handle, serde_json::json!({
+                "status": "error",
+                "message": "下载已取消"
+            }));
+            return Err("下载已取消".to_string());
+        }
+        Err(e) => {
+            // 保留已下载的部分，下次下载时继续
+            let error_msg = format!("{} ({})", e, proxy_info);
+            println!("错误: {}", error_msg);
+            emit_download_progress(&app_handle, serde_json::json!({
+                "status": "error",
+                "message": error_msg
+            }));
+            return Err(error_msg);
+        }
+    };
+    println!("下载文件SHA-256: {}", actual_sha256);
+
+    // 校验失败时拒绝安装
+    if let Some(expected) = &expected_sha256 {
+        if !expected.eq_ignore_ascii_case(&actual_sha256) {
+            let error_msg = format!(
+                "文件校验失败，期望 {}，实际 {}",
+                expected, actual_sha256
+            );
+            println!("错误: {}", error_msg);
+            let _ = tokio::fs::remove_file(&target_path).await;
+            emit_download_progress(&app_handle, serde_json::json!({
+                "status": "error",
+                "message": error_msg
+            }));
+            return Err(error_msg);
+        }
+        println!("文件校验通过");
+    }
+
+    println!("开始解压文件...");
+    // 发送解压开始事件
+    emit_download_progress(&app_handle, serde_json::json!({
+        "status": "extracting",
+        "message": "正在从临时目录解压安装...",
+        "progress": 0
+    }));
+
+    // 解压文件到版本目录，并切换为当前版本
+    let extract_result = extract_nodepass_archive(&target_path, &install_dir, &app_handle)
+        .await
+        .and_then(|exe_path| versions.activate(&version_tag).map(|_| exe_path));
+
+    match extract_result {
+        Ok(extracted_exe_path) => {
+            println!("解压成功: {}", extracted_exe_path);
+
+            // 清理临时文件
+            println!("清理临时文件: {:?}", target_path);
+            if let Err(e) = tokio::fs::remove_file(&target_path).await {
+                println!("警告: 删除临时文件失败: {}", e);
+                // 不影响主流程，只记录警告
+            }
+
+            // 发送完成事件
+            emit_download_progress(&app_handle, serde_json::json!({
+                "status": "completed",
+                "message": "安装完成！临时文件已清理",
+                "path": extracted_exe_path,
+                "sha256": actual_sha256,
+                "verified": expected_sha256.is_some(),
+                "version": version_tag
+            }));
+
+            // 按需逐个重启运行中的隧道，使其使用新版本
+            if rolling_restart.unwrap_or(false) {
+                tokio::spawn(rolling_restart_tunnels(app_handle.clone(), version_tag));
+            }
+
+            Ok(extracted_exe_path)
+        }
+        Err(e) => {
+            println!("解压失败: {}", e);
+
+            // 即使解压失败，也尝试清理临时文件
+            println!("清理临时文件: {:?}", target_path);
+            if let Err(cleanup_err) = tokio::fs::remove_file(&target_path).await {
+                println!("警告: 删除临时文件失败: {}", cleanup_err);
+            }
+
+            emit_download_progress(&app_handle, serde_json::json!({
+                "status": "error",
+                "message": format!("解压失败: {}", e)
+            }));
+            Err(e)
+        }
+    }
+}
+
+// 下载地址列表 (发布源, 地址)：先用前端选择的地址，再按顺序换用其它发布源
+fn download_candidates(
+    sources: &[ReleaseSource],
+    release: Option<&GitHubRelease>,
+    tag: &str,
+    filename: &str,
+    download_url: &str,
+) -> Vec<(String, String)> {
+    let mut candidates = vec![(
+        release
+            .and_then(|release| release.source.clone())
+            .unwrap_or_else(|| "指定地址".to_string()),
+        download_url.to_string(),
+    )];
+    for source in sources {
+        if let Some(url) = source.download_url(tag, filename) {
+            if !candidates.iter().any(|(_, existing)| *existing == url) {
+                candidates.push((source.label().to_string(), url));
+            }
+        }
+    }
+    candidates
+}
+
+// 查找下载文件在发布校验文件中的摘要；发布没有校验文件时返回 None
+async fn fetch_expected_checksum(
+    client: &reqwest::Client,
+    sources: &[ReleaseSource],
+    release: Option<&GitHubRelease>,
+    download_url: &str,
+    filename: &str,
+) -> Result<Option<String>, String> {
+    // 前端未传入发布信息时，根据下载地址中的版本标签查询
+    let fetched;
+    let release = match release {
+        Some(release) => release,
+        None => {
+            let tag = match checksum::release_tag_from_url(download_url) {
+                Some(tag) => tag,
+                None => return Ok(None),
+            };
+            fetched = release_sources::fetch_by_tag(client, sources, &tag).await?;
+            &fetched
+        }
+    };
+
+    let asset = match checksum::find_checksum_asset(&release.assets) {
+        Some(asset) => asset,
+        None => return Ok(None),
+    };
+    println!("下载校验文件: {}", proxy::redact(&asset.browser_download_url));
+
+    let response = client
+        .get(&asset.browser_download_url)
+        .timeout(Duration::from_secs(30))
+        .send()
+        .await
+        .map_err(|e| format!("下载校验文件失败: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("下载校验文件失败，HTTP状态: {}", response.status()));
+    }
+    let content = response
+        .text()
+        .await
+        .map_err(|e| format!("读取校验文件失败: {}", e))?;
+
+    checksum::parse_checksums(&content)
+        .remove(filename)
+        .map(Some)
+        .ok_or_else(|| format!("校验文件 {} 中没有 {} 的记录", asset.name, filename))
+}
+
+#[tauri::command]
+async fn list_nodepass_versions(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<InstalledVersion>, String> {
+    Ok(state.versions.list())
+}
+
+// 切换当前版本，已运行的隧道在重启后使用新版本
+#[tauri::command]
+async fn switch_nodepass_version(
+    app_handle: AppHandle,
+    state: tauri::State<'_, AppState>,
+    version: String,
+    rolling_restart: Option<bool>,
+) -> Result<(), String> {
+    state.versions.activate(&version)?;
+    if rolling_restart.unwrap_or(false) {
+        tokio::spawn(rolling_restart_tunnels(app_handle, version));
+    }
+    Ok(())
+}
+
+// 逐个重启跟随当前版本的运行中隧道，每个隧道确认恢复运行后再处理下一个；
+// 某个隧道重启失败时停止，其余隧道保持原样
+async fn rolling_restart_tunnels(app_handle: AppHandle, version: String) {
+    let state = app_handle.state::<AppState>();
+    let registry = state.registry.clone();
+    let liveness = state.liveness.clone();
+
+    let tunnel_ids: Vec<String> = registry
+        .running()
+        .into_iter()
+        .map(|(tunnel_id, _)| tunnel_id)
+        .filter(|tunnel_id| {
+            registry
+                .definition(tunnel_id)
+                .map(|definition| definition.nodepass_version.is_none())
+                .unwrap_or(false)
+        })
+        .collect();
+    println!("升级到 {} 后逐个重启 {} 个隧道", version, tunnel_ids.len());
+
+    let mut results = Vec::new();
+    let mut failed = false;
+    for tunnel_id in tunnel_ids {
+        if failed {
+            results.push(serde_json::json!({
+                "tunnel_id": tunnel_id,
+                "status": "skipped"
+            }));
+            continue;
+        }
+
+        let _ = app_handle.emit(
+            "nodepass-upgrade-progress",
+            serde_json::json!({
+                "tunnel_id": tunnel_id,
+                "version": version,
+                "status": "restarting"
+            }),
+        );
+
+        let restarted = async {
+            let pid = restart_registered_tunnel(&app_handle, &registry, &tunnel_id).await?;
+            // 启动后进程需要保持运行一段时间才算恢复
+            sleep(UPGRADE_HEALTH_CHECK_DELAY).await;
+            if registry.pid_of(&tunnel_id) == Some(pid) && liveness.is_alive(pid) {
+                Ok(pid)
+            } else {
+                Err(format!("隧道 {} 重启后未能保持运行", tunnel_id))
+            }
+        }
+        .await;
+
+        let result = match restarted {
+            Ok(pid) => serde_json::json!({
+                "tunnel_id": tunnel_id,
+                "version": version,
+                "status": "restarted",
+                "pid": pid
+            }),
+            Err(error) => {
+                println!("滚动重启隧道 {} 失败: {}", tunnel_id, error);
+                failed = true;
+                serde_json::json!({
+                    "tunnel_id": tunnel_id,
+                    "version": version,
+                    "status": "failed",
+                    "error": error
+                })
+            }
+        };
+        let _ = app_handle.emit("nodepass-upgrade-progress", result.clone());
+        results.push(result);
+    }
+
+    let _ = app_handle.emit(
+        "nodepass-upgrade-completed",
+        serde_json::json!({
+            "version": version,
+            "success": !failed,
+            "results": results
+        }),
+    );
+}
+
+#[tauri::command]
+async fn rollback_nodepass_version(state: tauri::State<'_, AppState>) -> Result<String, String> {
+    state.versions.rollback()
+}
+
+#[tauri::command]
+async fn remove_nodepass_version(
+    state: tauri::State<'_, AppState>,
+    version: String,
+) -> Result<(), String> {
+    // 有隧道正在使用或固定了该版本时不允许删除
+    let pinned: Vec<(String, String)> = state
+        .registry
+        .list()
+        .into_iter()
+        .filter_map(|tunnel| {
+            let pin = tunnel.definition.nodepass_version?;
+            Some((tunnel.definition.id, pin))
+        })
+        .collect();
+    state
+        .versions
+        .remove(&version, &state.registry.running_executables(), &pinned)
+}
+
+// 设置隧道固定使用的版本，传空值时跟随当前版本
+#[tauri::command]
+async fn set_tunnel_nodepass_version(
+    state: tauri::State<'_, AppState>,
+    tunnel_id: String,
+    version: Option<String>,
+) -> Result<TunnelSnapshot, String> {
+    let mut definition = state
+        .registry
+        .definition(&tunnel_id)
+        .ok_or_else(|| format!("隧道 {} 不存在", tunnel_id))?;
+    let version = version.filter(|version| !version.trim().is_empty());
+    if let Some(version) = &version {
+        if !state.versions.is_installed(version) {
+            return Err(format!("NodePass 版本 {} 未安装", version));
+        }
+    }
+    definition.nodepass_version = version;
+    state.registry.upsert(definition)
+}
+
+// 从发布中选择适合当前系统和架构的下载包
+#[tauri::command]
+async fn select_release_asset(release: GitHubRelease) -> Result<GitHubAsset, String> {
+    platform::select_asset(&release.assets).cloned().ok_or_else(|| {
+        format!(
+            "未找到适合当前系统的下载包 ({}/{})",
+            platform::release_os(),
+            platform::release_arch()
+        )
+    })
+}
+
+#[tauri::command]
+async fn cancel_download(filename: Option<String>) -> Result<(), String> {
+    println!("收到取消下载请求，设置取消标志");
+    DOWNLOAD_CANCELLED.store(true, Ordering::Relaxed);
+    // 已暂停的下载没有在运行，直接删除保留的部分
+    if let Some(filename) = filename {
+        download::discard_partial(&std::env::temp_dir().join(filename)).await;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn pause_download() -> Result<(), String> {
+    println!("收到暂停下载请求，设置暂停标志");
+    DOWNLOAD_PAUSED.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+#[tauri::command]
+async fn open_directory(path: String) -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        std::process::Command::new("explorer")
+            .arg(&path)
+            .creation_flags(0x08000000) // CREATE_NO_WINDOW
+            .spawn()
+            .map_err(|e| format!("打开目录失败: {}", e))?;
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        std::process::Command::new("open")
+            .arg(&path)
+            .spawn()
+            .map_err(|e| format!("打开目录失败: {}", e))?;
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        std::process::Command::new("xdg-open")
+            .arg(&path)
+            .spawn()
+            .map_err(|e| format!("打开目录失败: {}", e))?;
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn show_window(app_handle: AppHandle) -> Result<(), String> {
+    if let Some(window) = app_handle.get_webview_window("main") {
+        window.show().map_err(|e| format!("显示窗口失败: {}", e))?;
+        window
+            .set_focus()
+            .map_err(|e| format!("聚焦窗口失败: {}", e))?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn hide_window(app_handle: AppHandle) -> Result<(), String> {
+    if let Some(window) = app_handle.get_webview_window("main") {
+        window.hide().map_err(|e| format!("隐藏窗口失败: {}", e))?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_running_tunnels_count(state: tauri::State<'_, AppState>) -> Result<usize, String> {
+    Ok(state.registry.running_count())
+}
+
+#[tauri::command]
+async fn show_exit_confirmation(app_handle: AppHandle) -> Result<bool, String> {
+    // 获取所有运行中的隧道
+    let running_tunnels = app_handle.state::<AppState>().registry.running_count();
+
+    // 如果有运行中的隧道，返回 true 表示需要确认
+    Ok(running_tunnels > 0)
+}
+
+#[tauri::command]
+async fn exit_app(app_handle: AppHandle, state: tauri::State<'_, AppState>) -> Result<(), String> {
+    // 退出时不再自动重启任何隧道
+    for tunnel in state.registry.list() {
+        state.registry.with_restart(&tunnel.definition.id, |restart_state| {
+            restart_state.stop_requested = true;
+        });
+    }
+
+    // 获取所有运行中的隧道
+    let tunnel_ids: Vec<String> = state
+        .registry
+        .running()
+        .into_iter()
+        .map(|(tunnel_id, _)| tunnel_id)
+        .collect();
+    let tunnel_count = tunnel_ids.len();
+
+    if tunnel_count > 0 {
+        println!("应用退出时检测到 {} 个运行中的隧道进程，正在停止...", tunnel_count);
+        
+        // 发送日志事件到前端
+        let _ = app_handle.emit(
+            "app-log",
+            serde_json::json!({
+                "level": "info",
+                "message": format!("应用退出时检测到 {} 个运行中的隧道进程，正在停止...", tunnel_count),
+                "source": "ExitHandler"
+            }),
+        );
+
+        // 并行停止所有隧道，每个隧道先尝试优雅退出
+        stop_tunnels_concurrently(&state.registry, tunnel_ids).await;
+
+        // 发送所有隧道已停止的日志
+        let _ = app_handle.emit(
+            "app-log",
+            serde_json::json!({
+                "level": "info",
+                "message": format!("已停止所有 {} 个隧道进程，应用即将退出", tunnel_count),
+                "source": "ExitHandler"
+            }),
+        );
+
+        // 发送隧道状态更新事件，通知前端更新所有隧道状态为已停止
+        let _ = app_handle.emit(
+            "all-tunnels-stopped",
+            serde_json::json!({
+                "message": "所有隧道已停止",
+                "stopped_count": tunnel_count
+            }),
+        );
+
+        println!("已停止所有 {} 个隧道进程，应用即将退出", tunnel_count);
+    } else {
+        println!("应用退出时没有运行中的隧道进程");
+        
+        // 发送日志事件到前端
+        let _ = app_handle.emit(
+            "app-log",
+            serde_json::json!({
+                "level": "info",
+                "message": "应用退出时没有运行中的隧道进程",
+                "source": "ExitHandler"
+            }),
+        );
+    }
+
+    // 退出应用
+    app_handle.exit(0);
+    Ok(())
+}
+
+#[tauri::command]
+async fn update_tray_tooltip(
+    _app_handle: AppHandle,
+    _state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    // 这个函数暂时保留为空，因为托盘tooltip的更新逻辑比较复杂
+    // 可以在后续版本中实现动态更新托盘提示信息的功能
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_window_theme(app_handle: AppHandle, _theme: String) -> Result<(), String> {
+    if let Some(window) = app_handle.get_webview_window("main") {
+        #[cfg(target_os = "windows")]
+        {
+            // 基于Tauri v2官方文档，设置窗口主题为深色
+            // 这将确保系统框颜色为深色，对应#131B2C的深色主题
+            let _ = window.set_theme(Some(tauri::Theme::Dark));
+
+            // 可选：设置窗口装饰（如果需要自定义标题栏）
+            // let _ = window.set_decorations(false);
+
+            // 可选：设置窗口阴影
+            // let _ = window.set_shadow(true);
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            // macOS平台也设置深色主题
+            let _ = window.set_theme(Some(tauri::Theme::Dark));
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            // Linux平台设置深色主题
+            let _ = window.set_theme(Some(tauri::Theme::Dark));
+        }
+
+        Ok(())
+    } else {
+        Err("窗口未找到".to_string())
+    }
+}
+
+// 新增：专门用于初始化窗口主题的函数
+#[tauri::command]
+async fn initialize_window_theme(app_handle: AppHandle) -> Result<(), String> {
+    if let Some(window) = app_handle.get_webview_window("main") {
+        // 设置为深色主题，确保系统框颜色为深色（对应#131B2C的深色标题栏）
+        let _ = window.set_theme(Some(tauri::Theme::Dark));
+
+        #[cfg(target_os = "windows")]
+        {
+            // Windows特定的窗口设置
+            // 确保窗口使用深色标题栏
+            let _ = window.set_theme(Some(tauri::Theme::Dark));
+        }
+
+        Ok(())
+    } else {
+        Err("窗口未找到".to_string())
+    }
+}
+
+// 新增：请求关闭窗口的函数
+#[tauri::command]
+async fn request_close(app_handle: AppHandle) -> Result<(), String> {
+    // 发送关闭确认事件到前端
+    let _ = app_handle.emit("close-requested", ());
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_app_version() -> Result<String, String> {
+    Ok(env!("CARGO_PKG_VERSION").to_string())
+}
+
+// 新增：在默认浏览器中打开URL
+#[tauri::command]
+async fn open_url_in_default_browser(url: String) -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        std::process::Command::new("cmd")
+            .args(&["/c", "start", &url])
+            .creation_flags(0x08000000) // CREATE_NO_WINDOW
+            .spawn()
+            .map_err(|e| format!("打开浏览器失败: {}", e))?;
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        std::process::Command::new("open")
+            .arg(&url)
+            .spawn()
+            .map_err(|e| format!("打开浏览器失败: {}", e))?;
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        std::process::Command::new("xdg-open")
+            .arg(&url)
+            .spawn()
+            .map_err(|e| format!("打开浏览器失败: {}", e))?;
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+fn minimize_window(window: Window) {
+    let _ = window.minimize();
+}
+
+#[tauri::command]
+fn maximize_window(window: Window) {
+    let _ = window.maximize();
+}
+
+#[tauri::command]
+fn unmaximize_window(window: Window) {
+    let _ = window.unmaximize();
+}
+
+#[tauri::command]
+fn close_window(window: Window) {
+    let _ = window.close();
+}
+
+#[tauri::command]
+fn is_maximized(window: Window) -> bool {
+    window.is_maximized().unwrap_or(false)
+}
+
+fn find_nodepass_executable() -> Option<String> {
+    // 1. 优先检查可执行文件同目录（主要安装位置）
+    if let Ok(exe_path) = std::env::current_exe() {
+        if let Some(exe_dir) = exe_path.parent() {
+            let resource_exe = exe_dir.join(platform::executable_name());
+            if resource_exe.exists() {
+                return Some(resource_exe.to_string_lossy().to_string());
+            }
+        }
+    }
+
+    // 2. 检查PATH环境变量
+    if let Some(exe_path) = platform::find_in_path(platform::executable_name()) {
+        return Some(exe_path.to_string_lossy().to_string());
+    }
+
+    // 3. 检查当前目录（开发环境）
+    if let Ok(current_dir) = std::env::current_dir() {
+        let current_dir_exe = current_dir.join(platform::executable_name());
+        if current_dir_exe.exists() {
+            return Some(current_dir_exe.to_string_lossy().to_string());
+        }
+    }
+
+    // 4. 最后尝试直接使用nodepass命令（假设在PATH中）
+    Some("nodepass".to_string())
+}
+
+// 使用AppHandle的版本，用于更准确的资源路径解析
+fn find_nodepass_executable_with_handle(app_handle: &AppHandle) -> Option<String> {
+    // 1. 优先检查可执行文件同目录（主要安装位置）
+    if let Ok(exe_path) = std::env::current_exe() {
+        if let Some(exe_dir) = exe_path.parent() {
+            let exe_nodepass = exe_dir.join(platform::executable_name());
+            println!("检查可执行文件同目录: {:?}", exe_nodepass);
+            if exe_nodepass.exists() {
+                println!("在可执行文件同目录找到 NodePass: {:?}", exe_nodepass);
+                return Some(exe_nodepass.to_string_lossy().to_string());
+            }
+        }
+    }
+
+    // 2. 检查应用资源目录（生产环境）
+    if let Ok(resource_path) = app_handle
+        .path()
+        .resolve(platform::executable_name(), tauri::path::BaseDirectory::Resource)
+    {
+        println!("检查资源目录: {:?}", resource_path);
+        if resource_path.exists() {
+            println!("在资源目录找到 NodePass: {:?}", resource_path);
+            return Some(resource_path.to_string_lossy().to_string());
+        }
+    }
+
+    // 3. 检查PATH环境变量
+    if let Some(exe_path) = platform::find_in_path(platform::executable_name()) {
+        println!("在PATH中找到 NodePass: {:?}", exe_path);
+        return Some(exe_path.to_string_lossy().to_string());
+    }
+
+    // 4. 最后检查当前工作目录（开发环境）
+    if let Ok(current_dir) = std::env::current_dir() {
+        let current_nodepass = current_dir.join(platform::executable_name());
+        println!("检查当前工作目录: {:?}", current_nodepass);
+        if current_nodepass.exists() {
+            println!("在当前工作目录找到 NodePass: {:?}", current_nodepass);
+            return Some(current_nodepass.to_string_lossy().to_string());
+        }
+    }
+
+    println!("未找到 {}", platform::executable_name());
+    None
+}
+
+// 生成 NodePass 的启动参数：隧道配置转换成的 URL
+fn build_nodepass_command(config: &NodePassConfig) -> Result<Vec<String>, String> {
+    Ok(vec![config.to_url()?])
+}
+
+// 解压NodePass压缩包：先把可执行文件取到临时目录，再移动到安装目录
+async fn extract_nodepass_archive(
+    archive_path: &Path,
+    extract_to: &Path,
+    app_handle: &AppHandle,
+) -> Result<String, String> {
+    let filename = archive_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("");
+
+    println!("解压文件: {} 到目录: {:?}", filename, extract_to);
+
+    let staging_dir = extract_to.join(".nodepass-staging");
+    let _ = std::fs::remove_dir_all(&staging_dir);
+
+    // 解压到临时目录完成后再整体替换，运行中的隧道不受影响
+    let result = stage_nodepass_archive(archive_path, &staging_dir, app_handle)
+        .and_then(|staged_exe| install_staged_executable(&staged_exe, extract_to));
+    let _ = std::fs::remove_dir_all(&staging_dir);
+
+    match &result {
+        Ok(exe_path) => {
+            println!("解压成功: {}", exe_path);
+        }
+        Err(e) => {
+            println!("解压失败: {}", e);
+        }
+    }
+
+    result
+}
+
+// 从压缩包中取出可执行文件到临时目录
+fn stage_nodepass_archive(
+    archive_path: &Path,
+    staging_dir: &Path,
+    app_handle: &AppHandle,
+) -> Result<PathBuf, String> {
+    let filename = archive_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    let on_entry = |index: usize, name: &str| {
+        println!("检查压缩包条目: {}", name);
+        emit_download_progress(app_handle, serde_json::json!({
+            "status": "extracting",
+            "message": format!("正在解压... {} ({})", index, name)
+        }));
+    };
+    let exe_name = platform::executable_name();
+
+    if filename.ends_with(".tar.gz") || filename.ends_with(".tgz") {
+        archive::extract_tar_gz(archive_path, staging_dir, exe_name, on_entry)
+    } else if filename.ends_with(".zip") {
+        archive::extract_zip(archive_path, staging_dir, exe_name, on_entry)
+    } else {
+        Err(format!("不支持的压缩包格式: {}", filename))
+    }
+}
+
+// 把临时目录中的可执行文件安装到目标目录
+fn install_staged_executable(staged_exe: &Path, install_dir: &Path) -> Result<String, String> {
+    platform::make_executable(staged_exe)?;
+    std::fs::create_dir_all(install_dir).map_err(|e| format!("创建安装目录失败: {}", e))?;
+    let exe_path = install_dir.join(platform::executable_name());
+    platform::replace_executable(staged_exe, &exe_path)?;
+    Ok(exe_path.to_string_lossy().to_string())
+}
+
+// 离线安装：接受 .tar.gz、.zip 或可执行文件本身，确认能运行并读出版本号后安装为对应版本
+#[tauri::command]
+async fn install_nodepass_from_file(
+    app_handle: AppHandle,
+    state: tauri::State<'_, AppState>,
+    path: String,
+) -> Result<String, String> {
+    let source = PathBuf::from(path.trim());
+    if !source.is_file() {
+        return Err(format!("文件不存在: {}", source.display()));
+    }
+    println!("从本地文件安装 NodePass: {:?}", source);
+
+    emit_download_progress(&app_handle, serde_json::json!({
+        "status": "extracting",
+        "message": "正在从本地文件安装...",
+        "progress": 0
+    }));
+
+    let staging_dir = state.versions.staging_dir();
+    let result = install_offline_file(&app_handle, &state, &source, &staging_dir).await;
+    let _ = std::fs::remove_dir_all(&staging_dir);
+
+    match &result {
+        Ok((version, exe_path)) => {
+            emit_download_progress(&app_handle, serde_json::json!({
+                "status": "completed",
+                "message": format!("已安装 NodePass {}", version),
+                "path": exe_path,
+                "version": version,
+                "source": "本地文件"
+            }));
+        }
+        Err(e) => {
+            println!("离线安装失败: {}", e);
+            emit_download_progress(&app_handle, serde_json::json!({
+                "status": "error",
+                "message": e
+            }));
+        }
+    }
+    result.map(|(version, _)| version)
+}
+
+async fn install_offline_file(
+    app_handle: &AppHandle,
+    state: &AppState,
+    source: &Path,
+    staging_dir: &Path,
+) -> Result<(String, String), String> {
+    let name = source
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    let staged_exe = if name.ends_with(".tar.gz") || name.ends_with(".tgz") || name.ends_with(".zip") {
+        stage_nodepass_archive(source, staging_dir, app_handle)?
+    } else {
+        std::fs::create_dir_all(staging_dir).map_err(|e| format!("创建临时目录失败: {}", e))?;
+        let staged_exe = staging_dir.join(platform::executable_name());
+        std::fs::copy(source, &staged_exe).map_err(|e| format!("复制文件失败: {}", e))?;
+        staged_exe
+    };
+    platform::make_executable(&staged_exe)?;
+
+    // 确认文件确实是可以运行的 NodePass
+    let version = state
+        .version_probe
+        .probe(&staged_exe)
+        .await?
+        .version
+        .ok_or_else(|| "无法读取版本号，文件不是有效的 NodePass 可执行文件".to_string())?;
+    println!("离线安装包版本: {}", version);
+
+    let exe_path = install_staged_executable(&staged_exe, &state.versions.version_dir(&version)?)?;
+    state.versions.activate(&version)?;
+    Ok((version, exe_path))
+}
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    env_logger::init();
+
+    let app_state = AppState::new();
+
+    // 启动时清理过期的隧道日志
+    app_state.tunnel_logs.enforce_retention();
+
+    tauri::Builder::default()
+        .plugin(tauri_plugin_window_state::Builder::new().build())
+        .plugin(tauri_plugin_notification::init())
+        .manage(app_state)
+        .setup(|app| {
+            let app_handle = app.handle().clone();
+
+            // 基于Tauri v2官方文档设置窗口主题
+            if let Some(window) = app.get_webview_window("main") {
+                // 设置深色主题，确保系统框颜色为深色（对应#131B2C的深色标题栏）
+                let _ = window.set_theme(Some(tauri::Theme::Dark));
+
+                #[cfg(target_os = "windows")]
+                {
+                    // Windows平台特定设置
+                    // 确保窗口标题栏使用深色主题
+                    let _ = window.set_theme(Some(tauri::Theme::Dark));
+
+                    // 使用自定义标题栏，decorations已在tauri.conf.json中设置为false
+                    // 自定义标题栏颜色为#131B2C，确保视觉一致性
+                }
+
+                // 在窗口加载完成后再次确认主题设置
+                let main_window = window.clone();
+                tauri::async_runtime::spawn(async move {
+                    // 等待前端加载完成
+                    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+                    // 再次确保深色主题设置
+                    let _ = main_window.set_theme(Some(tauri::Theme::Dark));
+
+                    // 发送主题初始化事件到前端
+                    let _ = main_window.emit(
+                        "window-theme-initialized",
+                        serde_json::json!({
+                            "theme": "dark",
+                            "systemFrame": "#131B2C",
+                            "decorations": false,
+                            "customTitlebar": true
+                        }),
+                    );
+                });
+            }
+
+            // 创建托盘菜单
+            let quit = MenuItem::with_id(app, "quit", "退出", true, None::<&str>)?;
+            let menu = Menu::with_items(app, &[&quit])?;
+
+            // 创建托盘图标
+            let _tray = TrayIconBuilder::with_id("main")
+                .tooltip("NodePass GUI - 无运行中的隧道")
+                .icon(app.default_window_icon().unwrap().clone())
+                .menu(&menu)
+                .show_menu_on_left_click(false)
+                .on_tray_icon_event(|tray, event| {
+                    match event {
+                        TrayIconEvent::Click {
+                            button,
+                            button_state,
+                            ..
+                        } => {
+                            match button {
+                                tauri::tray::MouseButton::Left => {
+                                    if button_state == tauri::tray::MouseButtonState::Up {
+                                        // 左键单击显示窗口
+                                        if let Some(window) =
+                                            tray.app_handle().get_webview_window("main")
+                                        {
+                                            let _ = window.show();
+                                            let _ = window.set_focus();
+                                        }
+                                    }
+                                }
+                                _ => {}
+                            }
+                        }
+                        TrayIconEvent::DoubleClick { .. } => {
+                            // 双击显示窗口
+                            if let Some(window) = tray.app_handle().get_webview_window("main") {
+                                let _ = window.show();
+                                let _ = window.set_focus();
+                            }
+                        }
+                        _ => {}
+                    }
+                })
+                .on_menu_event({
+                    let app_handle = app.handle().clone();
+                    move |_app, event| {
+                        match event.id().as_ref() {
+                            "quit" => {
+                                // 退出应用
+                                let app_handle_clone = app_handle.clone();
+                                tauri::async_runtime::spawn(async move {
+                                    let _ = exit_app(
+                                        app_handle_clone.clone(),
+                                        app_handle_clone.state::<AppState>(),
+                                    )
+                                    .await;
+                                });
+                            }
+                            _ => {}
+                        }
+                    }
+                })
+                .build(app)?;
+
+            // 重新接管上次运行留下的隧道进程
+            tauri::async_runtime::spawn(adopt_surviving_tunnels(app.handle().clone()));
+
+            // 后台定时检查 NodePass 新版本
+            tauri::async_runtime::spawn(update_check_loop(app.handle().clone()));
+
+            // 监听窗口关闭事件
+            let main_window = app.get_webview_window("main").unwrap();
+            main_window.on_window_event(move |event| {
+                match event {
+                    WindowEvent::CloseRequested { api, .. } => {
+                        // 阻止默认关闭行为
+                        api.prevent_close();
+
+                        // 发送关闭确认事件到前端
+                        let _ = app_handle.emit("close-requested", ());
+                    }
+                    _ => {}
+                }
+            });
+
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            get_restart_stats,
+            list_tunnels,
+            get_tunnel_store_error,
+            get_tunnel,
+            save_tunnel,
+            delete_tunnel,
+            start_tunnel,
+            stop_tunnel,
+            get_orphan_processes,
+            kill_orphan_process,
+            stop_nodepass_by_pid,
+            stop_all_nodepass,
+            get_tunnel_logs,
+            get_tunnel_log_history,
+            get_log_settings,
+            update_log_settings,
+            get_fatal_rules,
+            update_fatal_rules,
+            test_fatal_rules,
+            get_app_config,
+            update_app_config,
+            import_legacy_app_config,
+            parse_nodepass_url,
+            validate_config,
+            check_nodepass_status,
+            get_latest_release,
+            get_release_sources,
+            update_release_sources,
+            get_update_check_settings,
+            update_update_check_settings,
+            check_for_updates,
+            download_nodepass,
+            cancel_download,
+            select_release_asset,
+            test_proxy,
+            install_nodepass_from_file,
+            list_nodepass_versions,
+            switch_nodepass_version,
+            rollback_nodepass_version,
+            remove_nodepass_version,
+            set_tunnel_nodepass_version,
+            pause_download,
+            open_directory,
+            show_window,
+            hide_window,
+            get_running_tunnels_count,
+            show_exit_confirmation,
+            exit_app,
+            update_tray_tooltip,
+            set_window_theme,
+            initialize_window_theme,
+            request_close,
+            get_app_version,
+            open_url_in_default_browser,
+            minimize_window,
+            maximize_window,
+            unmaximize_window,
+            close_window,
+            is_maximized
+        ])
+        .run(tauri::generate_context!())
+        .expect("error while running tauri application");
+}
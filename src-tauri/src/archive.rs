@@ -0,0 +1,362 @@
+// 安全地从发布压缩包中取出 NodePass 可执行文件：
+// 拒绝绝对路径、".." 和指向目标目录之外的链接，限制解压总大小，只解压可执行文件
+use flate2::read::GzDecoder;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use tar::{Archive, EntryType};
+
+// 解压内容的总大小上限
+const MAX_UNCOMPRESSED_SIZE: u64 = 512 * 1024 * 1024;
+
+// unix 文件类型中的符号链接
+const S_IFMT: u32 = 0o170000;
+const S_IFLNK: u32 = 0o120000;
+
+// 检查压缩包内的相对路径，返回去掉 "." 后的路径
+fn safe_relative_path(path: &Path) -> Result<PathBuf, String> {
+    let mut safe = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => safe.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                return Err(format!("压缩包包含不安全的路径: {}", path.display()));
+            }
+            Component::RootDir | Component::Prefix(_) => {
+                return Err(format!("压缩包包含绝对路径: {}", path.display()));
+            }
+        }
+    }
+    Ok(safe)
+}
+
+// 链接目标从 base 目录开始解析，不能离开解压目录
+fn check_link_target(entry_path: &Path, base: &Path, target: &Path) -> Result<(), String> {
+    let mut depth = base.components().count() as i64;
+    for component in target.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir => {
+                depth -= 1;
+                if depth < 0 {
+                    return Err(format!(
+                        "压缩包中的链接指向解压目录之外: {} -> {}",
+                        entry_path.display(),
+                        target.display()
+                    ));
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => {
+                return Err(format!(
+                    "压缩包中的链接指向绝对路径: {} -> {}",
+                    entry_path.display(),
+                    target.display()
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn size_limit_error(limit: u64) -> String {
+    format!("压缩包解压后超过大小限制 ({} MB)", limit / 1024 / 1024)
+}
+
+// 把条目内容写入 staging 目录，超过剩余额度时失败
+fn write_executable(
+    reader: &mut impl Read,
+    staging_dir: &Path,
+    exe_name: &str,
+    remaining: u64,
+    limit: u64,
+) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(staging_dir).map_err(|e| format!("创建临时目录失败: {}", e))?;
+    let outpath = staging_dir.join(exe_name);
+    let mut outfile =
+        std::fs::File::create(&outpath).map_err(|e| format!("创建文件失败: {}", e))?;
+
+    let written = std::io::copy(&mut reader.take(remaining + 1), &mut outfile)
+        .map_err(|e| format!("解压文件失败: {}", e))?;
+    if written > remaining {
+        drop(outfile);
+        let _ = std::fs::remove_file(&outpath);
+        return Err(size_limit_error(limit));
+    }
+    outfile.flush().map_err(|e| format!("写入文件失败: {}", e))?;
+    Ok(outpath)
+}
+
+// 从 .tar.gz 中取出可执行文件到 staging_dir，on_entry(序号, 条目名)
+pub fn extract_tar_gz(
+    archive_path: &Path,
+    staging_dir: &Path,
+    exe_name: &str,
+    on_entry: impl FnMut(usize, &str),
+) -> Result<PathBuf, String> {
+    extract_tar_gz_limited(archive_path, staging_dir, exe_name, MAX_UNCOMPRESSED_SIZE, on_entry)
+}
+
+fn extract_tar_gz_limited(
+    archive_path: &Path,
+    staging_dir: &Path,
+    exe_name: &str,
+    limit: u64,
+    mut on_entry: impl FnMut(usize, &str),
+) -> Result<PathBuf, String> {
+    let file = std::fs::File::open(archive_path).map_err(|e| format!("打开压缩包失败: {}", e))?;
+    let mut archive = Archive::new(GzDecoder::new(file));
+
+    let mut total_size = 0u64;
+    let mut extracted = None;
+
+    for (index, entry) in archive
+        .entries()
+        .map_err(|e| format!("读取压缩包条目失败: {}", e))?
+        .enumerate()
+    {
+        let mut entry = entry.map_err(|e| format!("读取条目失败: {}", e))?;
+        let path = entry
+            .path()
+            .map_err(|e| format!("获取文件路径失败: {}", e))?;
+        let path = safe_relative_path(&path)?;
+        on_entry(index + 1, &path.to_string_lossy());
+
+        let entry_type = entry.header().entry_type();
+        if matches!(entry_type, EntryType::Symlink | EntryType::Link) {
+            if let Some(target) = entry
+                .link_name()
+                .map_err(|e| format!("读取链接目标失败: {}", e))?
+            {
+                // 符号链接相对所在目录，硬链接相对压缩包根目录
+                let base = match entry_type {
+                    EntryType::Symlink => path.parent().unwrap_or(Path::new("")),
+                    _ => Path::new(""),
+                };
+                check_link_target(&path, base, &target)?;
+            }
+            continue;
+        }
+
+        total_size += entry.size();
+        if total_size > limit {
+            return Err(size_limit_error(limit));
+        }
+
+        let is_executable = path.file_name().and_then(|name| name.to_str()) == Some(exe_name);
+        if entry_type.is_file() && is_executable && extracted.is_none() {
+            let remaining = limit - (total_size - entry.size());
+            extracted = Some(write_executable(&mut entry, staging_dir, exe_name, remaining, limit)?);
+        }
+    }
+
+    extracted.ok_or_else(|| format!("压缩包中未找到{}文件", exe_name))
+}
+
+// 从 .zip 中取出可执行文件到 staging_dir
+pub fn extract_zip(
+    archive_path: &Path,
+    staging_dir: &Path,
+    exe_name: &str,
+    on_entry: impl FnMut(usize, &str),
+) -> Result<PathBuf, String> {
+    extract_zip_limited(archive_path, staging_dir, exe_name, MAX_UNCOMPRESSED_SIZE, on_entry)
+}
+
+fn extract_zip_limited(
+    archive_path: &Path,
+    staging_dir: &Path,
+    exe_name: &str,
+    limit: u64,
+    mut on_entry: impl FnMut(usize, &str),
+) -> Result<PathBuf, String> {
+    let file = std::fs::File::open(archive_path).map_err(|e| format!("打开压缩包失败: {}", e))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("读取压缩包失败: {}", e))?;
+
+    let mut total_size = 0u64;
+    let mut extracted = None;
+
+    for index in 0..archive.len() {
+        let mut file = archive
+            .by_index(index)
+            .map_err(|e| format!("读取压缩包条目失败: {}", e))?;
+        let path = file
+            .enclosed_name()
+            .map(Path::to_path_buf)
+            .ok_or_else(|| format!("压缩包包含不安全的路径: {}", file.name()))?;
+        on_entry(index + 1, &path.to_string_lossy());
+
+        // zip 中的符号链接以文件内容保存链接目标
+        if file.unix_mode().map(|mode| mode & S_IFMT == S_IFLNK) == Some(true) {
+            let mut target = String::new();
+            (&mut file)
+                .take(4096)
+                .read_to_string(&mut target)
+                .map_err(|e| format!("读取链接目标失败: {}", e))?;
+            check_link_target(&path, path.parent().unwrap_or(Path::new("")), Path::new(&target))?;
+            continue;
+        }
+
+        total_size += file.size();
+        if total_size > limit {
+            return Err(size_limit_error(limit));
+        }
+
+        let is_executable = path.file_name().and_then(|name| name.to_str()) == Some(exe_name);
+        if file.is_file() && is_executable && extracted.is_none() {
+            // 声明的大小可能与实际不符，写入时再按剩余额度限制
+            let remaining = limit - (total_size - file.size());
+            extracted = Some(write_executable(&mut file, staging_dir, exe_name, remaining, limit)?);
+        }
+    }
+
+    extracted.ok_or_else(|| format!("压缩包中未找到{}文件", exe_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use tempfile::TempDir;
+
+    const EXE: &str = "nodepass";
+
+    enum Entry<'a> {
+        File(&'a str, &'a [u8]),
+        Symlink(&'a str, &'a str),
+    }
+
+    fn tar_gz(dir: &TempDir, entries: &[Entry]) -> PathBuf {
+        let path = dir.path().join("test.tar.gz");
+        let encoder = flate2::write::GzEncoder::new(
+            File::create(&path).unwrap(),
+            flate2::Compression::default(),
+        );
+        let mut builder = tar::Builder::new(encoder);
+        for entry in entries {
+            let mut header = tar::Header::new_gnu();
+            let (name, data): (&str, &[u8]) = match entry {
+                Entry::File(name, data) => {
+                    header.set_entry_type(EntryType::Regular);
+                    header.set_size(data.len() as u64);
+                    (name, data)
+                }
+                Entry::Symlink(name, target) => {
+                    header.set_entry_type(EntryType::Symlink);
+                    header.set_size(0);
+                    header.set_link_name_literal(target).unwrap();
+                    (name, &[])
+                }
+            };
+            header.set_mode(0o755);
+            // 直接写入名称，绕过 tar 对 ".." 和绝对路径的检查
+            header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+            header.set_cksum();
+            builder.append(&header, data).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+        path
+    }
+
+    fn zip(dir: &TempDir, entries: &[Entry]) -> PathBuf {
+        let path = dir.path().join("test.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&path).unwrap());
+        let options = zip::write::FileOptions::default().unix_permissions(0o755);
+        for entry in entries {
+            match entry {
+                Entry::File(name, data) => {
+                    writer.start_file(*name, options).unwrap();
+                    writer.write_all(data).unwrap();
+                }
+                Entry::Symlink(name, target) => {
+                    writer.add_symlink(*name, *target, options).unwrap();
+                }
+            }
+        }
+        writer.finish().unwrap();
+        path
+    }
+
+    // 分别打包为 tar.gz 和 zip 后解压，返回取出的可执行文件内容
+    fn extract_both(entries: &[Entry], limit: u64) -> Vec<Result<Vec<u8>, String>> {
+        let dir = tempfile::tempdir().unwrap();
+        let staging = dir.path().join("staging");
+        let tar_path = tar_gz(&dir, entries);
+        let zip_path = zip(&dir, entries);
+        let results = [
+            extract_tar_gz_limited(&tar_path, &staging.join("tar"), EXE, limit, |_, _| {}),
+            extract_zip_limited(&zip_path, &staging.join("zip"), EXE, limit, |_, _| {}),
+        ];
+        // 解压目录之外不能出现任何文件
+        let names: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        assert!(names
+            .iter()
+            .all(|name| ["staging", "test.tar.gz", "test.zip"].contains(&name.as_str())));
+        results
+            .into_iter()
+            .map(|result| result.map(|path| std::fs::read(path).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn extracts_executable_from_nested_directory() {
+        for result in extract_both(
+            &[
+                Entry::File("nodepass_1.0/README.md", b"readme"),
+                Entry::File("./nodepass_1.0/nodepass", b"binary"),
+                Entry::Symlink("nodepass_1.0/latest", "nodepass"),
+            ],
+            1024,
+        ) {
+            assert_eq!(result.unwrap(), b"binary");
+        }
+    }
+
+    #[test]
+    fn rejects_parent_dir_traversal() {
+        for result in extract_both(&[Entry::File("../nodepass", b"evil")], 1024) {
+            assert!(result.unwrap_err().contains("不安全的路径"));
+        }
+    }
+
+    #[test]
+    fn rejects_absolute_paths() {
+        for result in extract_both(&[Entry::File("/tmp/nodepass", b"evil")], 1024) {
+            assert!(result.is_err());
+        }
+    }
+
+    #[test]
+    fn rejects_symlink_escape() {
+        for target in ["../../etc/passwd", "/etc/passwd"] {
+            for result in extract_both(
+                &[
+                    Entry::Symlink("bin/link", target),
+                    Entry::File("bin/nodepass", b"binary"),
+                ],
+                1024,
+            ) {
+                assert!(result.unwrap_err().contains("链接"));
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_oversized_entries() {
+        let large = vec![0u8; 4096];
+        for result in extract_both(&[Entry::File("nodepass", &large)], 1024) {
+            assert!(result.unwrap_err().contains("大小限制"));
+        }
+        // 单个条目不超限，但总大小超限
+        let half = vec![0u8; 600];
+        for result in extract_both(
+            &[Entry::File("README.md", &half), Entry::File("nodepass", &half)],
+            1024,
+        ) {
+            assert!(result.unwrap_err().contains("大小限制"));
+        }
+    }
+}
@@ -0,0 +1,144 @@
+// 下载文件的 SHA-256 校验：从发布附带的校验文件中查找对应文件的摘要
+use crate::GitHubAsset;
+use std::collections::HashMap;
+
+// 发布中的校验文件，例如 nodepass_1.2.3_checksums.txt、SHA256SUMS
+pub fn find_checksum_asset(assets: &[GitHubAsset]) -> Option<&GitHubAsset> {
+    assets.iter().find(|asset| {
+        let name = asset.name.to_lowercase();
+        (name.contains("checksum") || name.contains("sha256sum"))
+            && (name.ends_with(".txt") || !name.contains('.'))
+    })
+}
+
+// 解析校验文件，返回 文件名 -> 小写十六进制摘要。
+// 支持 "摘要  文件名"（含二进制模式的 "*文件名"）和 BSD 风格的 "SHA256 (文件名) = 摘要"
+pub fn parse_checksums(content: &str) -> HashMap<String, String> {
+    let mut checksums = HashMap::new();
+
+    for line in content.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if let Some(rest) = line.strip_prefix("SHA256 (") {
+            if let Some((name, digest)) = rest.split_once(") = ") {
+                if is_sha256_hex(digest.trim()) {
+                    checksums.insert(name.to_string(), digest.trim().to_lowercase());
+                }
+            }
+            continue;
+        }
+
+        let mut parts = line.split_whitespace();
+        if let (Some(digest), Some(name)) = (parts.next(), parts.next()) {
+            if is_sha256_hex(digest) {
+                checksums.insert(
+                    name.trim_start_matches('*').to_string(),
+                    digest.to_lowercase(),
+                );
+            }
+        }
+    }
+
+    checksums
+}
+
+fn is_sha256_hex(value: &str) -> bool {
+    value.len() == 64 && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+// 从 .../releases/download/<tag>/<文件名> 形式的下载地址中取出版本标签
+pub fn release_tag_from_url(url: &str) -> Option<String> {
+    let (_, rest) = url.split_once("/releases/download/")?;
+    let tag = rest.split('/').next()?;
+    if tag.is_empty() {
+        None
+    } else {
+        Some(tag.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIGEST: &str = "3a7bd3e2360a3d29eea436fcfb7e44c735d117c42d1c1835420b6b9942dd4f1b";
+
+    fn asset(name: &str) -> GitHubAsset {
+        GitHubAsset {
+            name: name.to_string(),
+            browser_download_url: format!(
+                "https://github.com/yosebyte/nodepass/releases/download/v1.4.0/{}",
+                name
+            ),
+            size: 0,
+        }
+    }
+
+    #[test]
+    fn parses_gnu_format() {
+        let content = format!(
+            "{}  nodepass_1.4.0_linux_amd64.tar.gz\n\n{}  nodepass_1.4.0_windows_amd64.zip\n",
+            DIGEST,
+            DIGEST.to_uppercase()
+        );
+        let checksums = parse_checksums(&content);
+        assert_eq!(checksums["nodepass_1.4.0_linux_amd64.tar.gz"], DIGEST);
+        // 摘要统一转为小写
+        assert_eq!(checksums["nodepass_1.4.0_windows_amd64.zip"], DIGEST);
+    }
+
+    #[test]
+    fn parses_binary_marker() {
+        let checksums = parse_checksums(&format!("{} *nodepass_1.4.0_linux_amd64.tar.gz", DIGEST));
+        assert_eq!(checksums["nodepass_1.4.0_linux_amd64.tar.gz"], DIGEST);
+    }
+
+    #[test]
+    fn parses_bsd_format() {
+        let checksums = parse_checksums(&format!(
+            "SHA256 (nodepass_1.4.0_darwin_arm64.tar.gz) = {}",
+            DIGEST
+        ));
+        assert_eq!(checksums["nodepass_1.4.0_darwin_arm64.tar.gz"], DIGEST);
+    }
+
+    #[test]
+    fn ignores_invalid_lines_and_missing_entries() {
+        let content = format!(
+            "# checksums\nnot-a-digest  nodepass.zip\n{}  nodepass_1.4.0_linux_amd64.tar.gz\nSHA256 (broken.zip) = xyz\n",
+            &DIGEST[..63]
+        );
+        let checksums = parse_checksums(&content);
+        assert!(checksums.is_empty());
+        assert!(!parse_checksums(&format!("{}  other.zip", DIGEST))
+            .contains_key("nodepass_1.4.0_linux_amd64.tar.gz"));
+    }
+
+    #[test]
+    fn finds_checksum_asset() {
+        let assets = vec![
+            asset("nodepass_1.4.0_linux_amd64.tar.gz"),
+            asset("nodepass_1.4.0_checksums.txt"),
+        ];
+        assert_eq!(find_checksum_asset(&assets).unwrap().name, "nodepass_1.4.0_checksums.txt");
+        assert_eq!(find_checksum_asset(&[asset("SHA256SUMS")]).unwrap().name, "SHA256SUMS");
+        // 签名等其它文件不算校验文件
+        assert!(find_checksum_asset(&[asset("checksums.txt.sig"), asset("nodepass.zip")]).is_none());
+    }
+
+    #[test]
+    fn extracts_release_tag() {
+        assert_eq!(
+            release_tag_from_url(
+                "https://github.com/yosebyte/nodepass/releases/download/v1.4.0/nodepass_1.4.0_linux_amd64.tar.gz"
+            )
+            .as_deref(),
+            Some("v1.4.0")
+        );
+        assert_eq!(
+            release_tag_from_url("https://mirror.example.com/nodepass/releases/download/v1.3.0-beta.1/x.zip")
+                .as_deref(),
+            Some("v1.3.0-beta.1")
+        );
+        assert_eq!(release_tag_from_url("https://example.com/releases/download//x.zip"), None);
+        assert_eq!(release_tag_from_url("https://example.com/nodepass.zip"), None);
+    }
+}
@@ -0,0 +1,195 @@
+// 应用配置（系统设置、代理）保存在带版本号的 JSON 文件中。
+// 写入时先写临时文件再改名，并保留上一次的配置作为备份
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+// 配置文件格式版本，格式变化时递增并在 migrate 中升级旧文件
+const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct StoredConfig {
+    #[serde(rename = "schemaVersion")]
+    schema_version: u32,
+    #[serde(rename = "savedAt", default)]
+    saved_at: Option<String>,
+    // 是否由前端 localStorage 中的旧配置导入
+    #[serde(rename = "migratedFromLocalStorage", default)]
+    migrated_from_local_storage: bool,
+    // 前端的 AppConfig
+    config: serde_json::Value,
+}
+
+#[derive(Clone)]
+pub struct ConfigStore {
+    config_file: PathBuf,
+    stored: Arc<Mutex<Option<StoredConfig>>>,
+}
+
+fn backup_path(path: &Path) -> PathBuf {
+    path.with_extension("json.bak")
+}
+
+fn read_stored(path: &Path) -> Result<StoredConfig, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("读取配置文件失败: {}", e))?;
+    let stored: StoredConfig =
+        serde_json::from_str(&content).map_err(|e| format!("解析配置文件失败: {}", e))?;
+    migrate(stored)
+}
+
+// 升级旧版本的配置文件
+fn migrate(stored: StoredConfig) -> Result<StoredConfig, String> {
+    if stored.schema_version > SCHEMA_VERSION {
+        return Err(format!(
+            "配置文件版本 {} 高于当前支持的版本 {}",
+            stored.schema_version, SCHEMA_VERSION
+        ));
+    }
+    Ok(StoredConfig {
+        schema_version: SCHEMA_VERSION,
+        ..stored
+    })
+}
+
+impl ConfigStore {
+    // 主文件损坏时使用备份
+    pub fn load(config_file: PathBuf) -> Self {
+        let stored = if config_file.exists() {
+            match read_stored(&config_file) {
+                Ok(stored) => Some(stored),
+                Err(e) => {
+                    println!("应用配置不可用: {}，尝试使用备份", e);
+                    read_stored(&backup_path(&config_file))
+                        .map_err(|e| println!("配置备份不可用: {}", e))
+                        .ok()
+                }
+            }
+        } else {
+            None
+        };
+        Self {
+            config_file,
+            stored: Arc::new(Mutex::new(stored)),
+        }
+    }
+
+    // 尚未保存过配置时返回 None
+    pub fn get(&self) -> Option<serde_json::Value> {
+        self.stored
+            .lock()
+            .ok()
+            .and_then(|stored| stored.as_ref().map(|stored| stored.config.clone()))
+    }
+
+    pub fn update(&self, config: serde_json::Value) -> Result<(), String> {
+        self.save(config, false)
+    }
+
+    // 导入前端 localStorage 中的旧配置，只在还没有配置文件时生效；返回当前配置
+    pub fn import_legacy(&self, content: &str) -> Result<serde_json::Value, String> {
+        if let Some(config) = self.get() {
+            println!("应用配置已存在，跳过导入旧配置");
+            return Ok(config);
+        }
+        let config: serde_json::Value =
+            serde_json::from_str(content).map_err(|e| format!("解析旧配置失败: {}", e))?;
+        self.save(config.clone(), true)?;
+        println!("已导入 localStorage 中的旧配置");
+        Ok(config)
+    }
+
+    // 旧版本在应用配置中保存的隧道列表，隧道定义现在统一保存在 tunnels.json
+    pub fn legacy_tunnels(&self) -> Vec<serde_json::Value> {
+        self.get()
+            .and_then(|config| config.get("tunnels").and_then(|tunnels| tunnels.as_array()).cloned())
+            .unwrap_or_default()
+    }
+
+    // 隧道导入注册表后从应用配置中移除
+    pub fn remove_tunnels(&self) -> Result<(), String> {
+        let mut config = match self.get() {
+            Some(config) => config,
+            None => return Ok(()),
+        };
+        match config.as_object_mut().and_then(|object| object.remove("tunnels")) {
+            Some(_) => self.update(config),
+            None => Ok(()),
+        }
+    }
+
+    fn save(&self, config: serde_json::Value, migrated: bool) -> Result<(), String> {
+        if !config.is_object() {
+            return Err("应用配置必须是 JSON 对象".to_string());
+        }
+        // 持有锁直到写入完成，避免并发写入交错
+        let mut current = self
+            .stored
+            .lock()
+            .map_err(|_| "应用配置被锁定".to_string())?;
+
+        let stored = StoredConfig {
+            schema_version: SCHEMA_VERSION,
+            saved_at: Some(chrono::Local::now().to_rfc3339()),
+            migrated_from_local_storage: migrated
+                || current
+                    .as_ref()
+                    .map(|stored| stored.migrated_from_local_storage)
+                    .unwrap_or(false),
+            config,
+        };
+        let content = serde_json::to_string_pretty(&stored)
+            .map_err(|e| format!("序列化应用配置失败: {}", e))?;
+        self.write_atomic(content.as_bytes())?;
+        *current = Some(stored);
+        Ok(())
+    }
+
+    fn write_atomic(&self, content: &[u8]) -> Result<(), String> {
+        if let Some(dir) = self.config_file.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("创建配置目录失败: {}", e))?;
+        }
+
+        let temp_file = self.config_file.with_extension("json.tmp");
+        let mut file =
+            fs::File::create(&temp_file).map_err(|e| format!("创建临时配置文件失败: {}", e))?;
+        file.write_all(content)
+            .and_then(|_| file.sync_all())
+            .map_err(|e| format!("写入临时配置文件失败: {}", e))?;
+        drop(file);
+
+        // 保留上一次的配置，改名前复制，主文件始终存在
+        if self.config_file.exists() {
+            fs::copy(&self.config_file, backup_path(&self.config_file))
+                .map_err(|e| format!("备份配置文件失败: {}", e))?;
+        }
+        fs::rename(&temp_file, &self.config_file).map_err(|e| {
+            let _ = fs::remove_file(&temp_file);
+            format!("保存配置文件失败: {}", e)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_tunnels_are_removed_from_app_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ConfigStore::load(dir.path().join("app_config.json"));
+        store
+            .import_legacy(r#"{"tunnels":[{"id":"a","mode":"server"}],"settings":{"theme":"dark"}}"#)
+            .unwrap();
+        assert_eq!(store.legacy_tunnels().len(), 1);
+
+        store.remove_tunnels().unwrap();
+        assert!(store.legacy_tunnels().is_empty());
+
+        // 重新加载后隧道列表不再出现，其他设置保留
+        let reloaded = ConfigStore::load(dir.path().join("app_config.json")).get().unwrap();
+        assert!(reloaded.get("tunnels").is_none());
+        assert_eq!(reloaded["settings"]["theme"], "dark");
+    }
+}
@@ -0,0 +1,480 @@
+// 可断点续传的文件下载：未完成的数据保存在 .part 文件中，元数据保存在旁边的 .part.json，
+// 重试时通过 Range/If-Range 从断点继续。未完成的数据按文件名和大小对应，切换下载源后也能续传
+use tokio_stream::StreamExt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+// 连续没有收到数据的最大请求次数，收到数据后重新计数
+const MAX_ATTEMPTS: u32 = 5;
+// 等待响应头、以及两个数据块之间的最长时间；不限制整个下载的总时长
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(30);
+const STALL_TIMEOUT: Duration = Duration::from_secs(30);
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(1);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+// 等待重试期间检查暂停和取消的间隔
+const CONTROL_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+struct PartialMeta {
+    // 下载的文件名（发布中的资源名）
+    #[serde(default)]
+    name: String,
+    // 上次请求的地址，只在同一地址续传时使用 If-Range
+    url: String,
+    etag: Option<String>,
+    #[serde(rename = "lastModified")]
+    last_modified: Option<String>,
+    #[serde(rename = "totalSize")]
+    total_size: Option<u64>,
+}
+
+pub enum DownloadEvent {
+    // 开始（或重新开始）传输，resumed_from 为续传的起始字节
+    Started { resumed_from: u64, total: Option<u64> },
+    Progress { downloaded: u64, total: Option<u64> },
+    Retrying { attempt: u32, delay: Duration, error: String },
+}
+
+pub enum DownloadOutcome {
+    Completed { sha256: String, size: u64 },
+    Paused { downloaded: u64 },
+    Cancelled,
+}
+
+// 暂停与取消标志
+pub struct DownloadControl<'a> {
+    pub paused: &'a AtomicBool,
+    pub cancelled: &'a AtomicBool,
+}
+
+// 单次请求的结果
+enum Attempt {
+    Done { sha256: String, size: u64 },
+    Paused(u64),
+    Cancelled,
+    // 可重试的错误（网络中断、服务器5xx等）
+    Retry(String),
+}
+
+pub fn partial_path(target: &Path) -> PathBuf {
+    let mut name = target.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    target.with_file_name(name)
+}
+
+fn meta_path(target: &Path) -> PathBuf {
+    let mut name = target.file_name().unwrap_or_default().to_os_string();
+    name.push(".part.json");
+    target.with_file_name(name)
+}
+
+fn load_meta(target: &Path) -> Option<PartialMeta> {
+    let content = std::fs::read_to_string(meta_path(target)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn save_meta(target: &Path, meta: &PartialMeta) {
+    if let Ok(content) = serde_json::to_string_pretty(meta) {
+        let _ = std::fs::write(meta_path(target), content);
+    }
+}
+
+// 删除未完成的下载数据
+pub async fn discard_partial(target: &Path) {
+    let _ = tokio::fs::remove_file(partial_path(target)).await;
+    let _ = tokio::fs::remove_file(meta_path(target)).await;
+}
+
+fn asset_name(target: &Path) -> String {
+    target
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+// 已下载部分的长度。文件名不同，或记录的文件大小与预期大小不一致时丢弃旧数据
+async fn resume_offset(target: &Path, expected_size: Option<u64>) -> (u64, PartialMeta) {
+    let name = asset_name(target);
+    match load_meta(target) {
+        Some(meta)
+            if meta.name == name
+                && !matches!((meta.total_size, expected_size), (Some(a), Some(b)) if a != b) =>
+        {
+            let len = tokio::fs::metadata(partial_path(target))
+                .await
+                .map(|metadata| metadata.len())
+                .unwrap_or(0);
+            (len, meta)
+        }
+        _ => {
+            discard_partial(target).await;
+            (
+                0,
+                PartialMeta {
+                    name,
+                    ..Default::default()
+                },
+            )
+        }
+    }
+}
+
+// 分块读取已下载的部分作为哈希的初始状态，不把整个文件读入内存
+async fn hash_prefix(path: &Path, len: u64) -> Result<Sha256, String> {
+    let file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| format!("读取下载文件失败: {}", e))?;
+    let mut reader = file.take(len);
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let n = reader
+            .read(&mut buffer)
+            .await
+            .map_err(|e| format!("读取下载文件失败: {}", e))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    Ok(hasher)
+}
+
+// 等待重试，期间响应暂停和取消；返回 None 表示可以重试
+async fn wait_retry(
+    delay: Duration,
+    target: &Path,
+    control: &DownloadControl<'_>,
+) -> Option<DownloadOutcome> {
+    let sleep = tokio::time::sleep(delay);
+    tokio::pin!(sleep);
+    let mut ticker = tokio::time::interval(CONTROL_POLL_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = &mut sleep => return None,
+            _ = ticker.tick() => {
+                if control.cancelled.load(Ordering::Relaxed) {
+                    discard_partial(target).await;
+                    return Some(DownloadOutcome::Cancelled);
+                }
+                if control.paused.load(Ordering::Relaxed) {
+                    let downloaded = tokio::fs::metadata(partial_path(target))
+                        .await
+                        .map(|metadata| metadata.len())
+                        .unwrap_or(0);
+                    return Some(DownloadOutcome::Paused { downloaded });
+                }
+            }
+        }
+    }
+}
+
+// 下载到 target，完成后返回文件的 SHA-256；expected_size 为发布信息中的文件大小
+pub async fn download_resumable(
+    client: &reqwest::Client,
+    url: &str,
+    target: &Path,
+    expected_size: Option<u64>,
+    control: DownloadControl<'_>,
+    mut on_event: impl FnMut(DownloadEvent),
+) -> Result<DownloadOutcome, String> {
+    let mut delay = INITIAL_RETRY_DELAY;
+    let mut failures = 0;
+
+    loop {
+        let mut received = false;
+        let attempt = download_attempt(client, url, target, expected_size, &control, &mut |event| {
+            if matches!(event, DownloadEvent::Progress { .. }) {
+                received = true;
+            }
+            on_event(event)
+        })
+        .await?;
+        match attempt {
+            Attempt::Done { sha256, size } => {
+                let part = partial_path(target);
+                let _ = tokio::fs::remove_file(target).await;
+                tokio::fs::rename(&part, target)
+                    .await
+                    .map_err(|e| format!("保存下载文件失败: {}", e))?;
+                let _ = tokio::fs::remove_file(meta_path(target)).await;
+
+                return Ok(DownloadOutcome::Completed { sha256, size });
+            }
+            Attempt::Paused(downloaded) => return Ok(DownloadOutcome::Paused { downloaded }),
+            Attempt::Cancelled => {
+                discard_partial(target).await;
+                return Ok(DownloadOutcome::Cancelled);
+            }
+            Attempt::Retry(error) => {
+                // 本次请求收到过数据说明网络仍在工作，重新计数
+                if received {
+                    failures = 0;
+                    delay = INITIAL_RETRY_DELAY;
+                }
+                failures += 1;
+                if failures >= MAX_ATTEMPTS {
+                    return Err(format!("下载失败，已连续重试 {} 次: {}", MAX_ATTEMPTS, error));
+                }
+                println!("下载中断 (第 {} 次): {}，{} 秒后重试", failures, error, delay.as_secs());
+                on_event(DownloadEvent::Retrying {
+                    attempt: failures,
+                    delay,
+                    error,
+                });
+                if let Some(outcome) = wait_retry(delay, target, &control).await {
+                    return Ok(outcome);
+                }
+                delay = (delay * 2).min(MAX_RETRY_DELAY);
+            }
+        }
+    }
+}
+
+async fn download_attempt(
+    client: &reqwest::Client,
+    url: &str,
+    target: &Path,
+    expected_size: Option<u64>,
+    control: &DownloadControl<'_>,
+    on_event: &mut impl FnMut(DownloadEvent),
+) -> Result<Attempt, String> {
+    let (mut offset, mut meta) = resume_offset(target, expected_size).await;
+    let part = partial_path(target);
+
+    let mut request = client.get(url);
+    if offset > 0 {
+        request = request.header(reqwest::header::RANGE, format!("bytes={}-", offset));
+        // 服务器上的文件变化时 If-Range 不成立，服务器会返回完整内容。
+        // 其它下载源的 ETag 不可比较，只核对文件总大小，内容由校验和把关
+        if meta.url == url {
+            if let Some(validator) = meta.etag.as_ref().or(meta.last_modified.as_ref()) {
+                request = request.header(reqwest::header::IF_RANGE, validator.as_str());
+            }
+        }
+    }
+
+    let response = match tokio::time::timeout(RESPONSE_TIMEOUT, request.send()).await {
+        Ok(Ok(response)) => response,
+        Ok(Err(e)) => return Ok(Attempt::Retry(format!("请求失败: {}", e))),
+        Err(_) => return Ok(Attempt::Retry("等待服务器响应超时".to_string())),
+    };
+    let status = response.status();
+
+    if status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+        // 已下载部分与服务器文件一样大，说明此前已经下载完成
+        if meta.total_size == Some(offset) {
+            let hasher = hash_prefix(&part, offset).await?;
+            return Ok(Attempt::Done {
+                sha256: format!("{:x}", hasher.finalize()),
+                size: offset,
+            });
+        }
+        discard_partial(target).await;
+        return Ok(Attempt::Retry("续传位置无效，重新下载".to_string()));
+    }
+    if status.is_server_error() {
+        return Ok(Attempt::Retry(format!("服务器错误: {}", status)));
+    }
+    if !status.is_success() {
+        return Err(format!("下载失败，HTTP状态: {}", status));
+    }
+
+    let resumed = status == reqwest::StatusCode::PARTIAL_CONTENT;
+    if !resumed {
+        // 服务器不支持续传或文件已变化，从头开始
+        offset = 0;
+    }
+
+    let headers = response.headers();
+    let header = |name: reqwest::header::HeaderName| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string())
+    };
+    let total_size = if resumed {
+        // Content-Range: bytes 100-999/1000
+        header(reqwest::header::CONTENT_RANGE)
+            .and_then(|range| range.rsplit('/').next().and_then(|total| total.parse().ok()))
+    } else {
+        response.content_length()
+    };
+    if resumed {
+        if let (Some(previous), Some(total)) = (meta.total_size, total_size) {
+            if previous != total {
+                discard_partial(target).await;
+                return Ok(Attempt::Retry(format!(
+                    "服务器上的文件大小已变化 ({} -> {})，重新下载",
+                    previous, total
+                )));
+            }
+        }
+    }
+    meta.url = url.to_string();
+    meta.etag = header(reqwest::header::ETAG);
+    meta.last_modified = header(reqwest::header::LAST_MODIFIED);
+    meta.total_size = total_size;
+    save_meta(target, &meta);
+
+    // 续传时先读入已下载部分，之后每个数据块随写入更新哈希
+    let mut hasher = if resumed {
+        hash_prefix(&part, offset).await?
+    } else {
+        Sha256::new()
+    };
+    let mut file = if resumed {
+        tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(&part)
+            .await
+            .map_err(|e| format!("打开下载文件失败: {}", e))?
+    } else {
+        tokio::fs::File::create(&part)
+            .await
+            .map_err(|e| format!("创建文件失败: {}", e))?
+    };
+
+    on_event(DownloadEvent::Started {
+        resumed_from: offset,
+        total: meta.total_size,
+    });
+
+    let mut downloaded = offset;
+    let mut stream = response.bytes_stream();
+    loop {
+        let chunk = match tokio::time::timeout(STALL_TIMEOUT, stream.next()).await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break,
+            Err(_) => {
+                let _ = file.flush().await;
+                return Ok(Attempt::Retry(format!(
+                    "{} 秒内没有收到数据",
+                    STALL_TIMEOUT.as_secs()
+                )));
+            }
+        };
+        if control.cancelled.load(Ordering::Relaxed) {
+            return Ok(Attempt::Cancelled);
+        }
+        if control.paused.load(Ordering::Relaxed) {
+            let _ = file.flush().await;
+            return Ok(Attempt::Paused(downloaded));
+        }
+
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                let _ = file.flush().await;
+                return Ok(Attempt::Retry(format!("读取数据块失败: {}", e)));
+            }
+        };
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| format!("写入文件失败: {}", e))?;
+        hasher.update(&chunk);
+        downloaded += chunk.len() as u64;
+
+        on_event(DownloadEvent::Progress {
+            downloaded,
+            total: meta.total_size,
+        });
+    }
+
+    file.flush()
+        .await
+        .map_err(|e| format!("刷新文件失败: {}", e))?;
+
+    match meta.total_size {
+        Some(total) if downloaded < total => Ok(Attempt::Retry(format!(
+            "连接提前关闭 ({}/{})",
+            downloaded, total
+        ))),
+        _ => Ok(Attempt::Done {
+            sha256: format!("{:x}", hasher.finalize()),
+            size: downloaded,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_partial(target: &Path, content: &[u8], meta: &PartialMeta) {
+        std::fs::write(partial_path(target), content).unwrap();
+        save_meta(target, meta);
+    }
+
+    #[tokio::test]
+    async fn hash_prefix_streams_existing_data() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.bin");
+        let content: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&path, &content).unwrap();
+
+        let full = hash_prefix(&path, content.len() as u64).await.unwrap();
+        assert_eq!(full.finalize(), Sha256::digest(&content));
+
+        let mut resumed = hash_prefix(&path, 70_000).await.unwrap();
+        resumed.update(&content[70_000..]);
+        assert_eq!(resumed.finalize(), Sha256::digest(&content));
+    }
+
+    #[tokio::test]
+    async fn partial_survives_source_switch() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("nodepass_1.0.0_linux_amd64.tar.gz");
+        let meta = PartialMeta {
+            name: asset_name(&target),
+            url: "https://github.com/a/b/releases/download/v1.0.0/x".to_string(),
+            total_size: Some(1000),
+            ..Default::default()
+        };
+        write_partial(&target, &[1; 300], &meta);
+
+        let (offset, meta) = resume_offset(&target, Some(1000)).await;
+        assert_eq!(offset, 300);
+        assert!(meta.url.contains("github.com"));
+    }
+
+    #[tokio::test]
+    async fn partial_discarded_when_size_differs() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("nodepass.zip");
+        let meta = PartialMeta {
+            name: asset_name(&target),
+            total_size: Some(1000),
+            ..Default::default()
+        };
+        write_partial(&target, &[1; 300], &meta);
+
+        let (offset, _) = resume_offset(&target, Some(2000)).await;
+        assert_eq!(offset, 0);
+        assert!(!partial_path(&target).exists());
+    }
+
+    #[tokio::test]
+    async fn retry_wait_stops_on_cancel() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("nodepass.zip");
+        let paused = AtomicBool::new(false);
+        let cancelled = AtomicBool::new(true);
+        let control = DownloadControl {
+            paused: &paused,
+            cancelled: &cancelled,
+        };
+
+        let outcome = tokio::time::timeout(
+            Duration::from_secs(1),
+            wait_retry(Duration::from_secs(30), &target, &control),
+        )
+        .await
+        .expect("取消后应立即结束等待");
+        assert!(matches!(outcome, Some(DownloadOutcome::Cancelled)));
+    }
+}
@@ -0,0 +1,429 @@
+// 致命错误规则：按子串或正则匹配隧道日志，在时间窗口内达到次数阈值后触发动作
+use crate::log_parser::LogRecord;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatchKind {
+    #[default]
+    #[serde(rename = "substring")]
+    Substring,
+    #[serde(rename = "regex")]
+    Regex,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RuleStream {
+    #[default]
+    #[serde(rename = "any")]
+    Any,
+    #[serde(rename = "stdout")]
+    Stdout,
+    #[serde(rename = "stderr")]
+    Stderr,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RuleAction {
+    // 停止隧道
+    #[default]
+    #[serde(rename = "stop")]
+    Stop,
+    // 重启隧道
+    #[serde(rename = "restart")]
+    Restart,
+    // 仅通知，不处理进程
+    #[serde(rename = "notify")]
+    Notify,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FatalRule {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(rename = "matchKind", default)]
+    pub match_kind: MatchKind,
+    pub pattern: String,
+    #[serde(rename = "caseSensitive", default)]
+    pub case_sensitive: bool,
+    #[serde(default)]
+    pub stream: RuleStream,
+    // 只匹配指定级别的日志，为空时不限
+    #[serde(default)]
+    pub level: Option<String>,
+    // 触发时上报的严重程度 (error / warn / info)
+    #[serde(default = "default_severity")]
+    pub severity: String,
+    #[serde(default)]
+    pub action: RuleAction,
+    // 在 windowSecs 秒内匹配达到 threshold 次才触发
+    #[serde(default = "default_threshold")]
+    pub threshold: u32,
+    #[serde(rename = "windowSecs", default = "default_window_secs")]
+    pub window_secs: u64,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_severity() -> String {
+    "error".to_string()
+}
+
+fn default_threshold() -> u32 {
+    1
+}
+
+fn default_window_secs() -> u64 {
+    60
+}
+
+// 默认规则，与之前硬编码的 "ERROR Resolve failed" 检测一致
+pub fn default_rules() -> Vec<FatalRule> {
+    vec![FatalRule {
+        id: "resolve-failed".to_string(),
+        name: "地址解析失败".to_string(),
+        enabled: true,
+        match_kind: MatchKind::Substring,
+        pattern: "resolve failed".to_string(),
+        case_sensitive: false,
+        stream: RuleStream::Any,
+        level: Some("error".to_string()),
+        severity: default_severity(),
+        action: RuleAction::Stop,
+        threshold: default_threshold(),
+        window_secs: default_window_secs(),
+    }]
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RuleHit {
+    #[serde(rename = "ruleId")]
+    pub rule_id: String,
+    #[serde(rename = "ruleName")]
+    pub rule_name: String,
+    pub action: RuleAction,
+    pub severity: String,
+    // 窗口内的匹配次数
+    pub occurrences: u32,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RuleTestResult {
+    pub line: String,
+    pub record: LogRecord,
+    // 匹配到的规则ID（未必达到阈值）
+    pub matched: Vec<String>,
+    pub triggered: Vec<RuleHit>,
+}
+
+struct CompiledRule {
+    rule: FatalRule,
+    regex: Option<Regex>,
+    needle: String,
+}
+
+impl CompiledRule {
+    fn compile(rule: FatalRule) -> Result<Self, String> {
+        let regex = match rule.match_kind {
+            MatchKind::Regex => Some(
+                RegexBuilder::new(&rule.pattern)
+                    .case_insensitive(!rule.case_sensitive)
+                    .build()
+                    .map_err(|e| format!("规则 {} 的正则表达式无效: {}", rule.id, e))?,
+            ),
+            MatchKind::Substring => None,
+        };
+        let needle = if rule.case_sensitive {
+            rule.pattern.clone()
+        } else {
+            rule.pattern.to_lowercase()
+        };
+        Ok(Self { rule, regex, needle })
+    }
+
+    fn matches(&self, record: &LogRecord, text: &str) -> bool {
+        if !self.rule.enabled {
+            return false;
+        }
+        let stream_ok = match self.rule.stream {
+            RuleStream::Any => true,
+            RuleStream::Stdout => record.stream == "stdout",
+            RuleStream::Stderr => record.stream == "stderr",
+        };
+        let level_ok = self
+            .rule
+            .level
+            .as_ref()
+            .is_none_or(|level| level.is_empty() || level.eq_ignore_ascii_case(&record.level));
+        if !stream_ok || !level_ok {
+            return false;
+        }
+
+        match &self.regex {
+            Some(regex) => regex.is_match(text),
+            None if self.rule.case_sensitive => text.contains(&self.needle),
+            None => text.to_lowercase().contains(&self.needle),
+        }
+    }
+}
+
+fn compile_rules(rules: Vec<FatalRule>) -> Result<Vec<CompiledRule>, String> {
+    rules.into_iter().map(CompiledRule::compile).collect()
+}
+
+// 记录每个 (隧道, 规则) 在时间窗口内的匹配时间
+#[derive(Default)]
+struct Occurrences(HashMap<(String, String), VecDeque<Instant>>);
+
+impl Occurrences {
+    fn evaluate(
+        &mut self,
+        rules: &[CompiledRule],
+        tunnel_id: &str,
+        record: &LogRecord,
+        text: &str,
+        now: Instant,
+    ) -> (Vec<String>, Vec<RuleHit>) {
+        let mut matched = Vec::new();
+        let mut triggered = Vec::new();
+
+        for compiled in rules {
+            if !compiled.matches(record, text) {
+                continue;
+            }
+            let rule = &compiled.rule;
+            matched.push(rule.id.clone());
+
+            let window = Duration::from_secs(rule.window_secs);
+            let times = self
+                .0
+                .entry((tunnel_id.to_string(), rule.id.clone()))
+                .or_default();
+            times.push_back(now);
+            while times
+                .front()
+                .is_some_and(|first| now.duration_since(*first) > window)
+            {
+                times.pop_front();
+            }
+
+            let occurrences = times.len() as u32;
+            if occurrences >= rule.threshold.max(1) {
+                // 触发后重新计数，避免每一行都重复触发
+                times.clear();
+                triggered.push(RuleHit {
+                    rule_id: rule.id.clone(),
+                    rule_name: rule.name.clone(),
+                    action: rule.action,
+                    severity: rule.severity.clone(),
+                    occurrences,
+                });
+            }
+        }
+
+        (matched, triggered)
+    }
+}
+
+struct RuleEngine {
+    rules: Vec<CompiledRule>,
+    occurrences: Occurrences,
+}
+
+#[derive(Clone)]
+pub struct FatalRuleStore {
+    rules_file: PathBuf,
+    engine: Arc<Mutex<RuleEngine>>,
+}
+
+impl FatalRuleStore {
+    // 从磁盘加载规则，文件不存在或无效时使用默认规则
+    pub fn load(rules_file: PathBuf) -> Self {
+        let rules = fs::read_to_string(&rules_file)
+            .ok()
+            .and_then(|content| serde_json::from_str::<Vec<FatalRule>>(&content).ok())
+            .unwrap_or_else(default_rules);
+
+        let compiled = compile_rules(rules).unwrap_or_else(|e| {
+            println!("加载致命错误规则失败: {}，使用默认规则", e);
+            compile_rules(default_rules()).unwrap_or_default()
+        });
+
+        Self {
+            rules_file,
+            engine: Arc::new(Mutex::new(RuleEngine {
+                rules: compiled,
+                occurrences: Occurrences::default(),
+            })),
+        }
+    }
+
+    pub fn rules(&self) -> Vec<FatalRule> {
+        self.engine
+            .lock()
+            .map(|engine| engine.rules.iter().map(|c| c.rule.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    // 校验并保存规则，重置计数
+    pub fn update(&self, rules: Vec<FatalRule>) -> Result<(), String> {
+        let compiled = compile_rules(rules.clone())?;
+
+        let content = serde_json::to_string_pretty(&rules)
+            .map_err(|e| format!("序列化致命错误规则失败: {}", e))?;
+        fs::write(&self.rules_file, content)
+            .map_err(|e| format!("保存致命错误规则失败: {}", e))?;
+
+        let mut engine = self
+            .engine
+            .lock()
+            .map_err(|_| "致命错误规则状态已损坏".to_string())?;
+        engine.rules = compiled;
+        engine.occurrences = Occurrences::default();
+        Ok(())
+    }
+
+    // 用当前规则评估一行日志，返回达到阈值的规则
+    pub fn evaluate(&self, tunnel_id: &str, record: &LogRecord, text: &str) -> Vec<RuleHit> {
+        match self.engine.lock() {
+            Ok(mut engine) => {
+                let RuleEngine { rules, occurrences } = &mut *engine;
+                occurrences
+                    .evaluate(rules, tunnel_id, record, text, Instant::now())
+                    .1
+            }
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+// 用给定规则（为空时用当前规则）依次评估样例日志，不影响运行中的计数
+pub fn test_rules(
+    rules: Vec<FatalRule>,
+    lines: Vec<String>,
+) -> Result<Vec<RuleTestResult>, String> {
+    let compiled = compile_rules(rules)?;
+    let mut occurrences = Occurrences::default();
+    let now = Instant::now();
+
+    Ok(lines
+        .into_iter()
+        .map(|line| {
+            // 以 "stderr:" 开头的样例视为来自 stderr
+            let (raw, from_stderr) = match line.strip_prefix("stderr:") {
+                Some(rest) => (rest.trim_start().to_string(), true),
+                None => (line.clone(), false),
+            };
+            let record = crate::log_parser::parse_line(&raw, from_stderr);
+            let text = crate::log_parser::strip_ansi(&raw);
+            let (matched, triggered) =
+                occurrences.evaluate(&compiled, "test", &record, &text, now);
+            RuleTestResult {
+                line,
+                record,
+                matched,
+                triggered,
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_parser::parse_line;
+
+    const LINE: &str = "2025-01-01 00:00:00.000 ERROR Resolve failed: lookup example.com";
+
+    fn rules(threshold: u32, window_secs: u64) -> Vec<CompiledRule> {
+        let rule = FatalRule {
+            threshold,
+            window_secs,
+            ..default_rules().remove(0)
+        };
+        compile_rules(vec![rule]).unwrap()
+    }
+
+    // 在 start 之后 offset_secs 秒评估一行日志，返回触发时的匹配次数
+    fn hit(
+        occurrences: &mut Occurrences,
+        rules: &[CompiledRule],
+        tunnel_id: &str,
+        start: Instant,
+        offset_secs: u64,
+    ) -> Option<u32> {
+        let record = parse_line(LINE, false);
+        let now = start + Duration::from_secs(offset_secs);
+        let (matched, triggered) = occurrences.evaluate(rules, tunnel_id, &record, LINE, now);
+        assert_eq!(matched, ["resolve-failed"]);
+        triggered.first().map(|hit| hit.occurrences)
+    }
+
+    #[test]
+    fn below_threshold_does_not_trigger() {
+        let rules = rules(3, 60);
+        let mut occurrences = Occurrences::default();
+        let start = Instant::now();
+        assert_eq!(hit(&mut occurrences, &rules, "a", start, 0), None);
+        assert_eq!(hit(&mut occurrences, &rules, "a", start, 10), None);
+    }
+
+    #[test]
+    fn triggers_at_threshold_inside_window() {
+        let rules = rules(3, 60);
+        let mut occurrences = Occurrences::default();
+        let start = Instant::now();
+        assert_eq!(hit(&mut occurrences, &rules, "a", start, 0), None);
+        assert_eq!(hit(&mut occurrences, &rules, "a", start, 30), None);
+        assert_eq!(hit(&mut occurrences, &rules, "a", start, 60), Some(3));
+    }
+
+    #[test]
+    fn hits_outside_window_expire() {
+        let rules = rules(3, 60);
+        let mut occurrences = Occurrences::default();
+        let start = Instant::now();
+        assert_eq!(hit(&mut occurrences, &rules, "a", start, 0), None);
+        assert_eq!(hit(&mut occurrences, &rules, "a", start, 50), None);
+        // 第一次匹配已超出窗口
+        assert_eq!(hit(&mut occurrences, &rules, "a", start, 70), None);
+        assert_eq!(hit(&mut occurrences, &rules, "a", start, 100), Some(3));
+    }
+
+    #[test]
+    fn trigger_resets_only_that_tunnel() {
+        let rules = rules(2, 60);
+        let mut occurrences = Occurrences::default();
+        let start = Instant::now();
+        assert_eq!(hit(&mut occurrences, &rules, "a", start, 0), None);
+        assert_eq!(hit(&mut occurrences, &rules, "b", start, 1), None);
+        assert_eq!(hit(&mut occurrences, &rules, "a", start, 2), Some(2));
+
+        // a 重新计数，b 的计数保留
+        assert_eq!(hit(&mut occurrences, &rules, "a", start, 3), None);
+        assert_eq!(hit(&mut occurrences, &rules, "b", start, 4), Some(2));
+        assert_eq!(hit(&mut occurrences, &rules, "a", start, 5), Some(2));
+    }
+
+    #[test]
+    fn non_matching_lines_are_ignored() {
+        let rules = rules(1, 60);
+        let mut occurrences = Occurrences::default();
+        let line = "2025-01-01 00:00:00.000 INFO Resolve failed once, retrying";
+        let record = parse_line(line, false);
+        let (matched, triggered) =
+            occurrences.evaluate(&rules, "a", &record, line, Instant::now());
+        assert!(matched.is_empty());
+        assert!(triggered.is_empty());
+    }
+}
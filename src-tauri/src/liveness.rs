@@ -0,0 +1,280 @@
+// 跨平台进程存活检测：Linux 使用 pidfd，Windows 使用进程句柄等待，其它平台轮询
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+// 轮询方式检测时的间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+pub trait ProcessLiveness: Send + Sync {
+    // 进程是否仍在运行
+    fn is_alive(&self, pid: u32) -> bool;
+
+    // 等待进程退出，适用于非本进程创建的子进程
+    fn wait_for_exit(&self, pid: u32) -> BoxFuture<()>;
+
+    // 请求进程退出；force 为 true 时强制结束
+    fn terminate(&self, pid: u32, force: bool) -> Result<(), String>;
+}
+
+// 获取当前平台的实现
+pub fn platform() -> Arc<dyn ProcessLiveness> {
+    #[cfg(target_os = "linux")]
+    {
+        Arc::new(unix::PidfdLiveness)
+    }
+
+    #[cfg(all(unix, not(target_os = "linux")))]
+    {
+        Arc::new(unix::PollingLiveness)
+    }
+
+    #[cfg(windows)]
+    {
+        Arc::new(windows_impl::HandleLiveness)
+    }
+}
+
+#[cfg(unix)]
+mod unix {
+    use super::{BoxFuture, ProcessLiveness, POLL_INTERVAL};
+
+    fn pid_alive(pid: u32) -> bool {
+        // 信号0只做权限与存在性检查；EPERM 说明进程存在但属于其他用户
+        let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+        result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+    }
+
+    fn send_signal(pid: u32, force: bool) -> Result<(), String> {
+        let signal = if force { libc::SIGKILL } else { libc::SIGTERM };
+        let result = unsafe { libc::kill(pid as libc::pid_t, signal) };
+        if result == 0 {
+            return Ok(());
+        }
+
+        let error = std::io::Error::last_os_error();
+        if error.raw_os_error() == Some(libc::ESRCH) {
+            // 进程已不存在
+            Ok(())
+        } else {
+            Err(format!("向进程 {} 发送信号失败: {}", pid, error))
+        }
+    }
+
+    async fn poll_until_exit(pid: u32) {
+        while pid_alive(pid) {
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    pub struct PidfdLiveness;
+
+    #[cfg(target_os = "linux")]
+    impl PidfdLiveness {
+        // 通过 pidfd 等待进程退出，内核不支持时回退到轮询
+        async fn wait_pidfd(pid: u32) {
+            use std::os::fd::{FromRawFd, OwnedFd};
+            use tokio::io::unix::AsyncFd;
+
+            let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid as libc::pid_t, 0) };
+            if fd < 0 {
+                let error = std::io::Error::last_os_error();
+                if error.raw_os_error() != Some(libc::ESRCH) {
+                    println!("pidfd_open({}) 失败: {}，改用轮询检测", pid, error);
+                    poll_until_exit(pid).await;
+                }
+                return;
+            }
+
+            let fd = unsafe { OwnedFd::from_raw_fd(fd as i32) };
+            match AsyncFd::new(fd) {
+                // 进程退出时 pidfd 变为可读
+                Ok(async_fd) => {
+                    let _ = async_fd.readable().await;
+                }
+                Err(e) => {
+                    println!("注册pidfd失败: {}，改用轮询检测", e);
+                    poll_until_exit(pid).await;
+                }
+            }
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    impl ProcessLiveness for PidfdLiveness {
+        fn is_alive(&self, pid: u32) -> bool {
+            pid_alive(pid)
+        }
+
+        fn wait_for_exit(&self, pid: u32) -> BoxFuture<()> {
+            Box::pin(Self::wait_pidfd(pid))
+        }
+
+        fn terminate(&self, pid: u32, force: bool) -> Result<(), String> {
+            send_signal(pid, force)
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub struct PollingLiveness;
+
+    #[cfg(not(target_os = "linux"))]
+    impl ProcessLiveness for PollingLiveness {
+        fn is_alive(&self, pid: u32) -> bool {
+            pid_alive(pid)
+        }
+
+        fn wait_for_exit(&self, pid: u32) -> BoxFuture<()> {
+            Box::pin(poll_until_exit(pid))
+        }
+
+        fn terminate(&self, pid: u32, force: bool) -> Result<(), String> {
+            send_signal(pid, force)
+        }
+    }
+}
+
+#[cfg(windows)]
+mod windows_impl {
+    use super::{BoxFuture, ProcessLiveness};
+    use windows::Win32::Foundation::{CloseHandle, HANDLE, WAIT_TIMEOUT};
+    use windows::Win32::System::Threading::{
+        OpenProcess, TerminateProcess, WaitForSingleObject, INFINITE, PROCESS_ACCESS_RIGHTS,
+        PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_SYNCHRONIZE, PROCESS_TERMINATE,
+    };
+
+    // 自动关闭的进程句柄
+    struct ProcessHandle(HANDLE);
+
+    impl ProcessHandle {
+        fn open(pid: u32, access: PROCESS_ACCESS_RIGHTS) -> Option<Self> {
+            unsafe { OpenProcess(access, false, pid) }.ok().map(ProcessHandle)
+        }
+    }
+
+    impl Drop for ProcessHandle {
+        fn drop(&mut self) {
+            unsafe {
+                let _ = CloseHandle(self.0);
+            }
+        }
+    }
+
+    pub struct HandleLiveness;
+
+    impl ProcessLiveness for HandleLiveness {
+        fn is_alive(&self, pid: u32) -> bool {
+            match ProcessHandle::open(pid, PROCESS_SYNCHRONIZE | PROCESS_QUERY_LIMITED_INFORMATION) {
+                Some(handle) => unsafe { WaitForSingleObject(handle.0, 0) == WAIT_TIMEOUT },
+                None => false,
+            }
+        }
+
+        fn wait_for_exit(&self, pid: u32) -> BoxFuture<()> {
+            Box::pin(async move {
+                let handle = match ProcessHandle::open(pid, PROCESS_SYNCHRONIZE) {
+                    Some(handle) => handle,
+                    None => return,
+                };
+                // 句柄等待是阻塞调用，放到阻塞线程池中执行
+                let _ = tokio::task::spawn_blocking(move || unsafe {
+                    WaitForSingleObject(handle.0, INFINITE);
+                    drop(handle);
+                })
+                .await;
+            })
+        }
+
+        fn terminate(&self, pid: u32, force: bool) -> Result<(), String> {
+            if !force {
+                return send_ctrl_break(pid);
+            }
+
+            let handle = match ProcessHandle::open(pid, PROCESS_TERMINATE) {
+                Some(handle) => handle,
+                None => return Ok(()),
+            };
+            let terminated = unsafe { TerminateProcess(handle.0, 1) }.as_bool();
+            if terminated {
+                Ok(())
+            } else {
+                Err(format!(
+                    "结束进程 {} 失败: {}",
+                    pid,
+                    std::io::Error::last_os_error()
+                ))
+            }
+        }
+    }
+
+    // 进程只能附加一个控制台，并发停止隧道时附加/发送/释放必须串行执行
+    static CONSOLE_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    // 向进程组发送 CTRL_BREAK；隧道进程以 CREATE_NEW_PROCESS_GROUP 启动，进程组ID即为PID。
+    // GUI进程本身没有控制台，需要临时附加到目标进程的控制台上才能发送事件
+    fn send_ctrl_break(pid: u32) -> Result<(), String> {
+        use windows::Win32::System::Console::{
+            AttachConsole, FreeConsole, GenerateConsoleCtrlEvent, SetConsoleCtrlHandler,
+            CTRL_BREAK_EVENT,
+        };
+
+        let _guard = CONSOLE_LOCK
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        unsafe {
+            let _ = FreeConsole();
+            if !AttachConsole(pid).as_bool() {
+                return Err(format!(
+                    "附加到进程控制台失败: {}",
+                    std::io::Error::last_os_error()
+                ));
+            }
+            // 忽略自身收到的控制台事件
+            let _ = SetConsoleCtrlHandler(None, true);
+            let sent = GenerateConsoleCtrlEvent(CTRL_BREAK_EVENT, pid).as_bool();
+            // 在释放控制台前读取错误码
+            let result = if sent {
+                Ok(())
+            } else {
+                Err(format!(
+                    "发送CTRL_BREAK失败: {}",
+                    std::io::Error::last_os_error()
+                ))
+            };
+            let _ = FreeConsole();
+            let _ = SetConsoleCtrlHandler(None, false);
+            result
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn detects_exit_of_terminated_process() {
+        let liveness = platform();
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let pid = child.id();
+        assert!(liveness.is_alive(pid));
+
+        liveness.terminate(pid, false).unwrap();
+        tokio::time::timeout(Duration::from_secs(5), liveness.wait_for_exit(pid))
+            .await
+            .expect("进程退出后应结束等待");
+        // 回收僵尸进程后不再视为存活
+        child.wait().unwrap();
+        assert!(!liveness.is_alive(pid));
+        // 已退出的进程再次结束不报错
+        assert!(liveness.terminate(pid, true).is_ok());
+    }
+}
@@ -0,0 +1,185 @@
+// NodePass 日志解析：去除颜色代码，拆分出时间、级别、组件、消息和键值字段
+use regex::Regex;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+// 组件名最大长度，超过时视为普通消息
+const MAX_COMPONENT_LEN: usize = 40;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct LogRecord {
+    // NodePass 输出的时间，缺失时为空
+    pub timestamp: Option<String>,
+    pub level: String,
+    pub component: Option<String>,
+    pub message: String,
+    // key=value 形式的字段
+    pub fields: BTreeMap<String, String>,
+    // 消息中出现的 host:port 地址
+    pub addresses: Vec<String>,
+    // 来源输出流 (stdout / stderr)
+    pub stream: String,
+}
+
+impl LogRecord {
+    // 与旧版日志格式兼容的文本，例如 "[INFO] Client started: ..."
+    pub fn display_line(&self) -> String {
+        match &self.component {
+            Some(component) => format!("[{}] {}: {}", self.level.to_uppercase(), component, self.message),
+            None => format!("[{}] {}", self.level.to_uppercase(), self.message),
+        }
+    }
+}
+
+fn ansi_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\x1b\[[0-9;?]*[ -/]*[@-~]").unwrap())
+}
+
+fn header_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    // 例如: 2025-01-01 12:00:00.000 INFO  Client started: ...
+    // 级别后必须是单词边界，避免 "Information"、"Errors" 等开头的消息被当成级别
+    RE.get_or_init(|| {
+        Regex::new(
+            r"^(?P<ts>\d{4}[-/]\d{2}[-/]\d{2}[ T]\d{2}:\d{2}:\d{2}(?:\.\d+)?(?:Z|[+-]\d{2}:?\d{2})?)?\s*(?:(?P<level>(?i:DEBUG|INFO|WARN|WARNING|ERROR|FATAL|EVENT))\b)?\s*(?P<rest>.*)$",
+        )
+        .unwrap()
+    })
+}
+
+fn field_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"\b(?P<key>[A-Za-z_][\w.]*)=(?P<value>"[^"]*"|\S+)"#).unwrap())
+}
+
+fn address_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"(?:\[[0-9A-Fa-f:.]+\]|[A-Za-z0-9](?:[A-Za-z0-9.\-]*[A-Za-z0-9])?):\d{1,5}\b").unwrap()
+    })
+}
+
+pub fn strip_ansi(line: &str) -> String {
+    ansi_regex().replace_all(line, "").to_string()
+}
+
+// 解析一行输出；from_stderr 仅在行内没有级别时用于推断级别
+pub fn parse_line(raw: &str, from_stderr: bool) -> LogRecord {
+    let line = strip_ansi(raw);
+    let line = line.trim_end();
+    let stream = if from_stderr { "stderr" } else { "stdout" }.to_string();
+
+    let captures = header_regex().captures(line);
+    let (timestamp, level, rest) = match &captures {
+        Some(captures) => (
+            captures.name("ts").map(|m| m.as_str().to_string()),
+            captures.name("level").map(|m| normalize_level(m.as_str())),
+            captures.name("rest").map(|m| m.as_str()).unwrap_or(""),
+        ),
+        None => (None, None, line),
+    };
+    let level = level.unwrap_or_else(|| if from_stderr { "error" } else { "info" }.to_string());
+
+    // "Tunnel connection: ..." 中冒号前的部分作为组件名
+    let (component, message) = match rest.split_once(": ") {
+        Some((head, tail))
+            if !head.is_empty()
+                && head.len() <= MAX_COMPONENT_LEN
+                && head.chars().all(|c| c.is_alphabetic() || c == ' ' || c == '-' || c == '_') =>
+        {
+            (Some(head.to_string()), tail.to_string())
+        }
+        _ => (None, rest.to_string()),
+    };
+
+    let fields = field_regex()
+        .captures_iter(&message)
+        .map(|captures| {
+            (
+                captures["key"].to_string(),
+                captures["value"].trim_matches('"').to_string(),
+            )
+        })
+        .collect();
+
+    let addresses = address_regex()
+        .find_iter(&message)
+        // 时间戳形如 12:00:00，不是地址
+        .filter(|m| !m.as_str().chars().all(|c| c.is_ascii_digit() || c == ':'))
+        .map(|m| m.as_str().to_string())
+        .collect();
+
+    LogRecord {
+        timestamp,
+        level,
+        component,
+        message,
+        fields,
+        addresses,
+        stream,
+    }
+}
+
+fn normalize_level(level: &str) -> String {
+    match level.to_ascii_lowercase().as_str() {
+        "warning" => "warn".to_string(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_header_component_and_fields() {
+        let record = parse_line(
+            "\x1b[32m2025-01-01 12:00:00.000 INFO  Client started: server=example.com:10101 target=127.0.0.1:8080\x1b[0m",
+            false,
+        );
+        assert_eq!(record.timestamp.as_deref(), Some("2025-01-01 12:00:00.000"));
+        assert_eq!(record.level, "info");
+        assert_eq!(record.component.as_deref(), Some("Client started"));
+        assert_eq!(record.fields["server"], "example.com:10101");
+        assert_eq!(record.fields["target"], "127.0.0.1:8080");
+        assert_eq!(record.addresses, ["example.com:10101", "127.0.0.1:8080"]);
+        assert_eq!(record.stream, "stdout");
+    }
+
+    #[test]
+    fn level_requires_word_boundary() {
+        let record = parse_line("Information: tunnel ready", false);
+        assert_eq!(record.level, "info");
+        assert_eq!(record.component.as_deref(), Some("Information"));
+        assert_eq!(record.message, "tunnel ready");
+
+        let record = parse_line("Errors detected in pool", false);
+        assert_eq!(record.level, "info");
+        assert_eq!(record.message, "Errors detected in pool");
+
+        let record = parse_line("2025-01-01 12:00:00 Warningless message", true);
+        assert_eq!(record.level, "error");
+        assert_eq!(record.message, "Warningless message");
+    }
+
+    #[test]
+    fn normalizes_levels() {
+        assert_eq!(parse_line("WARNING Pool low", false).level, "warn");
+        assert_eq!(parse_line("warn Pool low", false).level, "warn");
+        assert_eq!(parse_line("ERROR Resolve failed", false).level, "error");
+        assert_eq!(parse_line("ERROR Resolve failed", false).message, "Resolve failed");
+        // 没有级别时按输出流推断
+        assert_eq!(parse_line("plain output", true).level, "error");
+        assert_eq!(parse_line("plain output", false).level, "info");
+    }
+
+    #[test]
+    fn display_line_matches_legacy_format() {
+        let record = parse_line("2025-01-01 12:00:00 ERROR Tunnel closed: EOF", false);
+        assert_eq!(record.display_line(), "[ERROR] Tunnel closed: EOF");
+        let record = parse_line("DEBUG heartbeat", false);
+        assert_eq!(record.display_line(), "[DEBUG] heartbeat");
+    }
+}
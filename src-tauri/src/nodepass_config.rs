@@ -0,0 +1,396 @@
+// NodePass 隧道配置及启动URL的生成与解析
+use crate::config_validation;
+use indexmap::IndexMap;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+use serde::{Deserialize, Serialize};
+
+// 查询参数值中需要转义的字符；保留路径分隔符与盘符冒号，便于阅读
+const QUERY_VALUE: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'&')
+    .add(b'+')
+    .add(b'<')
+    .add(b'=')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+// 地址部分需要转义的字符，例如 IPv6 区域标识中的 %
+const ADDRESS: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+// 已有专用字段的参数名，不允许出现在 extra_params 中
+pub const KNOWN_PARAMS: &[&str] = &[
+    "log", "tls", "crt", "key", "min", "max", "mode", "read", "rate", "slot", "proxy", "notcp",
+    "noudp",
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct NodePassConfig {
+    pub mode: String,
+    #[serde(rename = "tunnelAddr")]
+    pub tunnel_addr: String,
+    #[serde(rename = "targetAddr")]
+    pub target_addr: String,
+    #[serde(rename = "logLevel")]
+    pub log_level: String,
+    #[serde(rename = "tlsMode")]
+    pub tls_mode: String,
+    #[serde(rename = "certFile")]
+    pub cert_file: Option<String>,
+    #[serde(rename = "keyFile")]
+    pub key_file: Option<String>,
+    // 连接池最小/最大容量 (min / max)
+    #[serde(rename = "minPool", default, skip_serializing_if = "Option::is_none")]
+    pub min_pool: Option<u32>,
+    #[serde(rename = "maxPool", default, skip_serializing_if = "Option::is_none")]
+    pub max_pool: Option<u32>,
+    // 运行模式 (mode)：0 自动，1 强制反向/单端，2 强制正向/双端
+    #[serde(rename = "runMode", default, skip_serializing_if = "Option::is_none")]
+    pub run_mode: Option<u8>,
+    // 读取超时 (read)，例如 "30s"、"10m"
+    #[serde(rename = "readTimeout", default, skip_serializing_if = "Option::is_none")]
+    pub read_timeout: Option<String>,
+    // 带宽限制 (rate)，单位 Mbps
+    #[serde(rename = "rateLimit", default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<u32>,
+    // 最大连接数 (slot)
+    #[serde(rename = "slotLimit", default, skip_serializing_if = "Option::is_none")]
+    pub slot_limit: Option<u32>,
+    // 是否发送 PROXY protocol 头 (proxy)
+    #[serde(rename = "proxyProtocol", default, skip_serializing_if = "Option::is_none")]
+    pub proxy_protocol: Option<bool>,
+    // 禁用 TCP / UDP 转发 (notcp / noudp)
+    #[serde(rename = "disableTcp", default, skip_serializing_if = "Option::is_none")]
+    pub disable_tcp: Option<bool>,
+    #[serde(rename = "disableUdp", default, skip_serializing_if = "Option::is_none")]
+    pub disable_udp: Option<bool>,
+    // 其它参数，按原顺序保留，便于支持新版本 NodePass 增加的参数
+    #[serde(rename = "extraParams", default, skip_serializing_if = "IndexMap::is_empty")]
+    pub extra_params: IndexMap<String, String>,
+}
+
+fn bool_param(value: bool) -> String {
+    if value { "1" } else { "0" }.to_string()
+}
+
+fn parse_bool_param(key: &str, value: &str) -> Result<bool, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "1" | "true" => Ok(true),
+        "0" | "false" => Ok(false),
+        _ => Err(format!("参数 {} 的值无效: {}", key, value)),
+    }
+}
+
+fn parse_number_param<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("参数 {} 需要是数字: {}", key, value))
+}
+
+// 与 Go 的 url.ParseQuery 一致：'+' 视为空格，其余按百分号编码解码
+fn decode_query_component(value: &str) -> Result<String, String> {
+    percent_decode_str(&value.replace('+', " "))
+        .decode_utf8()
+        .map(|decoded| decoded.to_string())
+        .map_err(|_| format!("参数不是有效的UTF-8: {}", value))
+}
+
+// 地址部分按百分号编码解码，'+' 保持原样
+fn decode_address(value: &str) -> Result<String, String> {
+    percent_decode_str(value)
+        .decode_utf8()
+        .map(|decoded| decoded.to_string())
+        .map_err(|_| format!("地址不是有效的UTF-8: {}", value))
+}
+
+// 检查 host:port 形式的地址，host 可以为空或是带方括号的 IPv6 地址
+pub fn validate_host_port(addr: &str, label: &str) -> Result<(), String> {
+    let (host, port) = if let Some(rest) = addr.strip_prefix('[') {
+        let (host, port) = rest
+            .split_once(']')
+            .ok_or_else(|| format!("{}的IPv6地址缺少右方括号: {}", label, addr))?;
+        let port = port
+            .strip_prefix(':')
+            .ok_or_else(|| format!("{}缺少端口: {}", label, addr))?;
+        // 链路本地地址可以带区域标识，例如 fe80::1%eth0
+        let address = host.split_once('%').map_or(host, |(address, _)| address);
+        if address.parse::<std::net::Ipv6Addr>().is_err() {
+            return Err(format!("{}的IPv6地址无效: {}", label, addr));
+        }
+        (host, port)
+    } else {
+        let (host, port) = addr
+            .rsplit_once(':')
+            .ok_or_else(|| format!("{}缺少端口: {}", label, addr))?;
+        if host.contains(':') {
+            return Err(format!("{}中的IPv6地址需要用方括号括起来: {}", label, addr));
+        }
+        (host, port)
+    };
+
+    if host.contains(char::is_whitespace) {
+        return Err(format!("{}的主机名无效: {}", label, addr));
+    }
+    match port.parse::<u16>() {
+        Ok(port) if port > 0 => Ok(()),
+        _ => Err(format!("{}的端口无效: {}", label, addr)),
+    }
+}
+
+impl NodePassConfig {
+    // 按顺序生成查询参数 (名称, 值)
+    pub fn query_params(&self) -> Vec<(String, String)> {
+        let mut params = vec![("log".to_string(), self.log_level.clone())];
+
+        // 客户端省略 tls 参数时按 0 处理，只在设置了其它值时输出
+        if self.mode == "client" {
+            if self.tls_mode != "0" {
+                params.push(("tls".to_string(), self.tls_mode.clone()));
+            }
+        } else {
+            params.push(("tls".to_string(), self.tls_mode.clone()));
+
+            // 与解析结果保持一致，TLS 模式不是 2 时 NodePass 会忽略证书参数
+            if let Some(cert) = self.cert_file.as_ref().filter(|cert| !cert.is_empty()) {
+                params.push(("crt".to_string(), cert.clone()));
+            }
+            if let Some(key) = self.key_file.as_ref().filter(|key| !key.is_empty()) {
+                params.push(("key".to_string(), key.clone()));
+            }
+        }
+
+        let typed = [
+            ("min", self.min_pool.map(|v| v.to_string())),
+            ("max", self.max_pool.map(|v| v.to_string())),
+            ("mode", self.run_mode.map(|v| v.to_string())),
+            ("read", self.read_timeout.clone()),
+            ("rate", self.rate_limit.map(|v| v.to_string())),
+            ("slot", self.slot_limit.map(|v| v.to_string())),
+            ("proxy", self.proxy_protocol.map(bool_param)),
+            ("notcp", self.disable_tcp.map(bool_param)),
+            ("noudp", self.disable_udp.map(bool_param)),
+        ];
+        for (name, value) in typed {
+            if let Some(value) = value {
+                params.push((name.to_string(), value));
+            }
+        }
+
+        for (key, value) in &self.extra_params {
+            params.push((key.clone(), value.clone()));
+        }
+
+        params
+    }
+
+    // 解析 NodePass 启动URL，例如 server://0.0.0.0:10101/127.0.0.1:8080?log=debug&tls=1
+    pub fn from_url(url: &str) -> Result<Self, String> {
+        let url = url.trim();
+        let (mode, rest) = url
+            .split_once("://")
+            .ok_or_else(|| format!("不是有效的NodePass地址: {}", url))?;
+        let mode = mode.to_ascii_lowercase();
+        if mode != "server" && mode != "client" {
+            return Err(format!("不支持的隧道模式: {}", mode));
+        }
+
+        // 去掉片段，拆分地址与查询参数
+        let rest = rest.split('#').next().unwrap_or_default();
+        let (addresses, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let (tunnel_addr, target_addr) = addresses.split_once('/').unwrap_or((addresses, ""));
+        let tunnel_addr = decode_address(tunnel_addr)?;
+        let target_addr = decode_address(target_addr)?;
+        let (tunnel_addr, target_addr) = (tunnel_addr.as_str(), target_addr.as_str());
+
+        validate_host_port(tunnel_addr, "隧道地址")?;
+        if target_addr.is_empty() {
+            // 客户端可以只指定隧道地址
+            if mode == "server" {
+                return Err("服务端模式需要目标地址".to_string());
+            }
+        } else {
+            validate_host_port(target_addr, "目标地址")?;
+        }
+
+        let mut config = NodePassConfig {
+            mode,
+            tunnel_addr: tunnel_addr.to_string(),
+            target_addr: target_addr.to_string(),
+            log_level: "info".to_string(),
+            tls_mode: "0".to_string(),
+            cert_file: None,
+            key_file: None,
+            min_pool: None,
+            max_pool: None,
+            run_mode: None,
+            read_timeout: None,
+            rate_limit: None,
+            slot_limit: None,
+            proxy_protocol: None,
+            disable_tcp: None,
+            disable_udp: None,
+            extra_params: IndexMap::new(),
+        };
+
+        // 与 NodePass 使用的 url.Query().Get 一致，重复的参数只取第一个值
+        let mut seen = std::collections::HashSet::new();
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let key = decode_query_component(key)?;
+            let value = decode_query_component(value)?;
+            if !seen.insert(key.clone()) {
+                continue;
+            }
+
+            match key.as_str() {
+                "log" => config.log_level = value,
+                "tls" => config.tls_mode = value,
+                "crt" => config.cert_file = Some(value),
+                "key" => config.key_file = Some(value),
+                "min" => config.min_pool = Some(parse_number_param(&key, &value)?),
+                "max" => config.max_pool = Some(parse_number_param(&key, &value)?),
+                "mode" => config.run_mode = Some(parse_number_param(&key, &value)?),
+                "read" => config.read_timeout = Some(value),
+                "rate" => config.rate_limit = Some(parse_number_param(&key, &value)?),
+                "slot" => config.slot_limit = Some(parse_number_param(&key, &value)?),
+                "proxy" => config.proxy_protocol = Some(parse_bool_param(&key, &value)?),
+                "notcp" => config.disable_tcp = Some(parse_bool_param(&key, &value)?),
+                "noudp" => config.disable_udp = Some(parse_bool_param(&key, &value)?),
+                // 未知参数原样保留
+                _ => {
+                    config.extra_params.insert(key, value);
+                }
+            }
+        }
+
+        config_validation::check_params(&config)?;
+        Ok(config)
+    }
+
+    // 生成 NodePass 启动URL，例如 server://0.0.0.0:10101/127.0.0.1:8080?log=info&tls=1
+    pub fn to_url(&self) -> Result<String, String> {
+        config_validation::check_params(self)?;
+
+        let mut url = format!(
+            "{}://{}/{}",
+            self.mode,
+            utf8_percent_encode(&self.tunnel_addr, ADDRESS),
+            utf8_percent_encode(&self.target_addr, ADDRESS)
+        );
+
+        let query: Vec<String> = self
+            .query_params()
+            .into_iter()
+            .map(|(key, value)| format!("{}={}", key, utf8_percent_encode(&value, QUERY_VALUE)))
+            .collect();
+        if !query.is_empty() {
+            url.push('?');
+            url.push_str(&query.join("&"));
+        }
+
+        Ok(url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 按 to_url 的参数顺序书写的地址，解析后再生成应与原文一致
+    const CANONICAL_URLS: &[&str] = &[
+        "server://0.0.0.0:10101/127.0.0.1:8080?log=debug&tls=1",
+        "server://:10101/[::1]:8080?log=warn&tls=2&crt=/etc/nodepass/cert.pem&key=/etc/nodepass/key.pem",
+        "server://0.0.0.0:10101/127.0.0.1:8080?log=info&tls=0&crt=C:/certs/my%20cert.pem",
+        "client://example.com:10101/127.0.0.1:8080?log=info&min=64&max=1024",
+        "client://[fe80::1%25eth0]:10101/127.0.0.1:8080?log=info",
+        "server://0.0.0.0:10101/127.0.0.1:8080?log=event&tls=1&mode=2&read=30s&rate=100&slot=5000&proxy=1&notcp=0&noudp=1&dial=10.0.0.2",
+        "client://tunnel.example.com:10101/127.0.0.1:53?log=error&mode=1&noudp=0&future_flag=a%26b",
+        "client://example.com:10101/127.0.0.1:8080?log=info&tls=1",
+        "client://127.0.0.1:10101/example.com:8080?log=debug&tls=2&mode=1",
+    ];
+
+    #[test]
+    fn canonical_urls_round_trip() {
+        for url in CANONICAL_URLS {
+            let config = NodePassConfig::from_url(url).unwrap();
+            assert_eq!(&config.to_url().unwrap(), url);
+            assert_eq!(NodePassConfig::from_url(url).unwrap(), config);
+        }
+    }
+
+    #[test]
+    fn cert_files_survive_round_trip_without_tls_2() {
+        let config = NodePassConfig::from_url(
+            "server://0.0.0.0:10101/127.0.0.1:8080?tls=1&crt=/a.pem&key=/a.key",
+        )
+        .unwrap();
+        let reparsed = NodePassConfig::from_url(&config.to_url().unwrap()).unwrap();
+        assert_eq!(reparsed.cert_file.as_deref(), Some("/a.pem"));
+        assert_eq!(reparsed.key_file.as_deref(), Some("/a.key"));
+    }
+
+    #[test]
+    fn repeated_params_keep_first_value() {
+        let config = NodePassConfig::from_url(
+            "server://0.0.0.0:10101/127.0.0.1:8080?log=debug&tls=1&log=info&dial=a&dial=b",
+        )
+        .unwrap();
+        assert_eq!(config.log_level, "debug");
+        assert_eq!(config.extra_params.get("dial").map(String::as_str), Some("a"));
+        assert_eq!(
+            config.to_url().unwrap(),
+            "server://0.0.0.0:10101/127.0.0.1:8080?log=debug&tls=1&dial=a"
+        );
+    }
+
+    #[test]
+    fn addresses_are_percent_decoded() {
+        let config =
+            NodePassConfig::from_url("server://0.0.0.0:10101/local%68ost:8080?log=info&tls=0")
+                .unwrap();
+        assert_eq!(config.target_addr, "localhost:8080");
+
+        let config =
+            NodePassConfig::from_url("client://[fe80::1%25eth0]:10101/127.0.0.1:8080").unwrap();
+        assert_eq!(config.tunnel_addr, "[fe80::1%eth0]:10101");
+    }
+
+    #[test]
+    fn query_values_are_decoded() {
+        let config = NodePassConfig::from_url(
+            "server://0.0.0.0:10101/127.0.0.1:8080?tls=2&crt=C%3A%5Ccerts%5Ca+b.pem",
+        )
+        .unwrap();
+        assert_eq!(config.cert_file.as_deref(), Some("C:\\certs\\a b.pem"));
+    }
+
+    #[test]
+    fn invalid_urls_are_rejected() {
+        for url in [
+            "http://0.0.0.0:10101/127.0.0.1:8080",
+            "server://0.0.0.0:10101",
+            "server://0.0.0.0/127.0.0.1:8080",
+            "server://::1:10101/127.0.0.1:8080",
+            "server://0.0.0.0:10101/127.0.0.1:8080?min=abc",
+            "server://0.0.0.0:10101/127.0.0.1:8080?min=10&max=5",
+        ] {
+            assert!(NodePassConfig::from_url(url).is_err(), "{}", url);
+        }
+    }
+}
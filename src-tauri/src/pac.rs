@@ -0,0 +1,224 @@
+// 使用内置的 QuickJS 执行 PAC 脚本，提供标准的 PAC 辅助函数
+use rquickjs::{CatchResultExt, Context, Function, Runtime};
+use std::net::{IpAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+// 脚本执行时间和内存上限，避免有问题的脚本卡住下载
+const PAC_TIMEOUT: Duration = Duration::from_secs(2);
+const PAC_MEMORY_LIMIT: usize = 16 * 1024 * 1024;
+
+// 不依赖系统调用的辅助函数用 JS 实现；dnsResolve 和 myIpAddress 由 Rust 提供
+const PAC_PRELUDE: &str = r#"
+function isPlainHostName(host) {
+  return host.indexOf('.') < 0;
+}
+function dnsDomainIs(host, domain) {
+  return host.length >= domain.length &&
+    host.substring(host.length - domain.length) === domain;
+}
+function localHostOrDomainIs(host, hostdom) {
+  return host === hostdom || hostdom.lastIndexOf(host + '.', 0) === 0;
+}
+function isResolvable(host) {
+  return dnsResolve(host) !== null;
+}
+function convertAddr(ip) {
+  var bytes = ip.split('.');
+  return ((bytes[0] << 24) | (bytes[1] << 16) | (bytes[2] << 8) | (bytes[3] | 0)) >>> 0;
+}
+function isInNet(host, pattern, mask) {
+  var ip = /^\d+\.\d+\.\d+\.\d+$/.test(host) ? host : dnsResolve(host);
+  if (!ip) {
+    return false;
+  }
+  var m = convertAddr(mask);
+  return ((convertAddr(ip) & m) >>> 0) === ((convertAddr(pattern) & m) >>> 0);
+}
+function dnsDomainLevels(host) {
+  return host.split('.').length - 1;
+}
+function shExpMatch(str, shexp) {
+  var re = shexp
+    .replace(/[.+^${}()|[\]\\]/g, '\\$&')
+    .replace(/\*/g, '.*')
+    .replace(/\?/g, '.');
+  return new RegExp('^' + re + '$').test(str);
+}
+var PAC_DAYS = ['SUN', 'MON', 'TUE', 'WED', 'THU', 'FRI', 'SAT'];
+var PAC_MONTHS = ['JAN', 'FEB', 'MAR', 'APR', 'MAY', 'JUN', 'JUL', 'AUG', 'SEP', 'OCT', 'NOV', 'DEC'];
+function pacArgs(args) {
+  var list = Array.prototype.slice.call(args);
+  var gmt = list.length > 0 && list[list.length - 1] === 'GMT';
+  if (gmt) {
+    list.pop();
+  }
+  return { list: list, now: new Date(), gmt: gmt };
+}
+function pacInRange(value, start, end) {
+  return start <= end ? value >= start && value <= end : value >= start || value <= end;
+}
+function weekdayRange() {
+  var a = pacArgs(arguments);
+  var day = a.gmt ? a.now.getUTCDay() : a.now.getDay();
+  var start = PAC_DAYS.indexOf(a.list[0]);
+  var end = a.list.length > 1 ? PAC_DAYS.indexOf(a.list[1]) : start;
+  return start >= 0 && end >= 0 && pacInRange(day, start, end);
+}
+function timeRange() {
+  var a = pacArgs(arguments);
+  var now = a.gmt
+    ? a.now.getUTCHours() * 3600 + a.now.getUTCMinutes() * 60 + a.now.getUTCSeconds()
+    : a.now.getHours() * 3600 + a.now.getMinutes() * 60 + a.now.getSeconds();
+  var n = a.list.map(Number);
+  switch (n.length) {
+    case 1: return Math.floor(now / 3600) === n[0];
+    case 2: return pacInRange(Math.floor(now / 3600), n[0], n[1] - 1);
+    case 4: return pacInRange(now, n[0] * 3600 + n[1] * 60, n[2] * 3600 + n[3] * 60 - 1);
+    case 6: return pacInRange(now, n[0] * 3600 + n[1] * 60 + n[2], n[3] * 3600 + n[4] * 60 + n[5]);
+    default: return false;
+  }
+}
+function dateRange() {
+  var a = pacArgs(arguments);
+  var date = a.gmt ? a.now.getUTCDate() : a.now.getDate();
+  var month = a.gmt ? a.now.getUTCMonth() : a.now.getMonth();
+  var year = a.gmt ? a.now.getUTCFullYear() : a.now.getFullYear();
+  // 每个参数按类型换算为可比较的值：日、月或年
+  function value(arg) {
+    if (typeof arg === 'string') {
+      return { kind: 'month', v: PAC_MONTHS.indexOf(arg) };
+    }
+    return arg > 31 ? { kind: 'year', v: arg } : { kind: 'day', v: arg };
+  }
+  function current(kind) {
+    return kind === 'month' ? month : kind === 'year' ? year : date;
+  }
+  var values = a.list.map(value);
+  if (values.length === 1) {
+    return current(values[0].kind) === values[0].v;
+  }
+  var half = values.length / 2;
+  for (var i = 0; i < half; i++) {
+    var start = values[i], end = values[i + half];
+    if (!pacInRange(current(start.kind), start.v, end.v)) {
+      return false;
+    }
+  }
+  return true;
+}
+"#;
+
+// 执行 PAC 脚本中的 FindProxyForURL，返回原始结果，例如 "PROXY 10.0.0.1:8080; DIRECT"
+pub fn find_proxy(script: &str, url: &str, host: &str) -> Result<String, String> {
+    let runtime = Runtime::new().map_err(|e| format!("创建脚本引擎失败: {}", e))?;
+    runtime.set_memory_limit(PAC_MEMORY_LIMIT);
+    let started = Instant::now();
+    runtime.set_interrupt_handler(Some(Box::new(move || started.elapsed() > PAC_TIMEOUT)));
+    let context = Context::full(&runtime).map_err(|e| format!("创建脚本上下文失败: {}", e))?;
+
+    context.with(|ctx| {
+        let globals = ctx.globals();
+        Function::new(ctx.clone(), dns_resolve)
+            .and_then(|function| globals.set("dnsResolve", function))
+            .and_then(|_| Function::new(ctx.clone(), my_ip_address))
+            .and_then(|function| globals.set("myIpAddress", function))
+            .map_err(|e| format!("注册 PAC 辅助函数失败: {}", e))?;
+
+        ctx.eval::<(), _>(PAC_PRELUDE)
+            .catch(&ctx)
+            .map_err(|e| format!("加载 PAC 辅助函数失败: {}", e))?;
+        ctx.eval::<(), _>(script)
+            .catch(&ctx)
+            .map_err(|e| format!("PAC 脚本错误: {}", e))?;
+
+        let find_proxy: Function = globals
+            .get("FindProxyForURL")
+            .map_err(|_| "PAC 脚本中没有 FindProxyForURL 函数".to_string())?;
+        find_proxy
+            .call::<_, String>((url, host))
+            .catch(&ctx)
+            .map_err(|e| format!("执行 FindProxyForURL 失败: {}", e))
+    })
+}
+
+// 解析 PAC 结果，返回第一个可用的代理地址；DIRECT 或没有可用代理时返回 None
+pub fn parse_result(result: &str) -> Option<String> {
+    for entry in result.split(';') {
+        let mut parts = entry.split_whitespace();
+        let kind = parts.next().unwrap_or("").to_uppercase();
+        let address = parts.next();
+        match (kind.as_str(), address) {
+            ("DIRECT", _) => return None,
+            ("PROXY", Some(address)) | ("HTTP", Some(address)) => {
+                return Some(format!("http://{}", address))
+            }
+            ("HTTPS", Some(address)) => return Some(format!("https://{}", address)),
+            ("SOCKS", Some(address)) | ("SOCKS5", Some(address)) => {
+                return Some(format!("socks5://{}", address))
+            }
+            // 不支持的类型（例如 SOCKS4）尝试下一项
+            _ => continue,
+        }
+    }
+    None
+}
+
+fn dns_resolve(host: String) -> Option<String> {
+    (host.as_str(), 0)
+        .to_socket_addrs()
+        .ok()?
+        .map(|addr| addr.ip())
+        .find(IpAddr::is_ipv4)
+        .map(|ip| ip.to_string())
+}
+
+// 本机用于访问外网的地址；UDP connect 不会真正发送数据
+fn my_ip_address() -> String {
+    UdpSocket::bind("0.0.0.0:0")
+        .and_then(|socket| socket.connect("8.8.8.8:80").map(|_| socket))
+        .and_then(|socket| socket.local_addr())
+        .map(|addr| addr.ip().to_string())
+        .unwrap_or_else(|_| "127.0.0.1".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/proxy.pac");
+
+    fn resolve(url: &str, host: &str) -> Option<String> {
+        parse_result(&find_proxy(FIXTURE, url, host).unwrap())
+    }
+
+    #[test]
+    fn fixture_script_selects_proxy_by_host() {
+        assert_eq!(resolve("http://intranet/", "intranet"), None);
+        assert_eq!(resolve("http://wiki.corp.example.com/", "wiki.corp.example.com"), None);
+        assert_eq!(
+            resolve("https://api.github.com/", "api.github.com"),
+            Some("http://proxy.example.com:8080".to_string())
+        );
+        assert_eq!(
+            resolve("http://10.1.2.3/", "10.1.2.3"),
+            Some("socks5://10.0.0.1:1080".to_string())
+        );
+        assert_eq!(
+            resolve("https://example.org/", "example.org"),
+            Some("https://secure.example.com:443".to_string())
+        );
+        // 不支持的 SOCKS4 跳过，使用下一项
+        assert_eq!(
+            resolve("http://example.org/", "example.org"),
+            Some("http://fallback.example.com:3128".to_string())
+        );
+    }
+
+    #[test]
+    fn script_errors_are_reported() {
+        assert!(find_proxy("function Other() {}", "http://a/", "a").is_err());
+        assert!(find_proxy("this is not javascript", "http://a/", "a").is_err());
+        // 死循环的脚本在超时后中断
+        assert!(find_proxy("function FindProxyForURL() { while (true) {} }", "http://a/", "a").is_err());
+    }
+}
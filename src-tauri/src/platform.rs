@@ -0,0 +1,327 @@
+// 按当前系统和架构选择 NodePass 发布包，以及查找可执行文件
+use crate::GitHubAsset;
+use std::path::{Path, PathBuf};
+
+// NodePass 可执行文件名
+pub fn executable_name() -> &'static str {
+    if cfg!(windows) {
+        "nodepass.exe"
+    } else {
+        "nodepass"
+    }
+}
+
+// 发布包命名中使用的系统名，例如 nodepass_1.2.3_linux_amd64.tar.gz
+pub fn release_os() -> &'static str {
+    match std::env::consts::OS {
+        "macos" => "darwin",
+        os => os,
+    }
+}
+
+// 发布包命名中使用的架构名（Go 的命名方式）
+pub fn release_arch() -> &'static str {
+    match std::env::consts::ARCH {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        "x86" => "386",
+        "arm" => "arm",
+        "powerpc64" => "ppc64le",
+        "riscv64" => "riscv64",
+        "s390x" => "s390x",
+        arch => arch,
+    }
+}
+
+// 同一系统/架构在不同发布中可能出现的写法
+fn os_aliases(os: &str) -> &'static [&'static str] {
+    match os {
+        "windows" => &["windows", "win"],
+        "darwin" => &["darwin", "macos", "mac", "osx"],
+        "linux" => &["linux"],
+        "freebsd" => &["freebsd"],
+        _ => &[],
+    }
+}
+
+fn arch_aliases(arch: &str) -> &'static [&'static str] {
+    match arch {
+        "amd64" => &["amd64", "x64"],
+        "arm64" => &["arm64", "aarch64"],
+        "386" => &["386", "i386", "x86"],
+        "arm" => &["arm", "armv7", "armv6"],
+        "ppc64le" => &["ppc64le"],
+        "riscv64" => &["riscv64"],
+        "s390x" => &["s390x"],
+        _ => &[],
+    }
+}
+
+fn is_archive(name: &str) -> bool {
+    name.ends_with(".tar.gz") || name.ends_with(".tgz") || name.ends_with(".zip")
+}
+
+// 选择适合当前系统的发布包
+pub fn select_asset(assets: &[GitHubAsset]) -> Option<&GitHubAsset> {
+    select_asset_for(assets, release_os(), release_arch())
+}
+
+pub fn select_asset_for<'a>(
+    assets: &'a [GitHubAsset],
+    os: &str,
+    arch: &str,
+) -> Option<&'a GitHubAsset> {
+    let os_names = os_aliases(os);
+    let arch_names = arch_aliases(arch);
+
+    let mut candidates: Vec<&GitHubAsset> = assets
+        .iter()
+        .filter(|asset| {
+            let name = asset.name.to_lowercase();
+            if !is_archive(&name) {
+                return false;
+            }
+            // 按分隔符拆成片段再比较，避免 arm 匹配到 arm64；
+            // x86_64 本身包含分隔符，先统一为 amd64，也避免 x86 匹配到它
+            let name = name.replace("x86_64", "amd64");
+            let tokens: Vec<&str> = name.split(['_', '-', '.']).collect();
+            tokens.iter().any(|token| os_names.contains(token))
+                && tokens.iter().any(|token| arch_names.contains(token))
+        })
+        .collect();
+
+    // Windows 优先 zip，其它系统优先 tar.gz
+    let preferred = if os == "windows" { ".zip" } else { ".tar.gz" };
+    candidates.sort_by_key(|asset| !asset.name.to_lowercase().ends_with(preferred));
+    candidates.into_iter().next()
+}
+
+// 在 PATH 中查找可执行文件，按系统使用对应的路径分隔符
+pub fn find_in_path(name: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(name))
+        .find(|candidate| candidate.is_file())
+}
+
+// 用新文件替换可执行文件：先复制一份旧文件作为备份，再把新文件直接改名覆盖目标，
+// 目标文件始终存在。Windows 上运行中的程序不能被覆盖但可以改名，覆盖失败时先把旧文件移开
+pub fn replace_executable(staged: &Path, target: &Path) -> Result<(), String> {
+    let dir = target.parent().ok_or_else(|| "安装目录无效".to_string())?;
+    let name = target
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    // 暂存文件不在目标目录时先复制过去，rename 跨文件系统会失败 (EXDEV)
+    if staged.parent() != Some(dir) {
+        let local = dir.join(format!(
+            "{}.new-{}",
+            name,
+            chrono::Local::now().timestamp_millis()
+        ));
+        std::fs::copy(staged, &local).map_err(|e| format!("复制可执行文件失败: {}", e))?;
+        let result = replace_executable(&local, target);
+        if result.is_err() {
+            let _ = std::fs::remove_file(&local);
+        } else {
+            let _ = std::fs::remove_file(staged);
+        }
+        return result;
+    }
+
+    // 清理之前替换留下的旧文件，仍在运行的会删除失败，留到下次
+    if let Ok(entries) = std::fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            if entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with(&format!("{}.old-", name))
+            {
+                let _ = std::fs::remove_file(entry.path());
+            }
+        }
+    }
+
+    let backup = if target.exists() {
+        let backup = dir.join(format!(
+            "{}.old-{}",
+            name,
+            chrono::Local::now().timestamp_millis()
+        ));
+        std::fs::copy(target, &backup).map_err(|e| format!("备份旧的可执行文件失败: {}", e))?;
+        Some(backup)
+    } else {
+        None
+    };
+
+    if let Err(e) = std::fs::rename(staged, target) {
+        let backup = match &backup {
+            Some(backup) => backup,
+            None => return Err(format!("安装可执行文件失败: {}", e)),
+        };
+        // 旧文件被占用，改名移开后再放入新文件
+        let aside = PathBuf::from(format!("{}-in-use", backup.display()));
+        std::fs::rename(target, &aside).map_err(|_| format!("安装可执行文件失败: {}", e))?;
+        if let Err(e) = std::fs::rename(staged, target) {
+            // 恢复旧文件，改名失败时用备份复制回去
+            if std::fs::rename(&aside, target).is_err() {
+                let _ = std::fs::copy(backup, target);
+            }
+            return Err(format!("安装可执行文件失败: {}", e));
+        }
+        let _ = std::fs::remove_file(&aside);
+    }
+
+    // 替换成功后不再需要备份
+    if let Some(backup) = backup {
+        let _ = std::fs::remove_file(backup);
+    }
+    Ok(())
+}
+
+// Unix 上为解压出的可执行文件加上执行权限
+#[cfg(unix)]
+pub fn make_executable(path: &Path) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut permissions = std::fs::metadata(path)
+        .map_err(|e| format!("读取文件权限失败: {}", e))?
+        .permissions();
+    permissions.set_mode(permissions.mode() | 0o755);
+    std::fs::set_permissions(path, permissions).map_err(|e| format!("设置执行权限失败: {}", e))
+}
+
+#[cfg(not(unix))]
+pub fn make_executable(_path: &Path) -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GitHubRelease;
+
+    fn releases() -> Vec<GitHubRelease> {
+        serde_json::from_str(include_str!("../tests/fixtures/releases.json")).unwrap()
+    }
+
+    fn selected(release: &GitHubRelease, os: &str, arch: &str) -> Option<String> {
+        select_asset_for(&release.assets, os, arch).map(|asset| asset.name.clone())
+    }
+
+    fn file_names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn replace_executable_overwrites_target_and_cleans_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("nodepass");
+        let staged = dir.path().join("nodepass.new");
+        std::fs::write(&target, b"old").unwrap();
+        std::fs::write(&staged, b"new").unwrap();
+        // 上次替换留下的旧文件
+        std::fs::write(dir.path().join("nodepass.old-1"), b"stale").unwrap();
+
+        replace_executable(&staged, &target).unwrap();
+        assert_eq!(std::fs::read(&target).unwrap(), b"new");
+        assert_eq!(file_names(dir.path()), ["nodepass"]);
+    }
+
+    #[test]
+    fn replace_executable_copies_from_other_directory() {
+        let staging = tempfile::tempdir().unwrap();
+        let install = tempfile::tempdir().unwrap();
+        let staged = staging.path().join("nodepass");
+        let target = install.path().join("nodepass");
+        std::fs::write(&staged, b"new").unwrap();
+
+        replace_executable(&staged, &target).unwrap();
+        assert_eq!(std::fs::read(&target).unwrap(), b"new");
+        assert_eq!(file_names(install.path()), ["nodepass"]);
+        assert!(!staged.exists());
+    }
+
+    #[test]
+    fn replace_executable_installs_missing_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("nodepass");
+        let staged = dir.path().join("nodepass.new");
+        std::fs::write(&staged, b"new").unwrap();
+
+        replace_executable(&staged, &target).unwrap();
+        assert_eq!(std::fs::read(&target).unwrap(), b"new");
+    }
+
+    #[test]
+    fn replace_executable_keeps_target_when_staged_is_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("nodepass");
+        std::fs::write(&target, b"old").unwrap();
+
+        assert!(replace_executable(&dir.path().join("missing"), &target).is_err());
+        assert_eq!(std::fs::read(&target).unwrap(), b"old");
+    }
+
+    #[test]
+    fn selects_asset_for_each_platform() {
+        let release = &releases()[0];
+        let cases = [
+            ("linux", "amd64", "nodepass_1.4.0_linux_amd64.tar.gz"),
+            ("linux", "arm64", "nodepass_1.4.0_linux_arm64.tar.gz"),
+            ("linux", "arm", "nodepass_1.4.0_linux_arm.tar.gz"),
+            ("linux", "386", "nodepass_1.4.0_linux_386.tar.gz"),
+            ("darwin", "amd64", "nodepass_1.4.0_darwin_amd64.tar.gz"),
+            ("darwin", "arm64", "nodepass_1.4.0_darwin_arm64.tar.gz"),
+            ("windows", "amd64", "nodepass_1.4.0_windows_amd64.zip"),
+            ("windows", "arm64", "nodepass_1.4.0_windows_arm64.zip"),
+            ("windows", "386", "nodepass_1.4.0_windows_386.zip"),
+            ("freebsd", "amd64", "nodepass_1.4.0_freebsd_amd64.tar.gz"),
+        ];
+        for (os, arch, expected) in cases {
+            assert_eq!(selected(release, os, arch).as_deref(), Some(expected), "{}/{}", os, arch);
+        }
+    }
+
+    #[test]
+    fn missing_platforms_select_nothing() {
+        let release = &releases()[0];
+        assert_eq!(selected(release, "darwin", "386"), None);
+        assert_eq!(selected(release, "linux", "riscv64"), None);
+        assert_eq!(selected(release, "plan9", "amd64"), None);
+    }
+
+    #[test]
+    fn matches_alias_names_and_preferred_format() {
+        let release = &releases()[1];
+        assert_eq!(
+            selected(release, "linux", "amd64").as_deref(),
+            Some("nodepass-v1.2.0-linux-x86_64.tar.gz")
+        );
+        assert_eq!(
+            selected(release, "linux", "arm64").as_deref(),
+            Some("nodepass-v1.2.0-linux-aarch64.tgz")
+        );
+        // arm 不能匹配到 arm64 / aarch64
+        assert_eq!(
+            selected(release, "linux", "arm").as_deref(),
+            Some("nodepass-v1.2.0-linux-armv7.tar.gz")
+        );
+        assert_eq!(
+            selected(release, "darwin", "amd64").as_deref(),
+            Some("nodepass-v1.2.0-macos-x86_64.tar.gz")
+        );
+        // Windows 同时有 zip 和 tar.gz 时选择 zip，校验文件和安装包不会被选中
+        assert_eq!(
+            selected(release, "windows", "amd64").as_deref(),
+            Some("nodepass-v1.2.0-win-x64.zip")
+        );
+        assert_eq!(selected(release, "darwin", "arm64"), None);
+    }
+}
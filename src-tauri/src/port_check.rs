@@ -0,0 +1,412 @@
+// 启动前检查监听端口：与其它隧道的冲突、能否绑定，以及占用端口的进程
+use crate::nodepass_config::NodePassConfig;
+use crate::registry::TunnelRegistry;
+use crate::start_error::StartError;
+use serde::Serialize;
+use std::net::{SocketAddr, TcpListener, UdpSocket};
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PortOwner {
+    pub pid: u32,
+    pub name: Option<String>,
+}
+
+// 隧道在本机的一个监听地址及其使用的协议
+#[derive(Debug, Clone, PartialEq)]
+pub struct Listener {
+    pub address: String,
+    pub protocols: Vec<&'static str>,
+    // 自动模式下目标地址不是本机地址时 NodePass 会改用正向模式，此时绑定失败不算错误
+    pub required: bool,
+}
+
+impl Listener {
+    fn new(address: &str, protocols: Vec<&'static str>, required: bool) -> Option<Self> {
+        if address.is_empty() || protocols.is_empty() {
+            return None;
+        }
+        Some(Self {
+            address: address.to_string(),
+            protocols,
+            required,
+        })
+    }
+}
+
+// 转发数据的协议，notcp/noudp 只作用于数据监听
+fn data_protocols(config: &NodePassConfig) -> Vec<&'static str> {
+    let mut protocols = Vec::new();
+    if config.disable_tcp != Some(true) {
+        protocols.push("tcp");
+    }
+    if config.disable_udp != Some(true) {
+        protocols.push("udp");
+    }
+    protocols
+}
+
+// 隧道会在本机监听的地址：
+// 服务端在隧道地址上监听控制通道 (TCP)，反向模式 (mode=1) 和自动模式 (mode=0) 下还在目标地址上监听数据；
+// 客户端在单端转发模式 (mode=1) 下在隧道地址上监听数据
+pub fn listeners(config: &NodePassConfig) -> Vec<Listener> {
+    match config.mode.as_str() {
+        "server" => {
+            let data = match config.run_mode.unwrap_or(0) {
+                0 => Listener::new(&config.target_addr, data_protocols(config), false),
+                1 => Listener::new(&config.target_addr, data_protocols(config), true),
+                _ => None,
+            };
+            Listener::new(&config.tunnel_addr, vec!["tcp"], true)
+                .into_iter()
+                .chain(data)
+                .collect()
+        }
+        "client" if config.run_mode == Some(1) => {
+            Listener::new(&config.tunnel_addr, data_protocols(config), true)
+                .into_iter()
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+// 拆分 host:port，host 去掉 IPv6 方括号
+fn split_host_port(addr: &str) -> Option<(String, u16)> {
+    let (host, port) = addr.rsplit_once(':')?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    Some((host.to_string(), port.parse().ok()?))
+}
+
+fn is_wildcard(host: &str) -> bool {
+    matches!(host, "" | "0.0.0.0" | "::")
+}
+
+// 两个监听是否会争用同一端口的同一协议
+fn listeners_overlap(a: &Listener, b: &Listener) -> bool {
+    a.protocols.iter().any(|protocol| b.protocols.contains(protocol))
+        && addresses_overlap(&a.address, &b.address)
+}
+
+// 两个监听地址是否会争用同一端口
+fn addresses_overlap(a: &str, b: &str) -> bool {
+    match (split_host_port(a), split_host_port(b)) {
+        (Some((host_a, port_a)), Some((host_b, port_b))) => {
+            port_a == port_b
+                && (host_a.eq_ignore_ascii_case(&host_b) || is_wildcard(&host_a) || is_wildcard(&host_b))
+        }
+        _ => false,
+    }
+}
+
+// 解析为可绑定的套接字地址，只写端口时按监听所有地址处理；域名解析不阻塞异步运行时
+async fn bind_address(addr: &str) -> Result<SocketAddr, String> {
+    let (host, port) =
+        split_host_port(addr).ok_or_else(|| format!("监听地址无效: {}", addr))?;
+    let host = if host.is_empty() { "0.0.0.0".to_string() } else { host };
+    let mut resolved = tokio::net::lookup_host((host.as_str(), port))
+        .await
+        .map_err(|e| format!("解析监听地址 {} 失败: {}", addr, e))?;
+    resolved
+        .next()
+        .ok_or_else(|| format!("解析监听地址 {} 失败", addr))
+}
+
+// 检查隧道的监听地址，失败时返回具体原因
+pub async fn preflight(
+    registry: &TunnelRegistry,
+    tunnel_id: &str,
+    config: &NodePassConfig,
+) -> Result<(), StartError> {
+    let own = listeners(config);
+    if own.is_empty() {
+        return Ok(());
+    }
+
+    // 与其它运行中的隧道比较
+    for (other_id, _) in registry.running() {
+        if other_id == tunnel_id {
+            continue;
+        }
+        let other = match registry.definition(&other_id) {
+            Some(definition) => definition,
+            None => continue,
+        };
+        let other_listeners = listeners(&other.config);
+        for listener in &own {
+            if let Some(other_listener) = other_listeners
+                .iter()
+                .find(|other_listener| listeners_overlap(listener, other_listener))
+            {
+                return Err(StartError::PortConflict {
+                    message: format!(
+                        "监听地址 {} 与运行中的隧道 {} ({}) 冲突",
+                        listener.address, other_id, other_listener.address
+                    ),
+                    address: listener.address.clone(),
+                    tunnel_id: other_id,
+                });
+            }
+        }
+    }
+
+    // 按各监听使用的协议实际尝试绑定
+    for listener in &own {
+        let address = &listener.address;
+        let socket_addr = match bind_address(address).await {
+            Ok(socket_addr) => socket_addr,
+            Err(_) if !listener.required => continue,
+            Err(e) => return Err(e.into()),
+        };
+        let checks: Vec<(&str, Option<std::io::Error>)> = listener
+            .protocols
+            .iter()
+            .map(|&protocol| {
+                let error = if protocol == "tcp" {
+                    TcpListener::bind(socket_addr).err()
+                } else {
+                    UdpSocket::bind(socket_addr).err()
+                };
+                (protocol, error)
+            })
+            .collect();
+
+        for (protocol, error) in checks {
+            let error = match error {
+                Some(error) => error,
+                None => continue,
+            };
+            if !listener.required && error.kind() == std::io::ErrorKind::AddrNotAvailable {
+                continue;
+            }
+            let owner = if protocol == "tcp" {
+                find_port_owner(socket_addr.port()).await
+            } else {
+                None
+            };
+            let owner_label = match &owner {
+                Some(PortOwner { pid, name: Some(name) }) => format!("，占用进程: {} (PID: {})", name, pid),
+                Some(PortOwner { pid, name: None }) => format!("，占用进程PID: {}", pid),
+                None => String::new(),
+            };
+            return Err(StartError::PortInUse {
+                message: format!(
+                    "无法监听 {} ({}): {}{}",
+                    address,
+                    protocol.to_uppercase(),
+                    error,
+                    owner_label
+                ),
+                address: address.clone(),
+                protocol: protocol.to_string(),
+                owner,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+// 查找监听指定 TCP 端口的进程
+#[cfg(target_os = "linux")]
+pub async fn find_port_owner(port: u16) -> Option<PortOwner> {
+    tokio::task::spawn_blocking(move || linux_port_owner(port))
+        .await
+        .ok()
+        .flatten()
+}
+
+#[cfg(target_os = "linux")]
+fn linux_port_owner(port: u16) -> Option<PortOwner> {
+    use std::fs;
+
+    // /proc/net/tcp 中状态 0A 为 LISTEN，local_address 形如 00000000:2775
+    let inode = ["/proc/net/tcp", "/proc/net/tcp6"].iter().find_map(|table| {
+        let content = fs::read_to_string(table).ok()?;
+        content.lines().skip(1).find_map(|line| {
+            let columns: Vec<&str> = line.split_whitespace().collect();
+            let local_port = columns.get(1)?.rsplit_once(':')?.1;
+            let listening = columns.get(3) == Some(&"0A");
+            if listening && u16::from_str_radix(local_port, 16).ok()? == port {
+                columns.get(9).map(|inode| inode.to_string())
+            } else {
+                None
+            }
+        })
+    })?;
+
+    let target = format!("socket:[{}]", inode);
+    for entry in fs::read_dir("/proc").ok()?.flatten() {
+        let pid: u32 = match entry.file_name().to_string_lossy().parse() {
+            Ok(pid) => pid,
+            Err(_) => continue,
+        };
+        let fds = match fs::read_dir(entry.path().join("fd")) {
+            Ok(fds) => fds,
+            Err(_) => continue,
+        };
+        let owns_socket = fds.flatten().any(|fd| {
+            fs::read_link(fd.path())
+                .map(|link| link.to_string_lossy() == target)
+                .unwrap_or(false)
+        });
+        if owns_socket {
+            let name = fs::read_to_string(entry.path().join("comm"))
+                .ok()
+                .map(|name| name.trim().to_string());
+            return Some(PortOwner { pid, name });
+        }
+    }
+    None
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+pub async fn find_port_owner(port: u16) -> Option<PortOwner> {
+    let output = tokio::process::Command::new("lsof")
+        .args(["-nP", &format!("-iTCP:{}", port), "-sTCP:LISTEN", "-Fpc"])
+        .output()
+        .await
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    // -F 输出每行一个字段：p<PID>、c<进程名>
+    let pid = stdout
+        .lines()
+        .find_map(|line| line.strip_prefix('p'))?
+        .parse()
+        .ok()?;
+    let name = stdout
+        .lines()
+        .find_map(|line| line.strip_prefix('c'))
+        .map(|name| name.to_string());
+    Some(PortOwner { pid, name })
+}
+
+#[cfg(windows)]
+pub async fn find_port_owner(port: u16) -> Option<PortOwner> {
+    let mut cmd = tokio::process::Command::new("netstat");
+    cmd.args(["-ano", "-p", "TCP"]);
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    let output = cmd.output().await.ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    // 例如:  TCP    0.0.0.0:10101    0.0.0.0:0    LISTENING    1234
+    let pid: u32 = stdout.lines().find_map(|line| {
+        let columns: Vec<&str> = line.split_whitespace().collect();
+        if columns.len() < 5 || columns[3] != "LISTENING" {
+            return None;
+        }
+        let local_port = columns[1].rsplit_once(':')?.1;
+        if local_port.parse::<u16>().ok()? == port {
+            columns[4].parse().ok()
+        } else {
+            None
+        }
+    })?;
+
+    let mut cmd = tokio::process::Command::new("tasklist");
+    cmd.args(["/FI", &format!("PID eq {}", pid), "/FO", "CSV", "/NH"]);
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    let name = cmd.output().await.ok().and_then(|output| {
+        // 例如: "nodepass.exe","1234","Console","1","10,000 K"
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .next()
+            .and_then(|line| line.split(',').next())
+            .map(|name| name.trim_matches('"').to_string())
+            .filter(|name| !name.is_empty() && !name.starts_with("INFO"))
+    });
+
+    Some(PortOwner { pid, name })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry(dir: &tempfile::TempDir) -> TunnelRegistry {
+        TunnelRegistry::load(dir.path().join("tunnels.json"), dir.path().join("runtime.json"))
+    }
+
+    fn listened(url: &str) -> Vec<(String, Vec<&'static str>, bool)> {
+        listeners(&NodePassConfig::from_url(url).unwrap())
+            .into_iter()
+            .map(|listener| (listener.address, listener.protocols, listener.required))
+            .collect()
+    }
+
+    #[test]
+    fn listen_addresses_by_mode() {
+        let control = ("0.0.0.0:10101".to_string(), vec!["tcp"], true);
+        // 自动模式：目标地址可能在本机监听
+        assert_eq!(
+            listened("server://0.0.0.0:10101/127.0.0.1:8080"),
+            [control.clone(), ("127.0.0.1:8080".to_string(), vec!["tcp", "udp"], false)]
+        );
+        // 反向模式：在目标地址上监听，noudp 只影响数据监听
+        assert_eq!(
+            listened("server://0.0.0.0:10101/127.0.0.1:8080?mode=1&noudp=1"),
+            [control.clone(), ("127.0.0.1:8080".to_string(), vec!["tcp"], true)]
+        );
+        // 正向模式：只有控制通道
+        assert_eq!(
+            listened("server://0.0.0.0:10101/127.0.0.1:8080?mode=2&notcp=1"),
+            [control]
+        );
+
+        assert!(listened("client://example.com:10101/127.0.0.1:8080").is_empty());
+        assert_eq!(
+            listened("client://127.0.0.1:10101/example.com:8080?mode=1"),
+            [("127.0.0.1:10101".to_string(), vec!["tcp", "udp"], true)]
+        );
+    }
+
+    #[test]
+    fn overlapping_addresses() {
+        assert!(addresses_overlap("0.0.0.0:10101", "127.0.0.1:10101"));
+        assert!(addresses_overlap(":10101", "[::1]:10101"));
+        assert!(addresses_overlap("LOCALHOST:80", "localhost:80"));
+        assert!(!addresses_overlap("127.0.0.1:10101", "192.168.1.2:10101"));
+        assert!(!addresses_overlap("0.0.0.0:10101", "0.0.0.0:10102"));
+    }
+
+    #[tokio::test]
+    async fn bind_address_resolves_hosts() {
+        assert_eq!(
+            bind_address(":10101").await.unwrap(),
+            "0.0.0.0:10101".parse::<SocketAddr>().unwrap()
+        );
+        assert_eq!(
+            bind_address("[::1]:10101").await.unwrap(),
+            "[::1]:10101".parse::<SocketAddr>().unwrap()
+        );
+        assert!(bind_address("localhost:10101").await.unwrap().ip().is_loopback());
+        assert!(bind_address("no-port").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn udp_check_follows_disable_udp() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = registry(&dir);
+        // 只占用 UDP 端口，TCP 端口保持空闲
+        let udp = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let port = udp.local_addr().unwrap().port();
+        if TcpListener::bind(("127.0.0.1", port)).is_err() {
+            return;
+        }
+
+        let url = format!("client://127.0.0.1:{}/127.0.0.1:8080?mode=1", port);
+        let config = NodePassConfig::from_url(&url).unwrap();
+        match preflight(&registry, "a", &config).await {
+            Err(StartError::PortInUse { protocol, .. }) => assert_eq!(protocol, "udp"),
+            other => panic!("expected UDP port in use, got {:?}", other),
+        }
+
+        let config = NodePassConfig::from_url(&format!("{}&noudp=1", url)).unwrap();
+        assert!(preflight(&registry, "a", &config).await.is_ok());
+
+        // 服务端的控制通道只使用 TCP
+        let url = format!("server://127.0.0.1:{}/127.0.0.1:8080?mode=2", port);
+        let config = NodePassConfig::from_url(&url).unwrap();
+        assert!(preflight(&registry, "a", &config).await.is_ok());
+    }
+}
@@ -0,0 +1,133 @@
+// 隧道进程的优雅停止：先请求退出，超时后再强制结束
+use crate::liveness::ProcessLiveness;
+use serde::Serialize;
+use std::time::Duration;
+use tokio::process::Child;
+use tokio::sync::oneshot;
+
+// 默认优雅退出等待时间
+pub const DEFAULT_STOP_TIMEOUT_MS: u64 = 5000;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum StopMethod {
+    // 进程在宽限期内自行退出
+    #[serde(rename = "graceful")]
+    Graceful,
+    // 超时或无法发送退出信号，已强制结束
+    #[serde(rename = "forced")]
+    Forced,
+}
+
+impl StopMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StopMethod::Graceful => "graceful",
+            StopMethod::Forced => "forced",
+        }
+    }
+}
+
+// 受监控的隧道进程：本进程启动的子进程，或GUI重启后重新接管的进程
+pub enum TunnelProcess {
+    Spawned(Child),
+    Adopted(u32),
+}
+
+impl TunnelProcess {
+    // 等待进程退出；重新接管的进程无法获取退出码
+    pub async fn wait(
+        &mut self,
+        liveness: &dyn ProcessLiveness,
+    ) -> std::io::Result<Option<std::process::ExitStatus>> {
+        match self {
+            TunnelProcess::Spawned(child) => child.wait().await.map(Some),
+            TunnelProcess::Adopted(pid) => {
+                liveness.wait_for_exit(*pid).await;
+                Ok(None)
+            }
+        }
+    }
+
+    pub async fn stop(
+        &mut self,
+        liveness: &dyn ProcessLiveness,
+        grace_period: Duration,
+    ) -> (std::io::Result<Option<std::process::ExitStatus>>, StopMethod) {
+        match self {
+            TunnelProcess::Spawned(child) => {
+                let (status, method) = stop_child(liveness, child, grace_period).await;
+                (status.map(Some), method)
+            }
+            TunnelProcess::Adopted(pid) => (Ok(None), stop_pid(liveness, *pid, grace_period).await),
+        }
+    }
+}
+
+// 发给隧道监控任务的停止请求
+pub struct StopRequest {
+    pub grace_period: Duration,
+    // 进程退出后由监控任务立即重新启动，而不是结束监控
+    pub restart: bool,
+    pub done: oneshot::Sender<StopOutcome>,
+}
+
+// 停止请求的处理结果，在注册表更新为已退出之后才发送
+pub struct StopOutcome {
+    pub method: StopMethod,
+    // 重启请求的结果：新进程ID或启动失败的原因
+    pub restarted: Option<Result<u32, String>>,
+}
+
+// 停止子进程：先请求优雅退出，等待宽限期后强制结束
+pub async fn stop_child(
+    liveness: &dyn ProcessLiveness,
+    child: &mut Child,
+    grace_period: Duration,
+) -> (std::io::Result<std::process::ExitStatus>, StopMethod) {
+    let requested = match child.id() {
+        // Unix 发送 SIGTERM，Windows 向进程组发送 CTRL_BREAK
+        Some(pid) => liveness.terminate(pid, false),
+        None => Err("进程已退出".to_string()),
+    };
+
+    match requested {
+        Ok(()) => {
+            if let Ok(status) = tokio::time::timeout(grace_period, child.wait()).await {
+                return (status, StopMethod::Graceful);
+            }
+            println!("进程未在 {} ms 内退出，强制结束", grace_period.as_millis());
+        }
+        Err(e) => {
+            println!("请求进程优雅退出失败: {}，强制结束", e);
+        }
+    }
+
+    if let Err(e) = child.start_kill() {
+        println!("强制结束进程失败: {}", e);
+    }
+    (child.wait().await, StopMethod::Forced)
+}
+
+// 停止非本进程持有句柄的进程（例如前端记录的旧PID）
+pub async fn stop_pid(liveness: &dyn ProcessLiveness, pid: u32, grace_period: Duration) -> StopMethod {
+    match liveness.terminate(pid, false) {
+        Ok(()) => {
+            if tokio::time::timeout(grace_period, liveness.wait_for_exit(pid))
+                .await
+                .is_ok()
+            {
+                return StopMethod::Graceful;
+            }
+            println!("进程 {} 未在 {} ms 内退出，强制结束", pid, grace_period.as_millis());
+        }
+        Err(e) => {
+            println!("请求进程 {} 优雅退出失败: {}，强制结束", pid, e);
+        }
+    }
+
+    if let Err(e) = liveness.terminate(pid, true) {
+        println!("强制结束进程 {} 失败: {}", pid, e);
+    }
+    let _ = tokio::time::timeout(grace_period, liveness.wait_for_exit(pid)).await;
+    StopMethod::Forced
+}
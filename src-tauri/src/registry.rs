@@ -0,0 +1,601 @@
+// 后端隧道注册表：按隧道ID统一管理隧道定义与运行时状态
+use crate::process_control::{StopRequest, DEFAULT_STOP_TIMEOUT_MS};
+use crate::restart::{RestartPolicy, RestartState, RestartStats};
+use crate::runtime_state::{self, RuntimeRecord};
+use crate::nodepass_config::NodePassConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+
+// 内存中保留的最大日志行数
+const MAX_LOG_LINES: usize = 500;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TunnelDefinition {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(flatten)]
+    pub config: NodePassConfig,
+    #[serde(rename = "restartPolicy", default)]
+    pub restart_policy: RestartPolicy,
+    // 停止时等待进程优雅退出的时间
+    #[serde(rename = "stopTimeoutMs", default = "default_stop_timeout_ms")]
+    pub stop_timeout_ms: u64,
+    #[serde(rename = "createdAt", default)]
+    pub created_at: Option<String>,
+    // 固定使用的 NodePass 版本，为空时使用当前版本
+    #[serde(rename = "nodepassVersion", default, skip_serializing_if = "Option::is_none")]
+    pub nodepass_version: Option<String>,
+}
+
+fn default_stop_timeout_ms() -> u64 {
+    DEFAULT_STOP_TIMEOUT_MS
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TunnelStatus {
+    #[default]
+    #[serde(rename = "stopped")]
+    Stopped,
+    #[serde(rename = "running")]
+    Running,
+    #[serde(rename = "restarting")]
+    Restarting,
+    #[serde(rename = "stopping")]
+    Stopping,
+    #[serde(rename = "error")]
+    Error,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct TunnelRuntime {
+    pub status: TunnelStatus,
+    pub pid: Option<u32>,
+    // 进程启动时间（Unix毫秒）
+    pub started_at: Option<u64>,
+    pub exit_code: Option<i32>,
+    pub error: Option<String>,
+}
+
+struct TunnelEntry {
+    definition: TunnelDefinition,
+    runtime: TunnelRuntime,
+    restart: RestartState,
+    logs: Arc<Mutex<Vec<String>>>,
+    // 向监控任务发送停止请求的通道，仅在进程运行时存在
+    control: Option<mpsc::UnboundedSender<StopRequest>>,
+    // 当前进程的启动信息，写入运行时状态文件
+    process: Option<RuntimeRecord>,
+}
+
+impl TunnelEntry {
+    fn new(definition: TunnelDefinition) -> Self {
+        Self {
+            definition,
+            runtime: TunnelRuntime::default(),
+            restart: RestartState::default(),
+            logs: Arc::new(Mutex::new(Vec::new())),
+            control: None,
+            process: None,
+        }
+    }
+}
+
+// 返回给前端的隧道快照
+#[derive(Debug, Serialize, Clone)]
+pub struct TunnelSnapshot {
+    #[serde(flatten)]
+    pub definition: TunnelDefinition,
+    pub runtime: TunnelRuntime,
+    pub restarts: RestartStats,
+}
+
+#[derive(Clone)]
+pub struct TunnelRegistry {
+    entries: Arc<Mutex<HashMap<String, TunnelEntry>>>,
+    // 无法对应到隧道、等待用户处理的遗留进程
+    orphans: Arc<Mutex<Vec<RuntimeRecord>>>,
+    store_file: PathBuf,
+    runtime_file: PathBuf,
+    // 加载隧道定义时遇到的问题，需要提示用户
+    load_error: Option<String>,
+    // 隧道定义文件无法读取或移开时停止保存，避免覆盖用户数据
+    writable: bool,
+}
+
+fn backup_path(path: &Path) -> PathBuf {
+    path.with_extension("json.bak")
+}
+
+fn read_definitions(path: &Path) -> Result<Vec<TunnelDefinition>, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("读取隧道定义失败: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("解析隧道定义失败: {}", e))
+}
+
+// 读取隧道定义，返回 (定义, 错误信息, 是否允许保存)
+fn load_definitions(store_file: &Path) -> (Vec<TunnelDefinition>, Option<String>, bool) {
+    let content = match fs::read_to_string(store_file) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return (Vec::new(), None, true),
+        Err(e) => {
+            return (
+                Vec::new(),
+                Some(format!("读取隧道定义失败，已暂停保存: {}", e)),
+                false,
+            )
+        }
+    };
+    let error = match serde_json::from_str(&content) {
+        Ok(definitions) => return (definitions, None, true),
+        Err(e) => e,
+    };
+
+    // 损坏的文件改名保留，再尝试使用备份
+    let corrupt = store_file.with_extension(format!(
+        "json.corrupt-{}",
+        chrono::Local::now().timestamp_millis()
+    ));
+    if let Err(e) = fs::rename(store_file, &corrupt) {
+        return (
+            Vec::new(),
+            Some(format!(
+                "隧道定义文件已损坏 ({})，且无法改名保留，已暂停保存: {}",
+                error, e
+            )),
+            false,
+        );
+    }
+    let message = format!(
+        "隧道定义文件已损坏 ({})，已另存为 {}",
+        error,
+        corrupt.display()
+    );
+    match read_definitions(&backup_path(store_file)) {
+        Ok(definitions) => (
+            definitions,
+            Some(format!("{}，已从备份恢复", message)),
+            true,
+        ),
+        Err(_) => (Vec::new(), Some(message), true),
+    }
+}
+
+impl TunnelRegistry {
+    // 从磁盘加载隧道定义，运行时状态从空开始；文件损坏时改名保留并尝试使用备份
+    pub fn load(store_file: PathBuf, runtime_file: PathBuf) -> Self {
+        let (definitions, load_error, writable) = load_definitions(&store_file);
+        if let Some(error) = &load_error {
+            println!("{}", error);
+        }
+
+        let entries = definitions
+            .into_iter()
+            .map(|definition| (definition.id.clone(), TunnelEntry::new(definition)))
+            .collect();
+
+        Self {
+            entries: Arc::new(Mutex::new(entries)),
+            orphans: Arc::new(Mutex::new(Vec::new())),
+            store_file,
+            runtime_file,
+            load_error,
+            writable,
+        }
+    }
+
+    pub fn load_error(&self) -> Option<String> {
+        self.load_error.clone()
+    }
+
+    // 上次运行时留下的进程记录
+    pub fn previous_runtime(&self) -> Vec<RuntimeRecord> {
+        runtime_state::load(&self.runtime_file)
+    }
+
+    // 写入运行时状态文件，持有 entries 锁时调用
+    fn persist_runtime(&self, entries: &HashMap<String, TunnelEntry>) {
+        let orphans = match self.orphans.lock() {
+            Ok(orphans) => orphans,
+            Err(_) => return,
+        };
+        let records: Vec<&RuntimeRecord> = entries
+            .values()
+            .filter_map(|entry| entry.process.as_ref())
+            .chain(orphans.iter())
+            .collect();
+        if let Err(e) = runtime_state::save(&self.runtime_file, &records) {
+            println!("{}", e);
+        }
+    }
+
+    pub fn orphans(&self) -> Vec<RuntimeRecord> {
+        self.orphans
+            .lock()
+            .map(|orphans| orphans.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn set_orphans(&self, records: Vec<RuntimeRecord>) {
+        if let Ok(entries) = self.lock() {
+            if let Ok(mut orphans) = self.orphans.lock() {
+                *orphans = records;
+            }
+            self.persist_runtime(&entries);
+        }
+    }
+
+    pub fn remove_orphan(&self, pid: u32) -> Option<RuntimeRecord> {
+        let entries = self.lock().ok()?;
+        let removed = {
+            let mut orphans = self.orphans.lock().ok()?;
+            let index = orphans.iter().position(|record| record.pid == pid)?;
+            orphans.remove(index)
+        };
+        self.persist_runtime(&entries);
+        Some(removed)
+    }
+
+    fn persist(&self, entries: &HashMap<String, TunnelEntry>) -> Result<(), String> {
+        let mut definitions: Vec<&TunnelDefinition> =
+            entries.values().map(|entry| &entry.definition).collect();
+        definitions.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+
+        let content = serde_json::to_string_pretty(&definitions)
+            .map_err(|e| format!("序列化隧道定义失败: {}", e))?;
+        self.write_atomic(content.as_bytes())
+    }
+
+    // 先写临时文件再改名，并保留上一次的定义作为备份
+    fn write_atomic(&self, content: &[u8]) -> Result<(), String> {
+        if !self.writable {
+            return Err(self
+                .load_error
+                .clone()
+                .unwrap_or_else(|| "隧道定义文件不可用，已暂停保存".to_string()));
+        }
+        if let Some(dir) = self.store_file.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("创建配置目录失败: {}", e))?;
+        }
+
+        let temp_file = self.store_file.with_extension("json.tmp");
+        let mut file =
+            fs::File::create(&temp_file).map_err(|e| format!("创建临时隧道定义文件失败: {}", e))?;
+        file.write_all(content)
+            .and_then(|_| file.sync_all())
+            .map_err(|e| format!("写入临时隧道定义文件失败: {}", e))?;
+        drop(file);
+
+        if self.store_file.exists() {
+            fs::copy(&self.store_file, backup_path(&self.store_file))
+                .map_err(|e| format!("备份隧道定义失败: {}", e))?;
+        }
+        fs::rename(&temp_file, &self.store_file).map_err(|e| {
+            let _ = fs::remove_file(&temp_file);
+            format!("保存隧道定义失败: {}", e)
+        })
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, HashMap<String, TunnelEntry>>, String> {
+        self.entries
+            .lock()
+            .map_err(|_| "隧道注册表已损坏".to_string())
+    }
+
+    fn snapshot(entry: &TunnelEntry) -> TunnelSnapshot {
+        TunnelSnapshot {
+            definition: entry.definition.clone(),
+            runtime: entry.runtime.clone(),
+            restarts: entry.restart.stats(&entry.definition.id),
+        }
+    }
+
+    // 新增或更新隧道定义，保留运行时状态
+    pub fn upsert(&self, definition: TunnelDefinition) -> Result<TunnelSnapshot, String> {
+        let mut entries = self.lock()?;
+        let entry = entries
+            .entry(definition.id.clone())
+            .or_insert_with(|| TunnelEntry::new(definition.clone()));
+        entry.definition = definition;
+        let snapshot = Self::snapshot(entry);
+        self.persist(&entries)?;
+        Ok(snapshot)
+    }
+
+    // 导入旧版本保存在其他文件中的隧道定义，已存在的ID保持不变；返回导入数量
+    pub fn import(&self, definitions: Vec<TunnelDefinition>) -> Result<usize, String> {
+        let mut entries = self.lock()?;
+        let mut imported = 0;
+        for definition in definitions {
+            if entries.contains_key(&definition.id) {
+                continue;
+            }
+            entries.insert(definition.id.clone(), TunnelEntry::new(definition));
+            imported += 1;
+        }
+        if imported > 0 {
+            self.persist(&entries)?;
+        }
+        Ok(imported)
+    }
+
+    pub fn remove(&self, tunnel_id: &str) -> Result<(), String> {
+        let mut entries = self.lock()?;
+        if let Some(entry) = entries.get(tunnel_id) {
+            if entry.runtime.pid.is_some() {
+                return Err(format!("隧道 {} 正在运行，请先停止", tunnel_id));
+            }
+        }
+        entries.remove(tunnel_id);
+        self.persist(&entries)
+    }
+
+    pub fn list(&self) -> Vec<TunnelSnapshot> {
+        let entries = match self.lock() {
+            Ok(entries) => entries,
+            Err(_) => return Vec::new(),
+        };
+        let mut snapshots: Vec<TunnelSnapshot> = entries.values().map(Self::snapshot).collect();
+        snapshots.sort_by(|a, b| {
+            a.definition
+                .created_at
+                .cmp(&b.definition.created_at)
+                .then(a.definition.id.cmp(&b.definition.id))
+        });
+        snapshots
+    }
+
+    pub fn get(&self, tunnel_id: &str) -> Option<TunnelSnapshot> {
+        self.lock().ok()?.get(tunnel_id).map(Self::snapshot)
+    }
+
+    pub fn definition(&self, tunnel_id: &str) -> Option<TunnelDefinition> {
+        self.lock()
+            .ok()?
+            .get(tunnel_id)
+            .map(|entry| entry.definition.clone())
+    }
+
+    pub fn logs(&self, tunnel_id: &str) -> Option<Arc<Mutex<Vec<String>>>> {
+        self.lock()
+            .ok()?
+            .get(tunnel_id)
+            .map(|entry| entry.logs.clone())
+    }
+
+    pub fn find_by_pid(&self, pid: u32) -> Option<String> {
+        self.lock()
+            .ok()?
+            .values()
+            .find(|entry| entry.runtime.pid == Some(pid))
+            .map(|entry| entry.definition.id.clone())
+    }
+
+    pub fn pid_of(&self, tunnel_id: &str) -> Option<u32> {
+        self.lock().ok()?.get(tunnel_id).and_then(|entry| entry.runtime.pid)
+    }
+
+    // 当前有进程在运行的隧道 (隧道ID, PID)
+    pub fn running(&self) -> Vec<(String, u32)> {
+        match self.lock() {
+            Ok(entries) => entries
+                .values()
+                .filter_map(|entry| {
+                    entry
+                        .runtime
+                        .pid
+                        .map(|pid| (entry.definition.id.clone(), pid))
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    // 运行中隧道使用的可执行文件 (隧道ID, 路径)
+    pub fn running_executables(&self) -> Vec<(String, String)> {
+        match self.lock() {
+            Ok(entries) => entries
+                .values()
+                .filter_map(|entry| {
+                    entry
+                        .process
+                        .as_ref()
+                        .map(|process| (entry.definition.id.clone(), process.executable.clone()))
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    pub fn running_count(&self) -> usize {
+        self.running().len()
+    }
+
+    pub fn stop_handle(&self, tunnel_id: &str) -> Option<mpsc::UnboundedSender<StopRequest>> {
+        self.lock()
+            .ok()?
+            .get(tunnel_id)
+            .and_then(|entry| entry.control.clone())
+    }
+
+    // 记录新进程已启动（或已重新接管）
+    pub fn mark_running(
+        &self,
+        tunnel_id: &str,
+        process: RuntimeRecord,
+        control: mpsc::UnboundedSender<StopRequest>,
+    ) {
+        if let Ok(mut entries) = self.lock() {
+            if let Some(entry) = entries.get_mut(tunnel_id) {
+                entry.control = Some(control);
+                entry.runtime = TunnelRuntime {
+                    status: TunnelStatus::Running,
+                    pid: Some(process.pid),
+                    started_at: Some(process.started_at),
+                    exit_code: None,
+                    error: None,
+                };
+                entry.process = Some(process);
+            }
+            self.persist_runtime(&entries);
+        }
+    }
+
+    // 记录进程已退出
+    pub fn mark_exited(&self, tunnel_id: &str, exit_code: Option<i32>, error: Option<String>) {
+        if let Ok(mut entries) = self.lock() {
+            if let Some(entry) = entries.get_mut(tunnel_id) {
+                entry.runtime.status = if error.is_some() {
+                    TunnelStatus::Error
+                } else {
+                    TunnelStatus::Stopped
+                };
+                entry.runtime.pid = None;
+                entry.control = None;
+                entry.process = None;
+                entry.runtime.exit_code = exit_code;
+                entry.runtime.error = error;
+                entry.restart.last_exit_code = exit_code;
+            }
+            self.persist_runtime(&entries);
+        }
+    }
+
+    pub fn set_status(&self, tunnel_id: &str, status: TunnelStatus) {
+        if let Ok(mut entries) = self.lock() {
+            if let Some(entry) = entries.get_mut(tunnel_id) {
+                entry.runtime.status = status;
+            }
+        }
+    }
+
+    // 记录已停止隧道的错误原因，例如触发致命错误规则后被停止
+    pub fn set_error(&self, tunnel_id: &str, error: String) {
+        if let Ok(mut entries) = self.lock() {
+            if let Some(entry) = entries.get_mut(tunnel_id) {
+                if entry.runtime.pid.is_none() {
+                    entry.runtime.status = TunnelStatus::Error;
+                    entry.runtime.error = Some(error);
+                }
+            }
+        }
+    }
+
+    // 读写重启状态
+    pub fn with_restart<T>(&self, tunnel_id: &str, f: impl FnOnce(&mut RestartState) -> T) -> Option<T> {
+        let mut entries = self.lock().ok()?;
+        entries.get_mut(tunnel_id).map(|entry| f(&mut entry.restart))
+    }
+}
+
+// 追加一行日志，超出上限时丢弃最早的记录
+pub fn push_log(logs: &Arc<Mutex<Vec<String>>>, line: String) {
+    if let Ok(mut log_vec) = logs.lock() {
+        log_vec.push(line);
+        // 限制日志数量，保留最近500条
+        if log_vec.len() > MAX_LOG_LINES {
+            log_vec.drain(0..100); // 删除前100条
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn definition(id: &str, created_at: &str) -> TunnelDefinition {
+        TunnelDefinition {
+            id: id.to_string(),
+            name: format!("隧道 {}", id),
+            config: NodePassConfig::from_url("server://:10101/127.0.0.1:8080").unwrap(),
+            restart_policy: RestartPolicy::default(),
+            stop_timeout_ms: DEFAULT_STOP_TIMEOUT_MS,
+            created_at: Some(created_at.to_string()),
+            nodepass_version: None,
+        }
+    }
+
+    fn open(dir: &Path) -> TunnelRegistry {
+        TunnelRegistry::load(dir.join("tunnels.json"), dir.join("runtime.json"))
+    }
+
+    fn ids(registry: &TunnelRegistry) -> Vec<String> {
+        registry
+            .list()
+            .into_iter()
+            .map(|snapshot| snapshot.definition.id)
+            .collect()
+    }
+
+    #[test]
+    fn missing_file_loads_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = open(dir.path());
+        assert!(registry.list().is_empty());
+        assert_eq!(registry.load_error(), None);
+    }
+
+    #[test]
+    fn upsert_and_remove_persist() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = open(dir.path());
+        registry.upsert(definition("b", "2024-01-02")).unwrap();
+        registry.upsert(definition("a", "2024-01-01")).unwrap();
+
+        let mut renamed = definition("b", "2024-01-02");
+        renamed.name = "新名称".to_string();
+        registry.upsert(renamed).unwrap();
+
+        let reloaded = open(dir.path());
+        assert_eq!(ids(&reloaded), ["a", "b"]);
+        assert_eq!(reloaded.definition("b").unwrap().name, "新名称");
+        // 第二次写入时保留了上一次的文件
+        assert!(dir.path().join("tunnels.json.bak").exists());
+        assert!(!dir.path().join("tunnels.json.tmp").exists());
+
+        reloaded.remove("a").unwrap();
+        assert_eq!(ids(&open(dir.path())), ["b"]);
+    }
+
+    #[test]
+    fn corrupt_file_is_kept_aside() {
+        let dir = tempfile::tempdir().unwrap();
+        let store_file = dir.path().join("tunnels.json");
+        fs::write(&store_file, "[{\"id\": ").unwrap();
+
+        let registry = open(dir.path());
+        assert!(registry.list().is_empty());
+        assert!(registry.load_error().unwrap().contains("已损坏"));
+        assert!(!store_file.exists());
+
+        let corrupt: Vec<PathBuf> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.to_string_lossy().contains("tunnels.json.corrupt-"))
+            .collect();
+        assert_eq!(corrupt.len(), 1);
+        assert_eq!(fs::read_to_string(&corrupt[0]).unwrap(), "[{\"id\": ");
+
+        // 之后保存不会覆盖改名保留的文件
+        registry.upsert(definition("a", "2024-01-01")).unwrap();
+        assert_eq!(fs::read_to_string(&corrupt[0]).unwrap(), "[{\"id\": ");
+        assert_eq!(ids(&open(dir.path())), ["a"]);
+    }
+
+    #[test]
+    fn corrupt_file_recovers_from_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = open(dir.path());
+        registry.upsert(definition("a", "2024-01-01")).unwrap();
+        registry.upsert(definition("b", "2024-01-02")).unwrap();
+        fs::write(dir.path().join("tunnels.json"), "not json").unwrap();
+
+        let recovered = open(dir.path());
+        assert_eq!(ids(&recovered), ["a"]);
+        assert!(recovered.load_error().unwrap().contains("已从备份恢复"));
+    }
+}
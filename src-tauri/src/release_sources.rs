@@ -0,0 +1,496 @@
+// NodePass 发布源：GitHub API、GitHub 代理前缀、返回相同 JSON 的自建镜像，以及普通的目录列表。
+// 按顺序尝试，每个源单独设置超时
+use crate::update_check::{self, UpdateChecker};
+use crate::{GitHubAsset, GitHubRelease};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+const GITHUB_API_LATEST: &str = "https://api.github.com/repos/yosebyte/nodepass/releases/latest";
+const GITHUB_API_TAGS: &str = "https://api.github.com/repos/yosebyte/nodepass/releases/tags";
+const GITHUB_DOWNLOAD_BASE: &str = "https://github.com/yosebyte/nodepass/releases/download";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum SourceKind {
+    // 官方 GitHub API
+    #[serde(rename = "github")]
+    GitHub,
+    // GitHub 加速代理，url 为前缀，例如 https://ghproxy.example.com/
+    #[serde(rename = "github_proxy")]
+    GitHubProxy,
+    // 返回 GitHub 发布 JSON 的镜像，url 为 JSON 地址
+    #[serde(rename = "mirror")]
+    Mirror,
+    // 直接列出发布文件的目录，url 为目录地址
+    #[serde(rename = "directory")]
+    Directory,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReleaseSource {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    pub kind: SourceKind,
+    #[serde(default)]
+    pub url: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(rename = "timeoutSecs", default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_timeout_secs() -> u64 {
+    15
+}
+
+impl ReleaseSource {
+    pub fn label(&self) -> &str {
+        if self.name.is_empty() {
+            &self.id
+        } else {
+            &self.name
+        }
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs.max(1))
+    }
+
+    // 通过该源下载指定版本文件的地址；自建镜像的地址只能从其 JSON 中得到
+    pub fn download_url(&self, tag: &str, filename: &str) -> Option<String> {
+        match self.kind {
+            SourceKind::GitHub => Some(format!("{}/{}/{}", GITHUB_DOWNLOAD_BASE, tag, filename)),
+            SourceKind::GitHubProxy => Some(format!(
+                "{}{}/{}/{}",
+                self.url, GITHUB_DOWNLOAD_BASE, tag, filename
+            )),
+            SourceKind::Directory => reqwest::Url::parse(&directory_base(&self.url))
+                .and_then(|base| base.join(filename))
+                .map(|url| url.to_string())
+                .ok(),
+            SourceKind::Mirror => None,
+        }
+    }
+}
+
+fn default_sources() -> Vec<ReleaseSource> {
+    vec![ReleaseSource {
+        id: "github".to_string(),
+        name: "GitHub".to_string(),
+        kind: SourceKind::GitHub,
+        url: String::new(),
+        enabled: true,
+        timeout_secs: default_timeout_secs(),
+    }]
+}
+
+// 目录地址需要以 / 结尾，否则相对地址会替换掉最后一级
+fn directory_base(url: &str) -> String {
+    if url.ends_with('/') {
+        url.to_string()
+    } else {
+        format!("{}/", url)
+    }
+}
+
+#[derive(Clone)]
+pub struct ReleaseSourceStore {
+    sources_file: PathBuf,
+    sources: Arc<Mutex<Vec<ReleaseSource>>>,
+}
+
+impl ReleaseSourceStore {
+    pub fn load(sources_file: PathBuf) -> Self {
+        let sources = fs::read_to_string(&sources_file)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_else(default_sources);
+        Self {
+            sources_file,
+            sources: Arc::new(Mutex::new(sources)),
+        }
+    }
+
+    pub fn sources(&self) -> Vec<ReleaseSource> {
+        self.sources
+            .lock()
+            .map(|sources| sources.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn enabled(&self) -> Vec<ReleaseSource> {
+        self.sources()
+            .into_iter()
+            .filter(|source| source.enabled)
+            .collect()
+    }
+
+    pub fn update(&self, sources: Vec<ReleaseSource>) -> Result<(), String> {
+        for source in &sources {
+            if source.kind != SourceKind::GitHub && source.url.trim().is_empty() {
+                return Err(format!("发布源 {} 缺少地址", source.label()));
+            }
+        }
+        let content = serde_json::to_string_pretty(&sources)
+            .map_err(|e| format!("序列化发布源失败: {}", e))?;
+        self.write_atomic(content.as_bytes())?;
+        if let Ok(mut current) = self.sources.lock() {
+            *current = sources;
+        }
+        Ok(())
+    }
+
+    // 先写临时文件再改名，写入中断时不会留下损坏的发布源列表
+    fn write_atomic(&self, content: &[u8]) -> Result<(), String> {
+        if let Some(dir) = self.sources_file.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("创建配置目录失败: {}", e))?;
+        }
+        let temp_file = self.sources_file.with_extension("json.tmp");
+        let mut file =
+            fs::File::create(&temp_file).map_err(|e| format!("创建临时发布源文件失败: {}", e))?;
+        file.write_all(content)
+            .and_then(|_| file.sync_all())
+            .map_err(|e| format!("写入临时发布源文件失败: {}", e))?;
+        drop(file);
+        fs::rename(&temp_file, &self.sources_file).map_err(|e| {
+            let _ = fs::remove_file(&temp_file);
+            format!("保存发布源失败: {}", e)
+        })
+    }
+}
+
+// 依次尝试各个发布源，返回第一个成功的结果，并记录其来源
+pub async fn fetch_latest(
+    client: &reqwest::Client,
+    sources: &[ReleaseSource],
+    checker: &UpdateChecker,
+) -> Result<GitHubRelease, String> {
+    let mut errors = Vec::new();
+
+    for source in sources {
+        println!("从发布源 {} 获取最新版本", source.label());
+        let result = match source.kind {
+            SourceKind::GitHub => {
+                checker
+                    .fetch_release_json(client, GITHUB_API_LATEST, source.timeout())
+                    .await
+            }
+            SourceKind::GitHubProxy => checker
+                .fetch_release_json(
+                    client,
+                    &format!("{}{}", source.url, GITHUB_API_LATEST),
+                    source.timeout(),
+                )
+                .await
+                .map(|release| through_proxy(source, release)),
+            SourceKind::Mirror => {
+                checker
+                    .fetch_release_json(client, &source.url, source.timeout())
+                    .await
+            }
+            SourceKind::Directory => {
+                let release = fetch_directory(client, source, None).await;
+                if let Ok(release) = &release {
+                    checker.remember(release);
+                }
+                release
+            }
+        };
+
+        match result {
+            Ok(mut release) => {
+                release.source = Some(source.label().to_string());
+                return Ok(release);
+            }
+            Err(e) => {
+                println!("发布源 {} 不可用: {}", source.label(), e);
+                errors.push(format!("{}: {}", source.label(), e));
+            }
+        }
+    }
+
+    all_failed(errors)
+}
+
+// 依次尝试各个发布源获取指定版本的发布信息
+pub async fn fetch_by_tag(
+    client: &reqwest::Client,
+    sources: &[ReleaseSource],
+    tag: &str,
+) -> Result<GitHubRelease, String> {
+    let mut errors = Vec::new();
+
+    for source in sources {
+        println!("从发布源 {} 获取版本 {}", source.label(), tag);
+        let result = match source.kind {
+            SourceKind::GitHub => {
+                fetch_release_json(client, &format!("{}/{}", GITHUB_API_TAGS, tag), source.timeout())
+                    .await
+            }
+            SourceKind::GitHubProxy => fetch_release_json(
+                client,
+                &format!("{}{}/{}", source.url, GITHUB_API_TAGS, tag),
+                source.timeout(),
+            )
+            .await
+            .map(|release| through_proxy(source, release)),
+            // 镜像只提供最新版本
+            SourceKind::Mirror => fetch_release_json(client, &source.url, source.timeout())
+                .await
+                .and_then(|release| {
+                    if release.tag_name == tag {
+                        Ok(release)
+                    } else {
+                        Err(format!("镜像中没有版本 {}", tag))
+                    }
+                }),
+            SourceKind::Directory => fetch_directory(client, source, Some(tag)).await,
+        };
+
+        match result {
+            Ok(mut release) => {
+                release.source = Some(source.label().to_string());
+                return Ok(release);
+            }
+            Err(e) => {
+                println!("发布源 {} 不可用: {}", source.label(), e);
+                errors.push(format!("{}: {}", source.label(), e));
+            }
+        }
+    }
+
+    all_failed(errors)
+}
+
+fn all_failed(errors: Vec<String>) -> Result<GitHubRelease, String> {
+    if errors.is_empty() {
+        Err("没有启用的发布源".to_string())
+    } else {
+        Err(format!("所有发布源均不可用: {}", errors.join("；")))
+    }
+}
+
+// 通过 GitHub 代理获取的发布，下载也走代理
+fn through_proxy(source: &ReleaseSource, mut release: GitHubRelease) -> GitHubRelease {
+    for asset in &mut release.assets {
+        asset.browser_download_url = format!("{}{}", source.url, asset.browser_download_url);
+    }
+    release
+}
+
+async fn fetch_release_json(
+    client: &reqwest::Client,
+    url: &str,
+    timeout: Duration,
+) -> Result<GitHubRelease, String> {
+    let response = client
+        .get(url)
+        .header("User-Agent", "NodePass-GUI")
+        .timeout(timeout)
+        .send()
+        .await
+        .map_err(|e| format!("请求发布信息失败: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("发布源返回错误: {}", response.status()));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| format!("解析发布信息失败: {}", e))
+}
+
+// 请求目录列表页面，解析出指定版本或最新版本的发布文件
+async fn fetch_directory(
+    client: &reqwest::Client,
+    source: &ReleaseSource,
+    tag: Option<&str>,
+) -> Result<GitHubRelease, String> {
+    let base = reqwest::Url::parse(&directory_base(&source.url))
+        .map_err(|e| format!("目录地址无效: {}", e))?;
+
+    let response = client
+        .get(base.clone())
+        .timeout(source.timeout())
+        .send()
+        .await
+        .map_err(|e| format!("请求目录失败: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("目录返回错误: {}", response.status()));
+    }
+    let html = response
+        .text()
+        .await
+        .map_err(|e| format!("读取目录失败: {}", e))?;
+
+    parse_directory_listing(&html, &base, tag)
+}
+
+fn href_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"href\s*=\s*["']([^"'?#]+)["']"#).unwrap())
+}
+
+fn versioned_file_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    // 例如 nodepass_1.2.3_linux_amd64.tar.gz、nodepass_1.2.3_checksums.txt
+    RE.get_or_init(|| Regex::new(r"^nodepass_v?(\d+\.\d+\.\d+[0-9A-Za-z.\-]*?)_").unwrap())
+}
+
+// 从目录列表 HTML 中取出 NodePass 发布文件，按版本分组后返回指定版本的一组；
+// 未指定版本时取最高的正式版，没有正式版时才使用预发布版本
+fn parse_directory_listing(
+    html: &str,
+    base: &reqwest::Url,
+    tag: Option<&str>,
+) -> Result<GitHubRelease, String> {
+    let mut files: Vec<(semver::Version, String, String)> = Vec::new();
+    for capture in href_regex().captures_iter(html) {
+        let link = &capture[1];
+        let name = link.rsplit('/').next().unwrap_or(link);
+        let version = match versioned_file_regex()
+            .captures(name)
+            .and_then(|capture| update_check::parse_version(&capture[1]))
+        {
+            Some(version) => version,
+            None => continue,
+        };
+        // 同一文件可能有多个链接（例如图标）
+        if files.iter().any(|(_, existing, _)| existing == name) {
+            continue;
+        }
+        if let Ok(url) = base.join(link) {
+            files.push((version, name.to_string(), url.to_string()));
+        }
+    }
+
+    let latest = match tag {
+        Some(tag) => update_check::parse_version(tag)
+            .filter(|version| files.iter().any(|(v, _, _)| v == version))
+            .ok_or_else(|| format!("目录中没有版本 {}", tag))?,
+        None => {
+            let versions = files.iter().map(|(version, _, _)| version);
+            versions
+                .clone()
+                .filter(|version| version.pre.is_empty())
+                .max()
+                .or_else(|| versions.max())
+                .cloned()
+                .ok_or_else(|| "目录中没有找到 NodePass 发布文件".to_string())?
+        }
+    };
+    let tag = format!("v{}", latest);
+
+    Ok(GitHubRelease {
+        name: tag.clone(),
+        tag_name: tag,
+        published_at: String::new(),
+        html_url: base.to_string(),
+        assets: files
+            .into_iter()
+            .filter(|(version, _, _)| *version == latest)
+            .map(|(_, name, url)| GitHubAsset {
+                name,
+                browser_download_url: url,
+                size: 0,
+            })
+            .collect(),
+        source: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listing(tag: Option<&str>) -> Result<GitHubRelease, String> {
+        let base = reqwest::Url::parse("https://mirror.example.com/nodepass/").unwrap();
+        parse_directory_listing(
+            include_str!("../tests/fixtures/directory_listing.html"),
+            &base,
+            tag,
+        )
+    }
+
+    fn asset_names(release: &GitHubRelease) -> Vec<&str> {
+        release.assets.iter().map(|asset| asset.name.as_str()).collect()
+    }
+
+    #[test]
+    fn directory_listing_picks_latest_stable_release() {
+        let release = listing(None).unwrap();
+        assert_eq!(release.tag_name, "v1.4.0");
+        assert_eq!(
+            asset_names(&release),
+            [
+                "nodepass_1.4.0_checksums.txt",
+                "nodepass_1.4.0_linux_amd64.tar.gz",
+                "nodepass_1.4.0_windows_amd64.zip"
+            ]
+        );
+        assert_eq!(
+            release.assets[2].browser_download_url,
+            "https://mirror.example.com/nodepass/nodepass_1.4.0_windows_amd64.zip"
+        );
+        assert!(crate::checksum::find_checksum_asset(&release.assets).is_some());
+    }
+
+    #[test]
+    fn directory_listing_selects_requested_tag() {
+        let beta = listing(Some("v1.5.0-beta.1")).unwrap();
+        assert_eq!(beta.tag_name, "v1.5.0-beta.1");
+        assert_eq!(beta.assets.len(), 2);
+
+        // 绝对地址的链接保持原样
+        let old = listing(Some("1.2.0")).unwrap();
+        assert_eq!(
+            old.assets[0].browser_download_url,
+            "https://cdn.example.com/nodepass/nodepass_v1.2.0_darwin_arm64.tar.gz"
+        );
+
+        assert!(listing(Some("v9.9.9")).unwrap_err().contains("v9.9.9"));
+    }
+
+    #[test]
+    fn directory_listing_ignores_other_links() {
+        let base = reqwest::Url::parse("https://mirror.example.com/nodepass/").unwrap();
+        let html = r#"<a href="../">../</a><a href="nodepass-dashboard_2.0.0_linux_amd64.tar.gz">x</a>
+            <a href="nodepass_latest_linux_amd64.tar.gz">x</a><a href="README.md">README</a>"#;
+        assert!(parse_directory_listing(html, &base, None).is_err());
+
+        // 只有预发布版本时使用预发布版本
+        let html = r#"<a href="nodepass_2.0.0-rc.1_linux_amd64.tar.gz">x</a>"#;
+        assert_eq!(parse_directory_listing(html, &base, None).unwrap().tag_name, "v2.0.0-rc.1");
+    }
+
+    #[test]
+    fn update_writes_sources_atomically() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("release_sources.json");
+        let store = ReleaseSourceStore::load(file.clone());
+        let mut sources = store.sources();
+        sources.push(ReleaseSource {
+            id: "mirror".to_string(),
+            name: String::new(),
+            kind: SourceKind::Directory,
+            url: "https://mirror.example.com/nodepass".to_string(),
+            enabled: true,
+            timeout_secs: 10,
+        });
+        store.update(sources).unwrap();
+        assert!(!dir.path().join("release_sources.json.tmp").exists());
+        assert_eq!(ReleaseSourceStore::load(file).sources().len(), 2);
+
+        let mut missing_url = store.sources();
+        missing_url[1].url.clear();
+        assert!(store.update(missing_url).is_err());
+    }
+}
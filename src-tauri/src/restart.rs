@@ -0,0 +1,169 @@
+// 隧道自动重启策略（指数退避 + 抖动）
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RestartMode {
+    #[default]
+    #[serde(rename = "never")]
+    Never,
+    #[serde(rename = "on-failure")]
+    OnFailure,
+    #[serde(rename = "always")]
+    Always,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct RestartPolicy {
+    pub mode: RestartMode,
+    // 最大连续重启次数，0 表示不限制
+    #[serde(rename = "maxRetries")]
+    pub max_retries: u32,
+    #[serde(rename = "initialDelayMs")]
+    pub initial_delay_ms: u64,
+    #[serde(rename = "maxDelayMs")]
+    pub max_delay_ms: u64,
+    pub multiplier: f64,
+    // 抖动比例 (0.0 - 1.0)，实际延迟在 delay * (1 ± jitter) 之间
+    pub jitter: f64,
+    // 进程稳定运行超过该时长后，连续重启计数清零
+    #[serde(rename = "resetAfterSecs")]
+    pub reset_after_secs: u64,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            mode: RestartMode::Never,
+            max_retries: 5,
+            initial_delay_ms: 1000,
+            max_delay_ms: 60_000,
+            multiplier: 2.0,
+            jitter: 0.2,
+            reset_after_secs: 300,
+        }
+    }
+}
+
+impl RestartPolicy {
+    // 根据退出情况判断是否需要重启
+    pub fn should_restart(&self, failed: bool) -> bool {
+        match self.mode {
+            RestartMode::Never => false,
+            RestartMode::OnFailure => failed,
+            RestartMode::Always => true,
+        }
+    }
+
+    // 是否已超过最大重启次数
+    pub fn retries_exhausted(&self, attempts: u32) -> bool {
+        self.max_retries > 0 && attempts >= self.max_retries
+    }
+
+    // 计算第 attempt 次重启（从1开始）前的等待时间
+    pub fn backoff_delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(32) as i32;
+        let multiplier = if self.multiplier < 1.0 { 1.0 } else { self.multiplier };
+        let base = (self.initial_delay_ms as f64) * multiplier.powi(exponent);
+        let capped = base.min(self.max_delay_ms.max(self.initial_delay_ms) as f64);
+
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        let factor = 1.0 + jitter * (rand::random::<f64>() * 2.0 - 1.0);
+
+        Duration::from_millis((capped * factor).max(0.0) as u64)
+    }
+}
+
+// 单个隧道的重启状态
+#[derive(Debug, Clone, Default)]
+pub struct RestartState {
+    // 当前连续重启次数
+    pub attempts: u32,
+    // 累计重启次数
+    pub total_restarts: u32,
+    pub last_restart_at: Option<u64>,
+    pub last_exit_code: Option<i32>,
+    // 用户主动停止时置位，监控任务据此放弃重启
+    pub stop_requested: bool,
+    // 每次手动启动时递增；仍在退避等待的旧监控任务发现变化后不再拉起进程
+    pub generation: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RestartStats {
+    pub tunnel_id: String,
+    pub attempts: u32,
+    pub total_restarts: u32,
+    pub last_restart_at: Option<u64>,
+    pub last_exit_code: Option<i32>,
+}
+
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+impl RestartState {
+    pub fn stats(&self, tunnel_id: &str) -> RestartStats {
+        RestartStats {
+            tunnel_id: tunnel_id.to_string(),
+            attempts: self.attempts,
+            total_restarts: self.total_restarts,
+            last_restart_at: self.last_restart_at,
+            last_exit_code: self.last_exit_code,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(mode: RestartMode) -> RestartPolicy {
+        RestartPolicy {
+            mode,
+            jitter: 0.0,
+            ..RestartPolicy::default()
+        }
+    }
+
+    #[test]
+    fn should_restart_follows_mode() {
+        assert!(!policy(RestartMode::Never).should_restart(true));
+        assert!(policy(RestartMode::OnFailure).should_restart(true));
+        assert!(!policy(RestartMode::OnFailure).should_restart(false));
+        assert!(policy(RestartMode::Always).should_restart(false));
+    }
+
+    #[test]
+    fn retries_exhausted_respects_unlimited() {
+        let mut policy = policy(RestartMode::Always);
+        assert!(!policy.retries_exhausted(4));
+        assert!(policy.retries_exhausted(5));
+        policy.max_retries = 0;
+        assert!(!policy.retries_exhausted(u32::MAX));
+    }
+
+    #[test]
+    fn backoff_grows_and_is_capped() {
+        let policy = policy(RestartMode::Always);
+        assert_eq!(policy.backoff_delay(1), Duration::from_millis(1000));
+        assert_eq!(policy.backoff_delay(3), Duration::from_millis(4000));
+        assert_eq!(policy.backoff_delay(20), Duration::from_millis(60_000));
+    }
+
+    #[test]
+    fn backoff_jitter_stays_in_range() {
+        let policy = RestartPolicy {
+            jitter: 0.5,
+            ..policy(RestartMode::Always)
+        };
+        for _ in 0..100 {
+            let delay = policy.backoff_delay(2).as_millis();
+            assert!((1000..=3000).contains(&delay), "{}", delay);
+        }
+    }
+}
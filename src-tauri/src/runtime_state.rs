@@ -0,0 +1,167 @@
+// 运行时状态文件：记录运行中的隧道进程，GUI 重启后据此重新接管仍存活的进程
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RuntimeRecord {
+    #[serde(rename = "tunnelId")]
+    pub tunnel_id: String,
+    pub pid: u32,
+    pub executable: String,
+    pub args: Vec<String>,
+    // 进程启动时间（Unix毫秒）
+    #[serde(rename = "startedAt")]
+    pub started_at: u64,
+    // 进程输出重定向到的文件
+    #[serde(rename = "stdoutFile")]
+    pub stdout_file: PathBuf,
+    #[serde(rename = "stderrFile")]
+    pub stderr_file: PathBuf,
+}
+
+impl RuntimeRecord {
+    pub fn command_line(&self) -> String {
+        std::iter::once(self.executable.as_str())
+            .chain(self.args.iter().map(|arg| arg.as_str()))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+pub fn load(runtime_file: &Path) -> Vec<RuntimeRecord> {
+    fs::read_to_string(runtime_file)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+pub fn save(runtime_file: &Path, records: &[&RuntimeRecord]) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(records)
+        .map_err(|e| format!("序列化运行时状态失败: {}", e))?;
+    fs::write(runtime_file, content).map_err(|e| format!("保存运行时状态失败: {}", e))
+}
+
+// 隧道ID由前端生成，替换掉不适合作为文件名的字符
+pub fn safe_file_name(tunnel_id: &str) -> String {
+    tunnel_id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+// 隧道输出文件路径 (stdout, stderr)
+pub fn capture_paths(capture_dir: &Path, tunnel_id: &str) -> (PathBuf, PathBuf) {
+    let name = safe_file_name(tunnel_id);
+    (
+        capture_dir.join(format!("{}.stdout.log", name)),
+        capture_dir.join(format!("{}.stderr.log", name)),
+    )
+}
+
+// 读取任务读过这么多内容后清空输出文件，避免长期运行的隧道占满磁盘
+pub const CAPTURE_TRUNCATE_BYTES: u64 = 1024 * 1024;
+
+// 创建（清空）输出文件。以追加方式打开，文件被清空后进程从头继续写入
+pub fn create_capture_file(path: &Path) -> Result<fs::File, String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建日志目录失败: {}", e))?;
+    }
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|file| file.set_len(0).map(|_| file))
+        .map_err(|e| format!("创建日志文件 {:?} 失败: {}", path, e))?;
+    Ok(file)
+}
+
+// 清空已读完的输出文件
+pub fn truncate_capture_file(path: &Path) -> std::io::Result<()> {
+    fs::OpenOptions::new().write(true).open(path)?.set_len(0)
+}
+
+// 检查进程当前的命令行是否与记录一致，避免PID被其他进程复用后误接管
+pub fn command_line_matches(actual: &str, record: &RuntimeRecord) -> bool {
+    let executable_name = Path::new(&record.executable)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| record.executable.clone());
+
+    actual.contains(&executable_name) && record.args.iter().all(|arg| actual.contains(arg.as_str()))
+}
+
+// 查询进程的命令行
+#[cfg(target_os = "linux")]
+pub async fn query_command_line(pid: u32) -> Option<String> {
+    let raw = tokio::fs::read(format!("/proc/{}/cmdline", pid)).await.ok()?;
+    let args: Vec<String> = raw
+        .split(|b| *b == 0)
+        .filter(|part| !part.is_empty())
+        .map(|part| String::from_utf8_lossy(part).to_string())
+        .collect();
+    if args.is_empty() {
+        None
+    } else {
+        Some(args.join(" "))
+    }
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+pub async fn query_command_line(pid: u32) -> Option<String> {
+    let output = tokio::process::Command::new("ps")
+        .args(["-o", "command=", "-p", &pid.to_string()])
+        .output()
+        .await
+        .ok()?;
+    let command_line = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if output.status.success() && !command_line.is_empty() {
+        Some(command_line)
+    } else {
+        None
+    }
+}
+
+#[cfg(windows)]
+pub async fn query_command_line(pid: u32) -> Option<String> {
+    let mut cmd = tokio::process::Command::new("powershell");
+    cmd.args([
+        "-NoProfile",
+        "-Command",
+        &format!(
+            "(Get-CimInstance Win32_Process -Filter 'ProcessId={}').CommandLine",
+            pid
+        ),
+    ]);
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+    let output = cmd.output().await.ok()?;
+    let command_line = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if output.status.success() && !command_line.is_empty() {
+        Some(command_line)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn capture_file_keeps_writing_after_truncate() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("capture").join("a.stdout.log");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "上次运行的输出\n").unwrap();
+
+        let mut writer = create_capture_file(&path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+        writer.write_all(b"first\n").unwrap();
+
+        truncate_capture_file(&path).unwrap();
+        writer.write_all(b"second\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second\n");
+    }
+}
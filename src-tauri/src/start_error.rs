@@ -0,0 +1,57 @@
+// 启动隧道失败时返回给前端的错误，按类型区分以便界面给出针对性提示
+use crate::config_validation::FieldError;
+use crate::port_check::PortOwner;
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "kind")]
+pub enum StartError {
+    // 配置检查未通过
+    #[serde(rename = "invalid_config")]
+    InvalidConfig {
+        message: String,
+        errors: Vec<FieldError>,
+    },
+    // 与另一个运行中的隧道监听同一地址
+    #[serde(rename = "port_conflict")]
+    PortConflict {
+        message: String,
+        address: String,
+        #[serde(rename = "tunnelId")]
+        tunnel_id: String,
+    },
+    // 端口已被其它程序占用或无法绑定
+    #[serde(rename = "port_in_use")]
+    PortInUse {
+        message: String,
+        address: String,
+        protocol: String,
+        owner: Option<PortOwner>,
+    },
+    #[serde(rename = "other")]
+    Other { message: String },
+}
+
+impl StartError {
+    pub fn message(&self) -> &str {
+        match self {
+            StartError::InvalidConfig { message, .. }
+            | StartError::PortConflict { message, .. }
+            | StartError::PortInUse { message, .. }
+            | StartError::Other { message } => message,
+        }
+    }
+}
+
+impl fmt::Display for StartError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl From<String> for StartError {
+    fn from(message: String) -> Self {
+        StartError::Other { message }
+    }
+}
@@ -0,0 +1,541 @@
+// 按下载地址解析系统代理：环境变量（大小写均可）及 NO_PROXY、PAC 脚本、
+// Windows 的 ProxyServer/ProxyOverride 以及 WinHTTP 设置
+use crate::pac;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+// 下载 PAC 脚本的超时时间
+const PAC_FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+// NO_PROXY 中的一项
+#[derive(Debug, Clone)]
+enum NoProxyRule {
+    // "*"：所有地址都不使用代理
+    All,
+    // 域名及其子域名，例如 example.com、.example.com、*.example.com
+    Domain { name: String, port: Option<u16> },
+    Ip { ip: IpAddr, port: Option<u16> },
+    // 网段，例如 10.0.0.0/8、fd00::/8
+    Cidr { network: IpAddr, prefix: u8 },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NoProxy {
+    rules: Vec<NoProxyRule>,
+}
+
+impl NoProxy {
+    // 逗号或空白分隔
+    pub fn parse(value: &str) -> Self {
+        let rules = value
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter_map(|entry| parse_no_proxy_rule(entry.trim()))
+            .collect();
+        Self { rules }
+    }
+
+    pub fn matches(&self, host: &str, port: u16) -> bool {
+        let host = host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .trim_end_matches('.')
+            .to_lowercase();
+        let host_ip = host.parse::<IpAddr>().ok();
+        let port_matches = |rule_port: &Option<u16>| rule_port.is_none_or(|p| p == port);
+
+        self.rules.iter().any(|rule| match rule {
+            NoProxyRule::All => true,
+            NoProxyRule::Domain { name, port } => {
+                port_matches(port)
+                    && (host == *name || host.ends_with(&format!(".{}", name)))
+            }
+            NoProxyRule::Ip { ip, port } => port_matches(port) && host_ip == Some(*ip),
+            NoProxyRule::Cidr { network, prefix } => host_ip
+                .map(|ip| in_network(ip, *network, *prefix))
+                .unwrap_or(false),
+        })
+    }
+}
+
+fn parse_no_proxy_rule(entry: &str) -> Option<NoProxyRule> {
+    if entry.is_empty() {
+        return None;
+    }
+    if entry == "*" {
+        return Some(NoProxyRule::All);
+    }
+    if let Some((network, prefix)) = entry.split_once('/') {
+        let network: IpAddr = network.trim_start_matches('[').trim_end_matches(']').parse().ok()?;
+        let prefix: u8 = prefix.parse().ok()?;
+        let max = if network.is_ipv4() { 32 } else { 128 };
+        return (prefix <= max).then_some(NoProxyRule::Cidr { network, prefix });
+    }
+
+    // 拆分端口：[::1]:8080、host:8080；不带方括号的 IPv6 地址没有端口
+    let (host, port) = if let Some(rest) = entry.strip_prefix('[') {
+        let (host, rest) = rest.split_once(']')?;
+        (host, rest.strip_prefix(':').and_then(|port| port.parse().ok()))
+    } else if entry.matches(':').count() == 1 {
+        let (host, port) = entry.split_once(':')?;
+        (host, Some(port.parse().ok()?))
+    } else {
+        (entry, None)
+    };
+
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        return Some(NoProxyRule::Ip { ip, port });
+    }
+    let name = host
+        .trim_start_matches("*.")
+        .trim_start_matches('.')
+        .trim_end_matches('.')
+        .to_lowercase();
+    (!name.is_empty()).then_some(NoProxyRule::Domain { name, port })
+}
+
+fn in_network(ip: IpAddr, network: IpAddr, prefix: u8) -> bool {
+    match (ip, network) {
+        (IpAddr::V4(ip), IpAddr::V4(network)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            u32::from(ip) & mask == u32::from(network) & mask
+        }
+        (IpAddr::V6(ip), IpAddr::V6(network)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            u128::from(ip) & mask == u128::from(network) & mask
+        }
+        _ => false,
+    }
+}
+
+// 代理地址没有写协议时按 HTTP 代理处理
+fn normalize_proxy(proxy: &str) -> String {
+    if proxy.contains("://") {
+        proxy.to_string()
+    } else {
+        format!("http://{}", proxy)
+    }
+}
+
+// 环境变量中的代理设置
+#[derive(Debug, Clone, Default)]
+pub struct EnvProxy {
+    http: Option<String>,
+    https: Option<String>,
+    all: Option<String>,
+    pub no_proxy: NoProxy,
+}
+
+impl EnvProxy {
+    pub fn from_env() -> Self {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    // 小写变量优先，其次是大写变量
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let read = |name: &str| {
+            lookup(name)
+                .or_else(|| lookup(&name.to_uppercase()))
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        Self {
+            http: read("http_proxy"),
+            https: read("https_proxy"),
+            all: read("all_proxy"),
+            no_proxy: NoProxy::parse(&read("no_proxy").unwrap_or_default()),
+        }
+    }
+
+    // 按地址的协议选择代理
+    pub fn proxy_for(&self, url: &reqwest::Url) -> Option<String> {
+        let proxy = match url.scheme() {
+            "https" => self.https.as_ref().or(self.all.as_ref()),
+            "http" => self.http.as_ref().or(self.all.as_ref()),
+            _ => self.all.as_ref(),
+        };
+        proxy.map(|proxy| normalize_proxy(proxy))
+    }
+}
+
+// 解析 Windows 的 ProxyServer，例如 "host:8080" 或 "http=host:8080;https=host:8443;socks=host:1080"
+pub fn parse_proxy_server(value: &str, scheme: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+    if !value.contains('=') {
+        return Some(normalize_proxy(value));
+    }
+
+    let entries: Vec<(String, &str)> = value
+        .split(';')
+        .filter_map(|entry| entry.split_once('='))
+        .map(|(protocol, address)| (protocol.trim().to_lowercase(), address.trim()))
+        .filter(|(_, address)| !address.is_empty())
+        .collect();
+    let find = |protocol: &str| {
+        entries
+            .iter()
+            .find(|(name, _)| name == protocol)
+            .map(|(_, address)| *address)
+    };
+
+    find(scheme)
+        .map(normalize_proxy)
+        .or_else(|| find("socks").map(|address| format!("socks5://{}", address)))
+}
+
+// Windows 的 ProxyOverride，分号分隔，支持 * 通配符，<local> 表示不含点的主机名
+pub fn bypass_matches(list: &str, host: &str) -> bool {
+    let host = host.to_lowercase();
+    list.split(';')
+        .map(|entry| entry.trim().to_lowercase())
+        .filter(|entry| !entry.is_empty())
+        .any(|entry| {
+            if entry == "<local>" {
+                return !host.contains('.');
+            }
+            let pattern = entry
+                .split_once("://")
+                .map(|(_, rest)| rest.to_string())
+                .unwrap_or(entry);
+            wildcard_match(&pattern, &host)
+        })
+}
+
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // 最近一个 * 的位置，以及它当时对应的文本位置
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+// 解析访问 url 时应使用的系统代理，None 表示直连
+pub async fn resolve(url: &str) -> Option<String> {
+    let url = reqwest::Url::parse(url).ok()?;
+    let host = url.host_str()?.to_string();
+    let port = url.port_or_known_default().unwrap_or(0);
+
+    let env = EnvProxy::from_env();
+    if env.no_proxy.matches(&host, port) {
+        println!("{} 在 NO_PROXY 中，不使用代理", host);
+        return None;
+    }
+    if let Some(proxy) = env.proxy_for(&url) {
+        return Some(proxy);
+    }
+
+    // 读取系统设置需要执行外部命令，放到阻塞线程中避免占用异步运行时
+    let pac_url = tokio::task::spawn_blocking(pac_url).await.ok().flatten();
+    if let Some(pac_url) = pac_url {
+        match resolve_with_pac(&pac_url, &url, &host).await {
+            Ok(proxy) => return proxy,
+            Err(e) => println!("PAC 脚本 {} 不可用: {}", pac_url, e),
+        }
+    }
+
+    tokio::task::spawn_blocking(move || platform_proxy(&url, &host))
+        .await
+        .ok()
+        .flatten()
+}
+
+// file:// 地址转换为本地路径，处理百分号编码和 Windows 盘符；其他地址返回 None
+fn pac_file_path(pac_url: &str) -> Option<Result<PathBuf, String>> {
+    let url = reqwest::Url::parse(pac_url).ok()?;
+    (url.scheme() == "file").then(|| {
+        url.to_file_path()
+            .map_err(|_| format!("PAC 脚本地址无效: {}", pac_url))
+    })
+}
+
+async fn resolve_with_pac(
+    pac_url: &str,
+    url: &reqwest::Url,
+    host: &str,
+) -> Result<Option<String>, String> {
+    // 获取 PAC 脚本本身不走代理
+    let client = reqwest::Client::builder()
+        .no_proxy()
+        .timeout(PAC_FETCH_TIMEOUT)
+        .build()
+        .map_err(|e| format!("创建HTTP客户端失败: {}", e))?;
+    let script = if let Some(path) = pac_file_path(pac_url) {
+        tokio::fs::read_to_string(path?)
+            .await
+            .map_err(|e| format!("读取 PAC 脚本失败: {}", e))?
+    } else {
+        client
+            .get(pac_url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| format!("下载 PAC 脚本失败: {}", e))?
+            .text()
+            .await
+            .map_err(|e| format!("读取 PAC 脚本失败: {}", e))?
+    };
+
+    // 与浏览器一致，HTTPS 地址只传入协议和主机部分
+    let script_url = if url.scheme() == "https" {
+        format!("{}://{}/", url.scheme(), host)
+    } else {
+        url.to_string()
+    };
+    let host = host.to_string();
+    let result = tokio::task::spawn_blocking(move || pac::find_proxy(&script, &script_url, &host))
+        .await
+        .map_err(|e| format!("执行 PAC 脚本失败: {}", e))??;
+    println!("PAC 结果: {}", result);
+    Ok(pac::parse_result(&result))
+}
+
+// 系统设置的 PAC 脚本地址
+#[cfg(target_os = "windows")]
+fn pac_url() -> Option<String> {
+    read_internet_setting("AutoConfigURL")
+}
+
+// GNOME 的自动代理设置
+#[cfg(target_os = "linux")]
+fn pac_url() -> Option<String> {
+    let gsettings = |key: &str| {
+        std::process::Command::new("gsettings")
+            .args(["get", "org.gnome.system.proxy", key])
+            .output()
+            .ok()
+            .map(|output| {
+                String::from_utf8_lossy(&output.stdout)
+                    .trim()
+                    .trim_matches('\'')
+                    .to_string()
+            })
+    };
+    if gsettings("mode")? != "auto" {
+        return None;
+    }
+    gsettings("autoconfig-url").filter(|url| !url.is_empty())
+}
+
+// macOS 的自动代理配置，例如 "ProxyAutoConfigURLString : http://example.com/proxy.pac"
+#[cfg(target_os = "macos")]
+fn pac_url() -> Option<String> {
+    let output = std::process::Command::new("scutil")
+        .arg("--proxy")
+        .output()
+        .ok()?;
+    let output = String::from_utf8_lossy(&output.stdout);
+    let value = |key: &str| {
+        output.lines().find_map(|line| {
+            let (name, value) = line.split_once(" : ")?;
+            (name.trim() == key).then(|| value.trim().to_string())
+        })
+    };
+    if value("ProxyAutoConfigEnable").as_deref() != Some("1") {
+        return None;
+    }
+    value("ProxyAutoConfigURLString")
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+fn pac_url() -> Option<String> {
+    None
+}
+
+// 系统设置的代理服务器和绕过列表，格式与 Windows 的 ProxyServer/ProxyOverride 相同
+fn platform_proxy(url: &reqwest::Url, host: &str) -> Option<String> {
+    let (server, bypass) = platform_proxy_settings()?;
+    if bypass_matches(&bypass, host) {
+        println!("{} 在代理绕过列表中，不使用代理", host);
+        return None;
+    }
+    parse_proxy_server(&server, url.scheme())
+}
+
+// Windows：先看 Internet 设置中的 ProxyServer，再看 WinHTTP 代理
+#[cfg(target_os = "windows")]
+fn platform_proxy_settings() -> Option<(String, String)> {
+    let enabled = read_internet_setting("ProxyEnable")
+        .map(|value| value.trim_start_matches("0x") != "0")
+        .unwrap_or(false);
+    if enabled {
+        if let Some(server) = read_internet_setting("ProxyServer") {
+            let bypass = read_internet_setting("ProxyOverride").unwrap_or_default();
+            return Some((server, bypass));
+        }
+    }
+    read_winhttp_proxy()
+}
+
+#[cfg(not(target_os = "windows"))]
+fn platform_proxy_settings() -> Option<(String, String)> {
+    None
+}
+
+// 读取 HKCU 下 Internet Settings 中的值
+#[cfg(target_os = "windows")]
+fn read_internet_setting(name: &str) -> Option<String> {
+    use std::os::windows::process::CommandExt;
+
+    let output = std::process::Command::new("reg")
+        .args([
+            "query",
+            "HKCU\\Software\\Microsoft\\Windows\\CurrentVersion\\Internet Settings",
+            "/v",
+            name,
+        ])
+        .creation_flags(0x08000000) // CREATE_NO_WINDOW
+        .output()
+        .ok()?;
+
+    // 例如: "    ProxyServer    REG_SZ    127.0.0.1:7890"
+    String::from_utf8_lossy(&output.stdout).lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        if parts.next()? != name {
+            return None;
+        }
+        parts.next()?;
+        let value = parts.collect::<Vec<_>>().join(" ");
+        (!value.is_empty()).then_some(value)
+    })
+}
+
+// netsh winhttp show proxy 的代理服务器和绕过列表
+#[cfg(target_os = "windows")]
+fn read_winhttp_proxy() -> Option<(String, String)> {
+    use std::os::windows::process::CommandExt;
+
+    let output = std::process::Command::new("netsh")
+        .args(["winhttp", "show", "proxy"])
+        .creation_flags(0x08000000) // CREATE_NO_WINDOW
+        .output()
+        .ok()?;
+    let output = String::from_utf8_lossy(&output.stdout);
+
+    // 例如: "    Proxy Server(s) :  127.0.0.1:7890"
+    let value = |labels: &[&str]| {
+        output.lines().find_map(|line| {
+            let (label, value) = line.split_once(':')?;
+            labels
+                .iter()
+                .any(|name| label.contains(name))
+                .then(|| value.trim().to_string())
+        })
+    };
+    let server = value(&["Proxy Server", "代理服务器"])
+        .filter(|server| !server.is_empty() && server != "(无)" && server != "(none)")?;
+    let bypass = value(&["Bypass List", "绕过列表"])
+        .filter(|bypass| bypass != "(无)" && bypass != "(none)")
+        .unwrap_or_default();
+    Some((server, bypass))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_proxy_matching() {
+        let no_proxy = NoProxy::parse("localhost, .example.com,*.corp.net internal:8080 10.0.0.0/8 [::1] fd00::/8");
+        assert!(no_proxy.matches("localhost", 80));
+        assert!(no_proxy.matches("example.com", 443));
+        assert!(no_proxy.matches("api.example.com", 443));
+        assert!(!no_proxy.matches("notexample.com", 443));
+        assert!(no_proxy.matches("git.corp.net", 22));
+        assert!(no_proxy.matches("internal", 8080));
+        assert!(!no_proxy.matches("internal", 80));
+        assert!(no_proxy.matches("10.20.30.40", 80));
+        assert!(!no_proxy.matches("11.0.0.1", 80));
+        assert!(no_proxy.matches("[::1]", 80));
+        assert!(no_proxy.matches("fd12::1", 80));
+        assert!(!no_proxy.matches("github.com", 443));
+
+        assert!(NoProxy::parse("*").matches("anything", 1));
+        assert!(!NoProxy::parse("").matches("anything", 1));
+    }
+
+    #[test]
+    fn env_proxy_prefers_lowercase_and_scheme() {
+        let env = EnvProxy::from_lookup(|name| match name {
+            "https_proxy" => Some("proxy.local:8443".to_string()),
+            "HTTPS_PROXY" => Some("http://ignored:1".to_string()),
+            "ALL_PROXY" => Some("socks5://all.local:1080".to_string()),
+            "NO_PROXY" => Some("github.com".to_string()),
+            _ => None,
+        });
+        let https = reqwest::Url::parse("https://example.com/").unwrap();
+        let http = reqwest::Url::parse("http://example.com/").unwrap();
+        assert_eq!(env.proxy_for(&https).as_deref(), Some("http://proxy.local:8443"));
+        assert_eq!(env.proxy_for(&http).as_deref(), Some("socks5://all.local:1080"));
+        assert!(env.no_proxy.matches("api.github.com", 443));
+    }
+
+    #[test]
+    fn windows_proxy_server_parsing() {
+        assert_eq!(
+            parse_proxy_server("127.0.0.1:7890", "https").as_deref(),
+            Some("http://127.0.0.1:7890")
+        );
+        let per_protocol = "http=proxy:8080; https=secure:8443;socks=socks:1080";
+        assert_eq!(
+            parse_proxy_server(per_protocol, "http").as_deref(),
+            Some("http://proxy:8080")
+        );
+        assert_eq!(
+            parse_proxy_server(per_protocol, "https").as_deref(),
+            Some("http://secure:8443")
+        );
+        // 没有对应协议时使用 SOCKS
+        assert_eq!(
+            parse_proxy_server("http=proxy:8080;socks=socks:1080", "https").as_deref(),
+            Some("socks5://socks:1080")
+        );
+        assert_eq!(parse_proxy_server("ftp=ftp:21", "https"), None);
+        assert_eq!(parse_proxy_server("  ", "https"), None);
+    }
+
+    #[test]
+    fn windows_proxy_override_matching() {
+        let bypass = "<local>;*.example.com;10.*;https://secure.test";
+        assert!(bypass_matches(bypass, "intranet"));
+        assert!(bypass_matches(bypass, "api.example.com"));
+        assert!(!bypass_matches(bypass, "example.com"));
+        assert!(bypass_matches(bypass, "10.0.0.1"));
+        assert!(bypass_matches(bypass, "SECURE.test"));
+        assert!(!bypass_matches(bypass, "github.com"));
+    }
+
+    #[test]
+    fn pac_file_urls_are_decoded() {
+        assert!(pac_file_path("http://example.com/proxy.pac").is_none());
+        #[cfg(unix)]
+        assert_eq!(
+            pac_file_path("file:///etc/proxy%20config/proxy.pac").unwrap().unwrap(),
+            PathBuf::from("/etc/proxy config/proxy.pac")
+        );
+        #[cfg(windows)]
+        assert_eq!(
+            pac_file_path("file:///C:/proxy%20config/proxy.pac").unwrap().unwrap(),
+            PathBuf::from("C:\\proxy config\\proxy.pac")
+        );
+    }
+}
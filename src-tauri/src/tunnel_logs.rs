@@ -0,0 +1,476 @@
+// 隧道日志持久化：每个隧道一个目录，按大小和日期滚动，启动时清理过期文件
+use crate::runtime_state::safe_file_name;
+use chrono::{DateTime, Local, NaiveDate, SecondsFormat};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+// 当前写入的日志文件名，滚动后的文件以时间戳命名
+const ACTIVE_LOG_NAME: &str = "current.log";
+
+// 未指定条数时返回的最大日志行数
+const DEFAULT_HISTORY_LIMIT: usize = 1000;
+
+// 单个日志文件大小的取值范围，过小会导致每写一行就滚动一次
+const MIN_FILE_SIZE_BYTES: u64 = 64 * 1024;
+const MAX_FILE_SIZE_BYTES: u64 = 1024 * 1024 * 1024;
+// 保留天数上限，0 表示不按天数清理
+const MAX_RETENTION_DAYS: u64 = 3650;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct LogSettings {
+    // 每个隧道保留的已滚动日志文件数
+    #[serde(rename = "maxLogFiles")]
+    pub max_log_files: usize,
+    #[serde(rename = "logRetentionDays")]
+    pub log_retention_days: u64,
+    // 单个日志文件达到该大小后滚动
+    #[serde(rename = "maxFileSizeBytes")]
+    pub max_file_size_bytes: u64,
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        Self {
+            max_log_files: 10,
+            log_retention_days: 30,
+            max_file_size_bytes: 5 * 1024 * 1024,
+        }
+    }
+}
+
+impl LogSettings {
+    // 把设置限制在有效范围内
+    fn clamped(self) -> Self {
+        Self {
+            max_file_size_bytes: self
+                .max_file_size_bytes
+                .clamp(MIN_FILE_SIZE_BYTES, MAX_FILE_SIZE_BYTES),
+            log_retention_days: self.log_retention_days.min(MAX_RETENTION_DAYS),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct LogHistoryEntry {
+    pub timestamp: String,
+    pub message: String,
+}
+
+struct ActiveLog {
+    file: File,
+    size: u64,
+    // 文件创建日期，跨天后滚动
+    opened_on: NaiveDate,
+}
+
+#[derive(Clone)]
+pub struct TunnelLogStore {
+    log_dir: PathBuf,
+    settings_file: PathBuf,
+    settings: Arc<Mutex<LogSettings>>,
+    active: Arc<Mutex<HashMap<String, ActiveLog>>>,
+}
+
+impl TunnelLogStore {
+    pub fn new(log_dir: PathBuf, settings_file: PathBuf) -> Self {
+        let settings = fs::read_to_string(&settings_file)
+            .ok()
+            .and_then(|content| serde_json::from_str::<LogSettings>(&content).ok())
+            .unwrap_or_default()
+            .clamped();
+
+        Self {
+            log_dir,
+            settings_file,
+            settings: Arc::new(Mutex::new(settings)),
+            active: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn settings(&self) -> LogSettings {
+        self.settings
+            .lock()
+            .map(|settings| settings.clone())
+            .unwrap_or_default()
+    }
+
+    // 保存日志设置（超出范围的值会被限制）并立即按新设置清理
+    pub fn update_settings(&self, settings: LogSettings) -> Result<(), String> {
+        let settings = settings.clamped();
+        let content = serde_json::to_string_pretty(&settings)
+            .map_err(|e| format!("序列化日志设置失败: {}", e))?;
+        fs::write(&self.settings_file, content).map_err(|e| format!("保存日志设置失败: {}", e))?;
+
+        if let Ok(mut current) = self.settings.lock() {
+            *current = settings;
+        }
+        self.enforce_retention();
+        Ok(())
+    }
+
+    fn tunnel_dir(&self, tunnel_id: &str) -> PathBuf {
+        self.log_dir.join(safe_file_name(tunnel_id))
+    }
+
+    // 追加一行日志，必要时先滚动文件
+    pub fn append(&self, tunnel_id: &str, message: &str) {
+        if let Err(e) = self.try_append(tunnel_id, message) {
+            println!("写入隧道 {} 日志文件失败: {}", tunnel_id, e);
+        }
+    }
+
+    fn try_append(&self, tunnel_id: &str, message: &str) -> std::io::Result<()> {
+        let settings = self.settings();
+        let mut active = self
+            .active
+            .lock()
+            .map_err(|_| std::io::Error::other("日志写入状态已损坏"))?;
+
+        let now = Local::now();
+        let line = format!(
+            "{} {}\n",
+            now.to_rfc3339_opts(SecondsFormat::Millis, false),
+            message
+        );
+
+        let needs_rotation = active.get(tunnel_id).is_some_and(|log| {
+            log.opened_on != now.date_naive()
+                || log.size + line.len() as u64 > settings.max_file_size_bytes
+        });
+        if needs_rotation {
+            active.remove(tunnel_id);
+            self.rotate(tunnel_id, &settings)?;
+        }
+
+        let log = match active.entry(tunnel_id.to_string()) {
+            std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+            std::collections::hash_map::Entry::Vacant(entry) => {
+                let log = self.open_active(tunnel_id)?;
+                // 上次运行留下的文件也可能已跨天或超过大小
+                if log.opened_on != now.date_naive() || log.size >= settings.max_file_size_bytes {
+                    drop(log);
+                    self.rotate(tunnel_id, &settings)?;
+                    entry.insert(self.open_active(tunnel_id)?)
+                } else {
+                    entry.insert(log)
+                }
+            }
+        };
+
+        log.file.write_all(line.as_bytes())?;
+        log.size += line.len() as u64;
+        Ok(())
+    }
+
+    fn open_active(&self, tunnel_id: &str) -> std::io::Result<ActiveLog> {
+        let dir = self.tunnel_dir(tunnel_id);
+        fs::create_dir_all(&dir)?;
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(ACTIVE_LOG_NAME))?;
+        let metadata = file.metadata()?;
+        let opened_on = metadata
+            .modified()
+            .map(|modified| DateTime::<Local>::from(modified).date_naive())
+            .unwrap_or_else(|_| Local::now().date_naive());
+
+        Ok(ActiveLog {
+            file,
+            size: metadata.len(),
+            opened_on: if metadata.len() == 0 {
+                Local::now().date_naive()
+            } else {
+                opened_on
+            },
+        })
+    }
+
+    // 把当前文件改名为带时间戳的归档文件
+    fn rotate(&self, tunnel_id: &str, settings: &LogSettings) -> std::io::Result<()> {
+        let dir = self.tunnel_dir(tunnel_id);
+        let current = dir.join(ACTIVE_LOG_NAME);
+        if current.exists() {
+            // 同一毫秒内多次滚动时加上序号，避免覆盖之前的归档
+            let stamp = Local::now().format("%Y%m%d-%H%M%S%.3f").to_string();
+            let mut archived = dir.join(format!("{}.log", stamp));
+            let mut sequence = 1;
+            while archived.exists() {
+                archived = dir.join(format!("{}_{:03}.log", stamp, sequence));
+                sequence += 1;
+            }
+            fs::rename(&current, archived)?;
+        }
+        prune_dir(&dir, settings);
+        Ok(())
+    }
+
+    // 按保留天数和文件数清理所有隧道的归档日志
+    pub fn enforce_retention(&self) {
+        let settings = self.settings();
+        let entries = match fs::read_dir(&self.log_dir) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+        for entry in entries.flatten() {
+            if entry.path().is_dir() {
+                prune_dir(&entry.path(), &settings);
+            }
+        }
+    }
+
+    // 读取隧道的历史日志（包括已停止的隧道），按时间顺序返回最近的 limit 条
+    pub fn history(
+        &self,
+        tunnel_id: &str,
+        since: Option<&str>,
+        limit: Option<usize>,
+    ) -> Result<Vec<LogHistoryEntry>, String> {
+        let since = match since {
+            Some(since) => Some(
+                DateTime::parse_from_rfc3339(since)
+                    .map_err(|e| format!("无效的时间格式 {}: {}", since, e))?,
+            ),
+            None => None,
+        };
+        let limit = limit.unwrap_or(DEFAULT_HISTORY_LIMIT);
+
+        let dir = self.tunnel_dir(tunnel_id);
+        let mut files = archived_logs(&dir);
+        files.push(dir.join(ACTIVE_LOG_NAME));
+
+        let mut entries = Vec::new();
+        for path in files {
+            let content = match fs::read(&path) {
+                Ok(content) => String::from_utf8_lossy(&content).to_string(),
+                Err(_) => continue,
+            };
+            for line in content.lines() {
+                let (timestamp, message) = match line.split_once(' ') {
+                    Some(parts) => parts,
+                    None => continue,
+                };
+                if let Some(since) = since {
+                    match DateTime::parse_from_rfc3339(timestamp) {
+                        Ok(time) if time >= since => {}
+                        _ => continue,
+                    }
+                }
+                entries.push(LogHistoryEntry {
+                    timestamp: timestamp.to_string(),
+                    message: message.to_string(),
+                });
+            }
+        }
+
+        if entries.len() > limit {
+            entries.drain(0..entries.len() - limit);
+        }
+        Ok(entries)
+    }
+}
+
+// 已滚动的归档文件，文件名即时间戳，按名称排序即按时间排序
+fn archived_logs(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.extension().is_some_and(|ext| ext == "log")
+                    && path.file_name().is_some_and(|name| name != ACTIVE_LOG_NAME)
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    files.sort();
+    files
+}
+
+fn prune_dir(dir: &Path, settings: &LogSettings) {
+    let max_age = Duration::from_secs(settings.log_retention_days * 24 * 60 * 60);
+    let mut files = archived_logs(dir);
+
+    // 超过保留天数的文件
+    files.retain(|path| {
+        let expired = settings.log_retention_days > 0
+            && fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                .is_some_and(|age| age > max_age);
+        if expired {
+            let _ = fs::remove_file(path);
+        }
+        !expired
+    });
+
+    // 超过数量上限时删除最早的文件
+    if settings.max_log_files > 0 && files.len() > settings.max_log_files {
+        for path in &files[..files.len() - settings.max_log_files] {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store(dir: &Path, settings: LogSettings) -> TunnelLogStore {
+        let store = TunnelLogStore::new(dir.join("logs"), dir.join("log_settings.json"));
+        // 测试中直接使用较小的文件大小，不经过取值范围限制
+        *store.settings.lock().unwrap() = settings;
+        store
+    }
+
+    fn archived_count(store: &TunnelLogStore, tunnel_id: &str) -> usize {
+        archived_logs(&store.tunnel_dir(tunnel_id)).len()
+    }
+
+    fn set_age(path: &Path, days: u64) {
+        let modified = SystemTime::now() - Duration::from_secs(days * 24 * 60 * 60);
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    #[test]
+    fn update_settings_clamps_values() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(dir.path(), LogSettings::default());
+        store
+            .update_settings(LogSettings {
+                max_log_files: 3,
+                log_retention_days: u64::MAX,
+                max_file_size_bytes: 0,
+            })
+            .unwrap();
+
+        let settings = store.settings();
+        assert_eq!(settings.max_log_files, 3);
+        assert_eq!(settings.log_retention_days, MAX_RETENTION_DAYS);
+        assert_eq!(settings.max_file_size_bytes, MIN_FILE_SIZE_BYTES);
+
+        // 重新加载时同样限制
+        let reloaded = TunnelLogStore::new(dir.path().join("logs"), dir.path().join("log_settings.json"));
+        assert_eq!(reloaded.settings().max_file_size_bytes, MIN_FILE_SIZE_BYTES);
+    }
+
+    #[test]
+    fn rotates_by_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(
+            dir.path(),
+            LogSettings {
+                max_log_files: 0,
+                log_retention_days: 0,
+                max_file_size_bytes: 100,
+            },
+        );
+        for i in 0..6 {
+            store.append("a", &format!("第 {} 行，内容足够长以便超过文件大小", i));
+        }
+        // 每个文件只能放下一行
+        assert_eq!(archived_count(&store, "a"), 5);
+        let history = store.history("a", None, None).unwrap();
+        assert_eq!(history.len(), 6);
+        assert!(history[0].message.starts_with("第 0 行"));
+        assert!(history[5].message.starts_with("第 5 行"));
+    }
+
+    #[test]
+    fn rotates_previous_day_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(dir.path(), LogSettings::default());
+        store.append("a", "昨天的日志");
+        store.active.lock().unwrap().clear();
+        set_age(&store.tunnel_dir("a").join(ACTIVE_LOG_NAME), 1);
+
+        // 重新打开时发现文件来自前一天
+        store.append("a", "今天的日志");
+        assert_eq!(archived_count(&store, "a"), 1);
+
+        // 已打开的文件跨天后同样滚动
+        let yesterday = Local::now().date_naive().pred_opt().unwrap();
+        store.active.lock().unwrap().get_mut("a").unwrap().opened_on = yesterday;
+        store.append("a", "跨天后的日志");
+        assert_eq!(archived_count(&store, "a"), 2);
+        assert_eq!(store.history("a", None, None).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn prune_dir_applies_age_and_count() {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, age_days) in [
+            ("20240101-000000.000.log", 40),
+            ("20240201-000000.000.log", 3),
+            ("20240202-000000.000.log", 2),
+            ("20240203-000000.000.log", 1),
+        ] {
+            let path = dir.path().join(name);
+            fs::write(&path, "line\n").unwrap();
+            set_age(&path, age_days);
+        }
+        fs::write(dir.path().join(ACTIVE_LOG_NAME), "line\n").unwrap();
+
+        prune_dir(
+            dir.path(),
+            &LogSettings {
+                max_log_files: 2,
+                log_retention_days: 30,
+                max_file_size_bytes: MIN_FILE_SIZE_BYTES,
+            },
+        );
+        let names: Vec<String> = archived_logs(dir.path())
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, ["20240202-000000.000.log", "20240203-000000.000.log"]);
+        // 当前文件不参与清理
+        assert!(dir.path().join(ACTIVE_LOG_NAME).exists());
+    }
+
+    #[test]
+    fn history_filters_by_since_and_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(dir.path(), LogSettings::default());
+        let tunnel_dir = store.tunnel_dir("a");
+        fs::create_dir_all(&tunnel_dir).unwrap();
+        fs::write(
+            tunnel_dir.join("20250101-000000.000.log"),
+            "2025-01-01T08:00:00.000+08:00 一\n2025-01-01T09:00:00.000+08:00 二\n",
+        )
+        .unwrap();
+        fs::write(
+            tunnel_dir.join(ACTIVE_LOG_NAME),
+            "2025-01-01T10:00:00.000+08:00 三\n无效行\n2025-01-01T11:00:00.000+08:00 四\n",
+        )
+        .unwrap();
+
+        let messages = |since: Option<&str>, limit: Option<usize>| -> Vec<String> {
+            store
+                .history("a", since, limit)
+                .unwrap()
+                .into_iter()
+                .map(|entry| entry.message)
+                .collect()
+        };
+        assert_eq!(messages(None, Some(2)), ["三", "四"]);
+        assert_eq!(messages(Some("2025-01-01T01:00:00Z"), None), ["二", "三", "四"]);
+        assert_eq!(messages(Some("2025-01-01T01:00:00Z"), Some(1)), ["四"]);
+        assert!(store.history("a", Some("yesterday"), None).is_err());
+        assert!(store.history("missing", None, None).unwrap().is_empty());
+    }
+}
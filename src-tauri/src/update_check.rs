@@ -0,0 +1,294 @@
+// 后台检查 NodePass 新版本：按设置的间隔轮询发布源，使用 ETag 避免重复下载，
+// 最近一次的发布信息缓存在磁盘上
+use crate::GitHubRelease;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UpdateCheckSettings {
+    // 对应前端的 checkUpdates 设置
+    #[serde(rename = "checkUpdates", default = "default_true")]
+    pub check_updates: bool,
+    #[serde(rename = "intervalHours", default = "default_interval_hours")]
+    pub interval_hours: u64,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_interval_hours() -> u64 {
+    6
+}
+
+impl Default for UpdateCheckSettings {
+    fn default() -> Self {
+        Self {
+            check_updates: true,
+            interval_hours: default_interval_hours(),
+        }
+    }
+}
+
+// 某个请求地址上次返回的 ETag 和发布信息
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct CachedResponse {
+    etag: String,
+    release: GitHubRelease,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct ReleaseCache {
+    #[serde(rename = "checkedAt")]
+    checked_at: String,
+    // 最近一次获取到的发布信息，不论来自哪个发布源
+    release: GitHubRelease,
+    // 按请求地址保存 ETag，切换发布源后各自的缓存仍然有效
+    #[serde(default)]
+    responses: HashMap<String, CachedResponse>,
+}
+
+#[derive(Clone)]
+pub struct UpdateChecker {
+    settings_file: PathBuf,
+    cache_file: PathBuf,
+    settings: Arc<Mutex<UpdateCheckSettings>>,
+    // 已经通知过的版本，避免每次检查都重复提醒
+    notified: Arc<Mutex<Option<String>>>,
+}
+
+impl UpdateChecker {
+    pub fn load(settings_file: PathBuf, cache_file: PathBuf) -> Self {
+        let settings = fs::read_to_string(&settings_file)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            settings_file,
+            cache_file,
+            settings: Arc::new(Mutex::new(settings)),
+            notified: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn settings(&self) -> UpdateCheckSettings {
+        self.settings
+            .lock()
+            .map(|settings| settings.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn update(&self, mut settings: UpdateCheckSettings) -> Result<(), String> {
+        settings.interval_hours = settings.interval_hours.max(1);
+        let content = serde_json::to_string_pretty(&settings)
+            .map_err(|e| format!("序列化更新检查设置失败: {}", e))?;
+        fs::write(&self.settings_file, content)
+            .map_err(|e| format!("保存更新检查设置失败: {}", e))?;
+        if let Ok(mut current) = self.settings.lock() {
+            *current = settings;
+        }
+        Ok(())
+    }
+
+    fn load_cache(&self) -> Option<ReleaseCache> {
+        let content = fs::read_to_string(&self.cache_file).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn save_cache(&self, cache: &ReleaseCache) {
+        if let Ok(content) = serde_json::to_string_pretty(cache) {
+            let _ = fs::write(&self.cache_file, content);
+        }
+    }
+
+    pub fn cached_release(&self) -> Option<GitHubRelease> {
+        self.load_cache().map(|cache| cache.release)
+    }
+
+    // 更新最近的发布信息，url 和 etag 存在时同时记录该地址的 ETag
+    fn store(&self, release: &GitHubRelease, response: Option<(&str, String)>) {
+        let mut responses = self
+            .load_cache()
+            .map(|cache| cache.responses)
+            .unwrap_or_default();
+        if let Some((url, etag)) = response {
+            responses.insert(
+                url.to_string(),
+                CachedResponse {
+                    etag,
+                    release: release.clone(),
+                },
+            );
+        }
+        self.save_cache(&ReleaseCache {
+            checked_at: chrono::Local::now().to_rfc3339(),
+            release: release.clone(),
+            responses,
+        });
+    }
+
+    // 记录从其它途径获取的发布信息
+    pub fn remember(&self, release: &GitHubRelease) {
+        self.store(release, None);
+    }
+
+    // 请求 GitHub 格式的发布 JSON；未变化时 GitHub 返回 304，不计入匿名请求的频率限制
+    pub async fn fetch_release_json(
+        &self,
+        client: &reqwest::Client,
+        url: &str,
+        timeout: std::time::Duration,
+    ) -> Result<GitHubRelease, String> {
+        let cached = self
+            .load_cache()
+            .and_then(|mut cache| cache.responses.remove(url));
+
+        let mut request = client
+            .get(url)
+            .header("User-Agent", "NodePass-GUI")
+            .timeout(timeout);
+        if let Some(cached) = &cached {
+            request = request.header(reqwest::header::IF_NONE_MATCH, cached.etag.as_str());
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("请求发布源失败: {}", e))?;
+
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            if let Some(cached) = cached {
+                self.store(&cached.release, Some((url, cached.etag.clone())));
+                return Ok(cached.release);
+            }
+        }
+        if !response.status().is_success() {
+            return Err(format!("发布源返回错误: {}", response.status()));
+        }
+
+        let etag = response
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
+        let release: GitHubRelease = response
+            .json()
+            .await
+            .map_err(|e| format!("解析发布信息失败: {}", e))?;
+
+        self.store(&release, etag.map(|etag| (url, etag)));
+        Ok(release)
+    }
+
+    // 记录已通知的版本，返回该版本是否是第一次通知
+    pub fn mark_notified(&self, tag: &str) -> bool {
+        match self.notified.lock() {
+            Ok(mut notified) if notified.as_deref() != Some(tag) => {
+                *notified = Some(tag.to_string());
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+pub fn parse_version(tag: &str) -> Option<semver::Version> {
+    let tag = tag.trim();
+    semver::Version::parse(tag.strip_prefix('v').unwrap_or(tag)).ok()
+}
+
+// 按语义化版本比较，latest 比 installed 新时返回 true；无法解析时按字符串是否不同判断
+pub fn is_newer(latest: &str, installed: &str) -> bool {
+    match (parse_version(latest), parse_version(installed)) {
+        (Some(latest), Some(installed)) => latest > installed,
+        _ => latest.trim_start_matches('v') != installed.trim_start_matches('v'),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    const RELEASE_JSON: &str = r#"{"tag_name":"v1.4.0","name":"v1.4.0","published_at":"2025-06-01T08:00:00Z","html_url":"https://github.com/yosebyte/nodepass/releases/tag/v1.4.0","assets":[]}"#;
+
+    fn checker(dir: &tempfile::TempDir) -> UpdateChecker {
+        UpdateChecker::load(
+            dir.path().join("update_check.json"),
+            dir.path().join("release_cache.json"),
+        )
+    }
+
+    // 本地发布源：带 If-None-Match: "v1" 的请求返回 304，其它请求返回发布信息；返回地址和收到 304 的次数
+    async fn serve_release() -> (String, Arc<AtomicUsize>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/releases/latest", listener.local_addr().unwrap());
+        let not_modified = Arc::new(AtomicUsize::new(0));
+        let counter = not_modified.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = Vec::new();
+                let mut buffer = [0u8; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    match stream.read(&mut buffer).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => request.extend_from_slice(&buffer[..n]),
+                    }
+                }
+                let request = String::from_utf8_lossy(&request).to_lowercase();
+                let response = if request.contains("if-none-match: \"v1\"") {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    "HTTP/1.1 304 Not Modified\r\nETag: \"v1\"\r\nConnection: close\r\n\r\n".to_string()
+                } else {
+                    format!(
+                        "HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        RELEASE_JSON.len(),
+                        RELEASE_JSON
+                    )
+                };
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        (url, not_modified)
+    }
+
+    #[test]
+    fn compares_versions() {
+        assert!(is_newer("v1.4.0", "1.3.9"));
+        assert!(is_newer("1.4.0", "v1.4.0-beta.2"));
+        assert!(is_newer("v1.4.0-beta.2", "v1.4.0-beta.1"));
+        assert!(!is_newer("v1.4.0-rc.1", "v1.4.0"));
+        assert!(!is_newer("v1.4.0", "1.4.0"));
+        assert!(!is_newer(" v1.3.0", "v1.4.0"));
+        // 无法解析时只要不同就视为新版本
+        assert!(is_newer("nightly-2", "nightly-1"));
+        assert!(!is_newer("vdev", "dev"));
+    }
+
+    #[tokio::test]
+    async fn reuses_cached_release_on_not_modified() {
+        let dir = tempfile::tempdir().unwrap();
+        let checker = checker(&dir);
+        let (url, not_modified) = serve_release().await;
+        let client = reqwest::Client::builder().no_proxy().build().unwrap();
+        let timeout = std::time::Duration::from_secs(5);
+
+        let first = checker.fetch_release_json(&client, &url, timeout).await.unwrap();
+        assert_eq!(first.tag_name, "v1.4.0");
+        assert_eq!(not_modified.load(Ordering::SeqCst), 0);
+
+        // 其它发布源的结果不影响这个地址的 ETag
+        let mut other = first.clone();
+        other.tag_name = "v1.5.0".to_string();
+        checker.remember(&other);
+
+        let second = checker.fetch_release_json(&client, &url, timeout).await.unwrap();
+        assert_eq!(not_modified.load(Ordering::SeqCst), 1);
+        assert_eq!(second.tag_name, "v1.4.0");
+        assert_eq!(checker.cached_release().unwrap().tag_name, "v1.4.0");
+    }
+}
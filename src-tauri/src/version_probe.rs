@@ -0,0 +1,158 @@
+// 探测 NodePass 可执行文件的版本：异步运行 --help 并设置超时，
+// 结果按文件路径和修改时间缓存，文件被替换后重新探测
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+// 正常的 nodepass --help 会立即返回
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct NodePassBuild {
+    pub version: Option<String>,
+    pub os: Option<String>,
+    pub arch: Option<String>,
+    // 版本行中其余的构建信息
+    pub build: Option<String>,
+}
+
+#[derive(Clone, Default)]
+pub struct VersionProbe {
+    cache: Arc<Mutex<HashMap<PathBuf, (SystemTime, NodePassBuild)>>>,
+}
+
+impl VersionProbe {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn probe(&self, path: &Path) -> Result<NodePassBuild, String> {
+        let modified = std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .map_err(|e| format!("读取文件信息失败: {}", e))?;
+
+        if let Some((cached_at, build)) = self
+            .cache
+            .lock()
+            .ok()
+            .and_then(|cache| cache.get(path).cloned())
+        {
+            if cached_at == modified {
+                return Ok(build);
+            }
+        }
+
+        let build = run_probe(path).await?;
+        if let Ok(mut cache) = self.cache.lock() {
+            cache.insert(path.to_path_buf(), (modified, build.clone()));
+        }
+        Ok(build)
+    }
+}
+
+async fn run_probe(path: &Path) -> Result<NodePassBuild, String> {
+    let mut cmd = tokio::process::Command::new(path);
+    cmd.arg("--help")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    // 在Windows上隐藏终端窗口
+    #[cfg(windows)]
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+    // 超时后 output() 被丢弃，kill_on_drop 会结束卡住的进程
+    let output = tokio::time::timeout(PROBE_TIMEOUT, cmd.output())
+        .await
+        .map_err(|_| format!("执行超时（{} 秒）", PROBE_TIMEOUT.as_secs()))?
+        .map_err(|e| format!("执行失败: {}", e))?;
+
+    // NodePass会输出版本信息到stderr，格式如: "Version: v1.2.4 windows/amd64"
+    let combined_output = format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stderr),
+        String::from_utf8_lossy(&output.stdout)
+    );
+    parse_help_output(&combined_output)
+}
+
+// 解析 --help 输出；既没有版本行也没有提到 nodepass 时认为不是 NodePass
+fn parse_help_output(output: &str) -> Result<NodePassBuild, String> {
+    let version_line = output
+        .lines()
+        .find_map(|line| line.split_once("Version:").map(|(_, rest)| rest));
+
+    let line = match version_line {
+        Some(line) => line,
+        None if output.to_lowercase().contains("nodepass") => {
+            return Ok(NodePassBuild::default());
+        }
+        None => return Err("该文件不是 NodePass 可执行文件".to_string()),
+    };
+
+    // 去掉输出边框等非版本字符
+    let mut tokens = line
+        .split_whitespace()
+        .map(|token| token.trim_matches(|c: char| !c.is_ascii() || c == '|'))
+        .filter(|token| !token.is_empty());
+
+    let mut build = NodePassBuild {
+        version: tokens.next().map(|token| token.to_string()),
+        ..NodePassBuild::default()
+    };
+    let mut rest = Vec::new();
+    for token in tokens {
+        match token.split_once('/') {
+            Some((os, arch)) if build.os.is_none() && !os.is_empty() && !arch.is_empty() => {
+                build.os = Some(os.to_string());
+                build.arch = Some(arch.to_string());
+            }
+            _ => rest.push(token),
+        }
+    }
+    if !rest.is_empty() {
+        build.build = Some(rest.join(" "));
+    }
+    Ok(build)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_current_banner() {
+        let build = parse_help_output(include_str!("../tests/fixtures/nodepass_help_current.txt")).unwrap();
+        assert_eq!(build.version.as_deref(), Some("v1.4.0"));
+        assert_eq!(build.os.as_deref(), Some("linux"));
+        assert_eq!(build.arch.as_deref(), Some("amd64"));
+        // 边框字符不算构建信息
+        assert_eq!(build.build.as_deref(), Some("go1.24.2"));
+    }
+
+    #[test]
+    fn parses_legacy_banner() {
+        let build = parse_help_output(include_str!("../tests/fixtures/nodepass_help_legacy.txt")).unwrap();
+        assert_eq!(build.version.as_deref(), Some("v1.2.4"));
+        assert_eq!(build.os.as_deref(), Some("windows"));
+        assert_eq!(build.arch.as_deref(), Some("amd64"));
+        assert_eq!(build.build, None);
+    }
+
+    #[test]
+    fn banner_without_version_line() {
+        let build = parse_help_output("Usage:\n    nodepass <core>://<tunnel_addr>/<target_addr>\n").unwrap();
+        assert_eq!(build.version, None);
+        assert_eq!(build.os, None);
+    }
+
+    #[test]
+    fn rejects_other_programs() {
+        assert!(parse_help_output(include_str!("../tests/fixtures/not_nodepass_help.txt")).is_err());
+        assert!(parse_help_output("").is_err());
+    }
+}
@@ -0,0 +1,339 @@
+// 多版本 NodePass 并存：每个版本安装在 versions/<tag>/ 下，active.json 记录当前使用的版本和上一个版本
+use crate::platform;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+struct ActivePointer {
+    active: Option<String>,
+    // 切换前的版本，用于回滚
+    previous: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct InstalledVersion {
+    pub tag: String,
+    pub path: String,
+    pub active: bool,
+    #[serde(rename = "installedAt")]
+    pub installed_at: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct VersionStore {
+    root: PathBuf,
+}
+
+// 版本标签作为目录名，只允许常见字符
+fn validate_tag(tag: &str) -> Result<(), String> {
+    // 以点开头的目录留给暂存目录使用
+    let valid = !tag.is_empty()
+        && !tag.starts_with('.')
+        && tag
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | '+'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("版本标签无效: {}", tag))
+    }
+}
+
+impl VersionStore {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    fn versions_dir(&self) -> PathBuf {
+        self.root.join("versions")
+    }
+
+    fn pointer_file(&self) -> PathBuf {
+        self.root.join("active.json")
+    }
+
+    // 离线安装的暂存目录，与版本目录在同一文件系统上
+    pub fn staging_dir(&self) -> PathBuf {
+        self.versions_dir().join(format!(
+            ".staging-{}",
+            chrono::Local::now().timestamp_millis()
+        ))
+    }
+
+    pub fn version_dir(&self, tag: &str) -> Result<PathBuf, String> {
+        validate_tag(tag)?;
+        Ok(self.versions_dir().join(tag))
+    }
+
+    pub fn executable_for(&self, tag: &str) -> Result<PathBuf, String> {
+        Ok(self.version_dir(tag)?.join(platform::executable_name()))
+    }
+
+    pub fn is_installed(&self, tag: &str) -> bool {
+        self.executable_for(tag)
+            .map(|path| path.is_file())
+            .unwrap_or(false)
+    }
+
+    fn load_pointer(&self) -> ActivePointer {
+        fs::read_to_string(self.pointer_file())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save_pointer(&self, pointer: &ActivePointer) -> Result<(), String> {
+        fs::create_dir_all(&self.root).map_err(|e| format!("创建版本目录失败: {}", e))?;
+        let content = serde_json::to_string_pretty(pointer)
+            .map_err(|e| format!("序列化版本信息失败: {}", e))?;
+
+        // 先写临时文件再改名，写入中断时不会留下损坏的版本信息
+        let pointer_file = self.pointer_file();
+        let temp_file = pointer_file.with_extension("json.tmp");
+        let mut file =
+            fs::File::create(&temp_file).map_err(|e| format!("创建临时版本文件失败: {}", e))?;
+        file.write_all(content.as_bytes())
+            .and_then(|_| file.sync_all())
+            .map_err(|e| format!("写入临时版本文件失败: {}", e))?;
+        drop(file);
+        fs::rename(&temp_file, &pointer_file).map_err(|e| {
+            let _ = fs::remove_file(&temp_file);
+            format!("保存版本信息失败: {}", e)
+        })
+    }
+
+    pub fn active(&self) -> Option<String> {
+        self.load_pointer()
+            .active
+            .filter(|tag| self.is_installed(tag))
+    }
+
+    // 当前版本的可执行文件
+    pub fn active_executable(&self) -> Option<PathBuf> {
+        self.active().and_then(|tag| self.executable_for(&tag).ok())
+    }
+
+    // 隧道指定了版本时使用该版本，否则使用当前版本；指定的版本未安装时报错
+    pub fn resolve(&self, pin: Option<&str>) -> Result<Option<PathBuf>, String> {
+        match pin.map(str::trim).filter(|pin| !pin.is_empty()) {
+            Some(tag) if self.is_installed(tag) => Ok(Some(self.executable_for(tag)?)),
+            Some(tag) => Err(format!("隧道指定的 NodePass 版本 {} 未安装", tag)),
+            None => Ok(self.active_executable()),
+        }
+    }
+
+    pub fn list(&self) -> Vec<InstalledVersion> {
+        let active = self.active();
+        let entries = match fs::read_dir(self.versions_dir()) {
+            Ok(entries) => entries,
+            Err(_) => return Vec::new(),
+        };
+
+        let mut versions: Vec<(std::time::SystemTime, InstalledVersion)> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let tag = entry.file_name().to_string_lossy().to_string();
+                let exe = self.executable_for(&tag).ok()?;
+                let modified = fs::metadata(&exe).ok()?.modified().ok()?;
+                Some((
+                    modified,
+                    InstalledVersion {
+                        active: active.as_deref() == Some(tag.as_str()),
+                        path: exe.to_string_lossy().to_string(),
+                        installed_at: Some(
+                            chrono::DateTime::<chrono::Local>::from(modified).to_rfc3339(),
+                        ),
+                        tag,
+                    },
+                ))
+            })
+            .collect();
+
+        // 最近安装的在前
+        versions.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+        versions.into_iter().map(|(_, version)| version).collect()
+    }
+
+    pub fn activate(&self, tag: &str) -> Result<(), String> {
+        if !self.is_installed(tag) {
+            return Err(format!("NodePass 版本 {} 未安装", tag));
+        }
+        let mut pointer = self.load_pointer();
+        if pointer.active.as_deref() != Some(tag) {
+            pointer.previous = pointer.active.take();
+            pointer.active = Some(tag.to_string());
+            self.save_pointer(&pointer)?;
+        }
+        println!("已切换到 NodePass 版本: {}", tag);
+        Ok(())
+    }
+
+    // 切回上一个版本
+    pub fn rollback(&self) -> Result<String, String> {
+        let previous = self
+            .load_pointer()
+            .previous
+            .filter(|tag| self.is_installed(tag))
+            .ok_or_else(|| "没有可回滚的版本".to_string())?;
+        self.activate(&previous)?;
+        Ok(previous)
+    }
+
+    // 删除版本；running 为运行中隧道的 (隧道ID, 可执行文件)，pinned 为固定了版本的 (隧道ID, 版本)
+    pub fn remove(
+        &self,
+        tag: &str,
+        running: &[(String, String)],
+        pinned: &[(String, String)],
+    ) -> Result<(), String> {
+        if self.active().as_deref() == Some(tag) {
+            return Err(format!("版本 {} 正在使用中，请先切换到其它版本", tag));
+        }
+        if let Some((tunnel_id, _)) = running
+            .iter()
+            .find(|(_, executable)| self.owns(tag, executable))
+        {
+            return Err(format!("隧道 {} 正在使用版本 {}", tunnel_id, tag));
+        }
+        if let Some((tunnel_id, _)) = pinned.iter().find(|(_, pin)| pin == tag) {
+            return Err(format!("隧道 {} 固定使用版本 {}", tunnel_id, tag));
+        }
+        let dir = self.version_dir(tag)?;
+        if !dir.exists() {
+            return Err(format!("NodePass 版本 {} 未安装", tag));
+        }
+        fs::remove_dir_all(&dir).map_err(|e| format!("删除版本 {} 失败: {}", tag, e))?;
+
+        let mut pointer = self.load_pointer();
+        if pointer.previous.as_deref() == Some(tag) {
+            pointer.previous = None;
+            self.save_pointer(&pointer)?;
+        }
+        Ok(())
+    }
+
+    // 某个可执行文件是否属于指定版本
+    pub fn owns(&self, tag: &str, executable: &str) -> bool {
+        self.version_dir(tag)
+            .map(|dir| Path::new(executable).starts_with(dir))
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn install(store: &VersionStore, tag: &str) -> PathBuf {
+        let exe = store.executable_for(tag).unwrap();
+        fs::create_dir_all(exe.parent().unwrap()).unwrap();
+        fs::write(&exe, tag).unwrap();
+        exe
+    }
+
+    fn store(dir: &tempfile::TempDir) -> VersionStore {
+        VersionStore::new(dir.path().to_path_buf())
+    }
+
+    #[test]
+    fn rollback_switches_to_previous_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(&dir);
+        install(&store, "v1.0.0");
+        install(&store, "v1.1.0");
+        store.activate("v1.0.0").unwrap();
+        store.activate("v1.1.0").unwrap();
+
+        assert_eq!(store.rollback().unwrap(), "v1.0.0");
+        assert_eq!(store.active().as_deref(), Some("v1.0.0"));
+        // 再次回滚回到之后的版本
+        assert_eq!(store.rollback().unwrap(), "v1.1.0");
+    }
+
+    #[test]
+    fn rollback_without_previous_version_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(&dir);
+        assert!(store.rollback().is_err());
+
+        install(&store, "v1.0.0");
+        store.activate("v1.0.0").unwrap();
+        assert!(store.rollback().is_err());
+
+        // 上一个版本已被删除
+        install(&store, "v1.1.0");
+        store.activate("v1.1.0").unwrap();
+        store.remove("v1.0.0", &[], &[]).unwrap();
+        assert!(store.rollback().is_err());
+        assert_eq!(store.active().as_deref(), Some("v1.1.0"));
+    }
+
+    #[test]
+    fn remove_refuses_versions_in_use() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(&dir);
+        install(&store, "v1.0.0");
+        let old_exe = install(&store, "v0.9.0");
+        store.activate("v1.0.0").unwrap();
+
+        assert!(store.remove("v1.0.0", &[], &[]).unwrap_err().contains("正在使用中"));
+        let running = vec![("a".to_string(), old_exe.to_string_lossy().to_string())];
+        assert!(store.remove("v0.9.0", &running, &[]).unwrap_err().contains("隧道 a"));
+        let pinned = vec![("b".to_string(), "v0.9.0".to_string())];
+        assert!(store.remove("v0.9.0", &[], &pinned).unwrap_err().contains("隧道 b"));
+
+        store.remove("v0.9.0", &[], &[]).unwrap();
+        assert!(!store.is_installed("v0.9.0"));
+        assert!(store.remove("v0.9.0", &[], &[]).is_err());
+    }
+
+    #[test]
+    fn resolve_uses_pin_or_active_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(&dir);
+        assert_eq!(store.resolve(None).unwrap(), None);
+
+        let active = install(&store, "v1.0.0");
+        let pinned = install(&store, "v0.9.0");
+        store.activate("v1.0.0").unwrap();
+        assert_eq!(store.resolve(None).unwrap(), Some(active.clone()));
+        assert_eq!(store.resolve(Some(" ")).unwrap(), Some(active));
+        assert_eq!(store.resolve(Some("v0.9.0")).unwrap(), Some(pinned));
+        assert!(store.resolve(Some("v2.0.0")).unwrap_err().contains("未安装"));
+        assert!(store.resolve(Some("../v1.0.0")).is_err());
+    }
+
+    #[test]
+    fn pointer_is_written_atomically() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(&dir);
+        install(&store, "v1.0.0");
+        store.activate("v1.0.0").unwrap();
+
+        assert!(!dir.path().join("active.json.tmp").exists());
+        let pointer: ActivePointer =
+            serde_json::from_str(&fs::read_to_string(dir.path().join("active.json")).unwrap()).unwrap();
+        assert_eq!(pointer.active.as_deref(), Some("v1.0.0"));
+
+        // 损坏的版本信息按没有当前版本处理，重新激活后恢复
+        fs::write(dir.path().join("active.json"), "{").unwrap();
+        assert_eq!(store.active(), None);
+        store.activate("v1.0.0").unwrap();
+        assert_eq!(store.active().as_deref(), Some("v1.0.0"));
+    }
+
+    #[test]
+    fn staging_dirs_are_not_listed() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(&dir);
+        install(&store, "v1.0.0");
+        let staging = store.staging_dir();
+        fs::create_dir_all(&staging).unwrap();
+        fs::write(staging.join(platform::executable_name()), "staged").unwrap();
+
+        let tags: Vec<String> = store.list().into_iter().map(|version| version.tag).collect();
+        assert_eq!(tags, ["v1.0.0"]);
+    }
+}
@@ -79,8 +79,15 @@ const AppContent: React.FC = () => {
       }
     })
 
+    // 后台检查到 NodePass 新版本
+    const unlistenUpdate = listen('update-available', (event) => {
+      const { latest, current } = event.payload as { latest: string, current: string }
+      message.info(`NodePass ${latest} 已发布（当前 ${current}），可在系统设置中更新`)
+    })
+
     return () => {
       unlistenClose.then(fn => fn())
+      unlistenUpdate.then(fn => fn())
       unlistenWindowTheme.then(fn => fn())
       unlistenTheme.then(fn => fn())
     }
@@ -2,8 +2,16 @@ import React, { useState, useEffect } from 'react'
 import { Modal, Form, Input, Switch, Select, Button, Space, Row, Col, message } from 'antd'
 import { FontAwesomeIcon } from '@fortawesome/react-fontawesome'
 import { faGlobe, faEye, faEyeSlash } from '@fortawesome/free-solid-svg-icons'
+import { invoke } from '@tauri-apps/api/core'
 import { ProxySettings } from '../utils/config'
 
+interface ProxyTestResult {
+  ok: boolean
+  latencyMs?: number
+  status?: number
+  error?: string
+}
+
 interface ProxySettingsModalProps {
   open: boolean
   onCancel: () => void
@@ -19,6 +27,7 @@ const ProxySettingsModal: React.FC<ProxySettingsModalProps> = ({
 }) => {
   const [form] = Form.useForm()
   const [loading, setLoading] = useState(false)
+  const [testing, setTesting] = useState(false)
 
 
   useEffect(() => {
@@ -44,6 +53,34 @@ const ProxySettingsModal: React.FC<ProxySettingsModalProps> = ({
     }
   }
 
+  // 使用当前填写的设置测试代理
+  const handleTest = async () => {
+    try {
+      setTesting(true)
+      const values = await form.validateFields()
+      const result = await invoke<ProxyTestResult>('test_proxy', {
+        proxySettings: {
+          ...values,
+          enabled: true,
+          username: values.username || '',
+          password: values.password || ''
+        }
+      })
+      if (result.ok) {
+        message.success(`代理可用，延迟 ${result.latencyMs} ms`)
+      } else {
+        message.error(`代理不可用: ${result.error}`)
+      }
+    } catch (error) {
+      console.error('测试代理失败:', error)
+      if (typeof error === 'string') {
+        message.error(`测试代理失败: ${error}`)
+      }
+    } finally {
+      setTesting(false)
+    }
+  }
+
   const handleCancel = () => {
     form.resetFields()
     onCancel()
@@ -64,6 +101,9 @@ const ProxySettingsModal: React.FC<ProxySettingsModalProps> = ({
           <Button onClick={handleCancel}>
             取消
           </Button>
+          <Button onClick={handleTest} loading={testing}>
+            测试连接
+          </Button>
           <Button type="primary" onClick={handleSave} loading={loading}>
             保存
           </Button>
@@ -93,7 +133,9 @@ const ProxySettingsModal: React.FC<ProxySettingsModalProps> = ({
         >
           <Select>
             <Select.Option value="http">HTTP</Select.Option>
+            <Select.Option value="https">HTTPS</Select.Option>
             <Select.Option value="socks5">SOCKS5</Select.Option>
+            <Select.Option value="socks5h">SOCKS5h（远程解析域名）</Select.Option>
           </Select>
         </Form.Item>
 
@@ -7,8 +7,8 @@ import {
 } from '@ant-design/icons'
 import { FontAwesomeIcon } from '@fortawesome/react-fontawesome'
 import { faServer, faDesktop } from '@fortawesome/free-solid-svg-icons'
-import { configManager } from '../utils/config'
-import { invoke } from '@tauri-apps/api/core'
+import { NodePassLogLevel } from '../utils/config'
+import { createTunnel, startTunnel } from '../utils/tunnels'
 import { useLog } from '../context/LogContext'
 import { useTunnel } from '../context/TunnelContext'
 
@@ -21,7 +21,7 @@ interface CreateTunnelForm {
   tunnelAddr: string
   targetAddr: string
   tlsMode: '0' | '1' | '2'
-  logLevel: 'error' | 'info' | 'debug'
+  logLevel: NodePassLogLevel
   certFile?: string
   keyFile?: string
   tlsConfig?: string
@@ -57,8 +57,8 @@ const CreateTunnel: React.FC = () => {
 
   const handleDeploy = async () => {
     try {
-      // 使用配置管理器保存隧道，添加默认的protocol字段（虽然不再使用）
-      const tunnelId = await configManager.addTunnel(config)
+      // 隧道定义保存到后端注册表
+      const tunnelId = await createTunnel(config)
       console.log('隧道创建成功，ID:', tunnelId)
       addLog('info', `隧道创建成功: ${config.name}`, 'CreateTunnel')
       message.success('隧道创建成功！')
@@ -73,30 +73,9 @@ const CreateTunnel: React.FC = () => {
         onOk: async () => {
           try {
             addLog('info', `开始启动隧道: ${config.name}`, 'CreateTunnel')
-            
-            // 构建NodePass配置
-            const nodePassConfig = {
-              mode: config.mode,
-              tunnelAddr: config.tunnelAddr,
-              targetAddr: config.targetAddr,
-              logLevel: config.logLevel,
-              tlsMode: config.tlsMode,
-              certFile: config.certFile || null,
-              keyFile: config.keyFile || null
-            }
 
-            // 调用Rust后端启动NodePass
-            const processId = await invoke<number>('start_nodepass', { 
-              config: nodePassConfig,
-              tunnelId: tunnelId
-            })
-            
-            // 更新隧道状态
-            await configManager.updateTunnel(tunnelId, { 
-              status: 'running',
-              processId: processId,
-              lastStarted: new Date().toISOString()
-            })
+            // 后端按隧道ID启动，运行状态由后端维护
+            const processId = await startTunnel(tunnelId)
 
             message.success(`隧道启动成功，进程ID: ${processId}`)
             addLog('info', `隧道 ${config.name} 启动成功，进程ID: ${processId}`, 'CreateTunnel')
@@ -104,13 +83,10 @@ const CreateTunnel: React.FC = () => {
             // 触发隧道管理页面刷新
             triggerRefresh()
           } catch (error) {
-            const errorMsg = `启动隧道失败: ${error}`
+            const errorMsg = `启动隧道失败: ${(error as any)?.message ?? error}`
             message.error(errorMsg)
             addLog('error', errorMsg, 'CreateTunnel')
-            
-            // 更新隧道状态为错误
-            await configManager.updateTunnel(tunnelId, { status: 'error' })
-            
+
             // 触发隧道管理页面刷新以更新错误状态
             triggerRefresh()
           }
@@ -156,6 +132,9 @@ const CreateTunnel: React.FC = () => {
         if (certFile) params.set('crt', certFile)
         if (keyFile) params.set('key', keyFile)
       }
+    } else if (tlsMode !== '0') {
+      // 与后端一致，客户端只在 TLS 模式不为 0 时带上 tls 参数
+      params.set('tls', tlsMode)
     }
     
     return `${cmd}?${params.toString()}`
@@ -196,10 +175,10 @@ const CreateTunnel: React.FC = () => {
                       rules={[{ required: true, message: '请选择日志级别' }]}
                     >
                       <Select placeholder="选择日志级别">
-                        <Option value="error">
+                        <Option value="debug">
                           <Space>
-                            <span>❌</span>
-                            <span>Error - 仅错误信息</span>
+                            <span>🐛</span>
+                            <span>Debug - 详细调试信息</span>
                           </Space>
                         </Option>
                         <Option value="info">
@@ -208,10 +187,28 @@ const CreateTunnel: React.FC = () => {
                             <span>Info - 常规信息</span>
                           </Space>
                         </Option>
-                        <Option value="debug">
+                        <Option value="warn">
                           <Space>
-                            <span>🐛</span>
-                            <span>Debug - 详细调试信息</span>
+                            <span>⚠️</span>
+                            <span>Warn - 警告及错误信息</span>
+                          </Space>
+                        </Option>
+                        <Option value="error">
+                          <Space>
+                            <span>❌</span>
+                            <span>Error - 仅错误信息</span>
+                          </Space>
+                        </Option>
+                        <Option value="event">
+                          <Space>
+                            <span>📊</span>
+                            <span>Event - 仅事件信息</span>
+                          </Space>
+                        </Option>
+                        <Option value="none">
+                          <Space>
+                            <span>🔇</span>
+                            <span>None - 不输出日志</span>
                           </Space>
                         </Option>
                       </Select>
@@ -1,5 +1,5 @@
 import React, { useState, useEffect, useRef } from 'react'
-import { Card, Button, Tag, Space, Modal, message, Switch, Radio, Input } from 'antd'
+import { Card, Button, Tag, Space, Modal, message, Switch, Radio, Input, Checkbox } from 'antd'
 import { FontAwesomeIcon } from '@fortawesome/react-fontawesome'
 import { 
   faDownload, 
@@ -24,6 +24,9 @@ import { ProxySettings } from '../utils/config'
 interface NodePassStatus {
   installed: boolean
   version?: string
+  os?: string
+  arch?: string
+  build?: string
   path?: string
   error?: string
 }
@@ -43,12 +46,14 @@ interface GitHubAsset {
 }
 
 interface DownloadProgress {
-  status: 'started' | 'downloading' | 'extracting' | 'completed' | 'error'
+  status: 'started' | 'downloading' | 'paused' | 'extracting' | 'completed' | 'error'
   progress?: number
   downloaded?: number
   total?: number
   message: string
   path?: string
+  // 当前使用的下载源
+  source?: string
 }
 
 // 设置项组件
@@ -94,6 +99,15 @@ const SystemSettings: React.FC<SystemSettingsProps> = () => {
   const [showProxyModal, setShowProxyModal] = useState(false)
   const [showEnvModal, setShowEnvModal] = useState(false)
   const [isDownloading, setIsDownloading] = useState(false)
+  // 当前下载的文件名，取消时用于清理已下载的部分
+  const [downloadingFile, setDownloadingFile] = useState<string | null>(null)
+  // 更新完成后逐个重启运行中的隧道
+  const [rollingRestart, setRollingRestart] = useState(false)
+  // 获取不到校验文件时终止下载，而不是跳过校验
+  const [requireChecksum, setRequireChecksum] = useState(false)
+  // 离线安装使用的本地文件路径
+  const [offlinePath, setOfflinePath] = useState('')
+  const [installingOffline, setInstallingOffline] = useState(false)
   const [envConfig, setEnvConfig] = useState<'none' | 'custom' | 'high-throughput' | 'low-latency' | 'resource-limited'>('none')
   
   // 从Context获取设置和日志
@@ -122,6 +136,26 @@ const SystemSettings: React.FC<SystemSettingsProps> = () => {
         addLog('error', `NodePass 下载失败: ${progress.message}`, 'SystemSettings')
         console.error('下载失败:', progress.message)
         setIsDownloading(false)
+      } else if (progress.status === 'paused') {
+        addLog('info', 'NodePass 下载已暂停', 'SystemSettings')
+        setIsDownloading(false)
+      }
+    })
+
+    // 监听升级后的滚动重启结果
+    const unlistenUpgrade = listen('nodepass-upgrade-completed', (event) => {
+      const payload = event.payload as { version: string, success: boolean, results: { tunnel_id: string, status: string, error?: string }[] }
+      payload.results.forEach(result => {
+        addLog(
+          result.status === 'failed' ? 'error' : 'info',
+          `隧道 ${result.tunnel_id} 升级到 ${payload.version}: ${result.status}${result.error ? ` (${result.error})` : ''}`,
+          'SystemSettings'
+        )
+      })
+      if (payload.success) {
+        message.success(`已使用 ${payload.version} 重启运行中的隧道`)
+      } else {
+        message.error('部分隧道重启失败，请查看日志')
       }
     })
 
@@ -131,6 +165,7 @@ const SystemSettings: React.FC<SystemSettingsProps> = () => {
 
     return () => {
       unlistenDownload.then(fn => fn())
+      unlistenUpgrade.then(fn => fn())
     }
   }, [])
 
@@ -181,6 +216,7 @@ const SystemSettings: React.FC<SystemSettingsProps> = () => {
   const downloadNodePass = async (asset: GitHubAsset) => {
     try {
       setIsDownloading(true)
+      setDownloadingFile(asset.name)
       addLog('info', `开始下载 NodePass: ${asset.name}`, 'SystemSettings')
       setDownloadProgress({
         status: 'started',
@@ -198,7 +234,10 @@ const SystemSettings: React.FC<SystemSettingsProps> = () => {
         invoke('download_nodepass', {
           downloadUrl: asset.browser_download_url,
           filename: asset.name,
-          proxySettings: settings.proxy
+          proxySettings: settings.proxy,
+          release: latestRelease,
+          rollingRestart: nodePassStatus?.installed ? rollingRestart : false,
+          requireChecksum
         })
         .then(resolve)
         .catch(reject)
@@ -213,6 +252,10 @@ const SystemSettings: React.FC<SystemSettingsProps> = () => {
       }, 1000)
       
     } catch (error: any) {
+      // 暂停不是错误，保留暂停状态以便继续下载
+      if (error === '下载已暂停') {
+        return
+      }
       const errorMsg = `下载失败: ${error?.message || error || '未知错误'}`
       console.error('下载函数调用失败:', error)
       addLog('error', errorMsg, 'SystemSettings')
@@ -262,27 +305,8 @@ const SystemSettings: React.FC<SystemSettingsProps> = () => {
     }
   }
 
-  // 获取适合当前系统的下载包
-  const getSystemAppropriateAsset = (assets: GitHubAsset[]) => {
-    // 优先查找 windows amd64 版本
-    const windowsAssets = assets.filter(asset => 
-      asset.name.includes('windows') && asset.name.includes('amd64')
-    );
-    
-    if (windowsAssets.length > 0) {
-      return windowsAssets[0];
-    }
-    
-    // 如果没找到，查找任何 windows 版本
-    const anyWindowsAssets = assets.filter(asset => 
-      asset.name.includes('windows') || asset.name.includes('win')
-    );
-    
-    return anyWindowsAssets.length > 0 ? anyWindowsAssets[0] : null;
-  }
-
-  // 下载适合系统的包
-  const downloadSystemAppropriate = () => {
+  // 下载适合系统的包，由后端按当前系统和架构选择
+  const downloadSystemAppropriate = async () => {
     try {
       if (!latestRelease) {
         console.error('没有最新版本信息')
@@ -290,26 +314,54 @@ const SystemSettings: React.FC<SystemSettingsProps> = () => {
       }
       
       console.log('开始选择适合系统的下载包')
-      const asset = getSystemAppropriateAsset(latestRelease.assets)
-      
-      if (asset) {
-        console.log('找到适合的下载包:', asset)
-        downloadNodePass(asset)
-      } else {
-        console.error('未找到适合当前系统的下载包')
-        message.error('未找到适合当前系统的下载包')
-      }
+      const asset = await invoke<GitHubAsset>('select_release_asset', { release: latestRelease })
+      console.log('找到适合的下载包:', asset)
+      downloadNodePass(asset)
     } catch (error) {
+      message.error(`${error}`)
       console.error('downloadSystemAppropriate 函数错误:', error)
       addLog('error', `选择下载包失败: ${error}`, 'SystemSettings')
     }
   }
 
+  // 从本地压缩包或可执行文件安装，用于无法访问发布源的环境
+  const installFromFile = async () => {
+    const path = offlinePath.trim()
+    if (!path) {
+      message.warning('请输入本地文件路径')
+      return
+    }
+
+    try {
+      setInstallingOffline(true)
+      addLog('info', `从本地文件安装 NodePass: ${path}`, 'SystemSettings')
+      const version = await invoke<string>('install_nodepass_from_file', { path })
+      addLog('info', `离线安装完成: ${version}`, 'SystemSettings')
+      setOfflinePath('')
+    } catch (error) {
+      message.error(`离线安装失败: ${error}`)
+      addLog('error', `离线安装失败: ${error}`, 'SystemSettings')
+    } finally {
+      setInstallingOffline(false)
+    }
+  }
+
+  // 暂停下载，已下载的部分会保留
+  const pauseDownload = async () => {
+    try {
+      await invoke('pause_download')
+    } catch (error) {
+      console.error('暂停下载失败:', error)
+      addLog('error', `暂停下载失败: ${error}`, 'SystemSettings')
+    }
+  }
+
   // 取消下载
   const cancelDownload = async () => {
     try {
-      // 调用后端取消下载
-      await invoke('cancel_download')
+      // 调用后端取消下载，并删除已下载的部分
+      await invoke('cancel_download', { filename: downloadingFile })
+      setDownloadingFile(null)
       setIsDownloading(false)
       setDownloadProgress(null)
       setShowDownloadModal(false)
@@ -452,7 +504,7 @@ const SystemSettings: React.FC<SystemSettingsProps> = () => {
                         height: 'auto',
                         lineHeight: '1.2'
                       }}
-                      title="点击检查更新"
+                      title={`${nodePassStatus.os ? `${nodePassStatus.os}/${nodePassStatus.arch} ` : ''}${nodePassStatus.build ? `${nodePassStatus.build} ` : ''}点击检查更新`}
                     >
                       {nodePassStatus.version || 'v?.?.?'}
                     </Button>
@@ -473,6 +525,29 @@ const SystemSettings: React.FC<SystemSettingsProps> = () => {
               </Space>
             </SettingItem>
 
+            <SettingItem 
+              label="离线安装"
+              description="从本地 .tar.gz、.zip 或可执行文件安装 NodePass"
+            >
+              <Space.Compact>
+                <Input
+                  size="small"
+                  value={offlinePath}
+                  placeholder="文件路径"
+                  onChange={(e) => setOfflinePath(e.target.value)}
+                  style={{ width: 180 }}
+                />
+                <Button
+                  size="small"
+                  onClick={installFromFile}
+                  loading={installingOffline}
+                  disabled={isDownloading}
+                >
+                  安装
+                </Button>
+              </Space.Compact>
+            </SettingItem>
+
             <SettingItem 
               label="应用版本"
               description="NodePass GUI 当前版本号"
@@ -687,7 +762,8 @@ const SystemSettings: React.FC<SystemSettingsProps> = () => {
                 }}>
                   {downloadProgress.status === 'completed' && '✅ 安装完成！请重新检测核心状态'}
                   {downloadProgress.status === 'error' && '❌ 下载失败，请查看日志'}
-                  {downloadProgress.status === 'downloading' && '⬇️ 正在下载...'}
+                  {downloadProgress.status === 'downloading' && `⬇️ 正在下载...${downloadProgress.source ? ` (${downloadProgress.source})` : ''}`}
+                  {downloadProgress.status === 'paused' && '⏸️ 下载已暂停'}
                   {downloadProgress.status === 'extracting' && '📦 正在安装...'}
                   {downloadProgress.status === 'started' && '🚀 准备下载...'}
                 </div>
@@ -703,14 +779,35 @@ const SystemSettings: React.FC<SystemSettingsProps> = () => {
             
             {/* 右侧：按钮 */}
             <Space>
+              <Checkbox
+                checked={requireChecksum}
+                disabled={isDownloading}
+                onChange={(e) => setRequireChecksum(e.target.checked)}
+              >
+                必须通过完整性校验
+              </Checkbox>
+              {nodePassStatus?.installed && (
+                <Checkbox
+                  checked={rollingRestart}
+                  disabled={isDownloading}
+                  onChange={(e) => setRollingRestart(e.target.checked)}
+                >
+                  更新后重启运行中的隧道
+                </Checkbox>
+              )}
               <Button 
-                onClick={isDownloading ? cancelDownload : () => {
+                onClick={isDownloading || downloadProgress?.status === 'paused' ? cancelDownload : () => {
                   setShowDownloadModal(false)
                   setDownloadProgress(null)
                 }}
               >
                 取消
               </Button>
+              {downloadProgress?.status === 'downloading' && (
+                <Button onClick={pauseDownload}>
+                  暂停
+                </Button>
+              )}
               <Button 
                 type="primary"
                 icon={downloadProgress?.status === 'downloading' || downloadProgress?.status === 'extracting' || downloadProgress?.status === 'started' ? undefined : <FontAwesomeIcon icon={faDownload} />}
@@ -722,6 +819,7 @@ const SystemSettings: React.FC<SystemSettingsProps> = () => {
                  downloadProgress?.status === 'downloading' ? '下载中...' :
                  downloadProgress?.status === 'extracting' ? '安装中...' :
                  downloadProgress?.status === 'completed' ? '已完成' :
+                 downloadProgress?.status === 'paused' ? '继续下载' :
                  downloadProgress?.status === 'error' ? '重新下载' : 
                  (nodePassStatus?.installed ? '更新' : '下载安装')}
               </Button>
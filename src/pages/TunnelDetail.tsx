@@ -5,28 +5,13 @@ import { FontAwesomeIcon } from '@fortawesome/react-fontawesome'
 import { faArrowLeft, faTrash, faPlay, faPause } from '@fortawesome/free-solid-svg-icons'
 import { listen } from '@tauri-apps/api/event'
 import { invoke } from '@tauri-apps/api/core'
-import { configManager } from '../utils/config'
+import { TunnelConfig } from '../utils/config'
+import { getTunnel, startTunnel, stopTunnel } from '../utils/tunnels'
 import { useLog } from '../context/LogContext'
 import { useSettings } from '../context/SettingsContext'
 
 const { Text } = Typography
 
-interface TunnelConfig {
-  id: string
-  name: string
-  mode: string
-  tunnelAddr: string
-  targetAddr: string
-  status: string
-  processId?: number
-  createdAt: string
-  lastStarted?: string
-  logLevel?: string
-  tlsMode?: string
-  certFile?: string
-  keyFile?: string
-}
-
 const TunnelDetail: React.FC = () => {
   const { id } = useParams<{ id: string }>()
   const navigate = useNavigate()
@@ -96,22 +81,16 @@ const TunnelDetail: React.FC = () => {
     const loadTunnelAndLogs = async () => {
       try {
         console.log('正在加载隧道信息，ID:', id)
-        const currentTunnel = configManager.getTunnel(id || '')
+        const currentTunnel = await getTunnel(id || '')
         console.log('获取到的隧道信息:', currentTunnel)
-        
-        if (!currentTunnel) {
-          message.error('隧道不存在')
-          navigate('/tunnels')
-          return
-        }
 
         setTunnel(currentTunnel)
 
         // 如果隧道正在运行，获取历史日志
-        if (currentTunnel.status === 'running' && currentTunnel.processId) {
+        if (currentTunnel.status === 'running') {
           try {
             const historyLogs = await invoke<string[]>('get_tunnel_logs', { 
-              processId: currentTunnel.processId 
+              tunnelId: currentTunnel.id
             })
             setLogs(historyLogs)
           } catch (error) {
@@ -131,38 +110,41 @@ const TunnelDetail: React.FC = () => {
     }
   }, [id, navigate, addLog])
 
+  // 隧道状态由后端注册表维护，状态变化时重新读取
+  useEffect(() => {
+    let unlisten: () => void;
+
+    listen('tunnel-status-changed', async (event) => {
+      const { tunnel_id } = event.payload as any;
+      if (tunnel_id === id) {
+        try {
+          setTunnel(await getTunnel(tunnel_id))
+        } catch (error) {
+          console.error('刷新隧道状态失败:', error)
+        }
+      }
+    }).then(fn => {
+      unlisten = fn;
+    });
+
+    return () => {
+      unlisten?.();
+    };
+  }, [id]);
+
   // 启动隧道
   const handleStart = async () => {
     if (!tunnel) return
     try {
       addLog('info', `开始启动隧道: ${tunnel.name}`, 'TunnelDetail')
-      
-      const nodePassConfig = {
-        mode: tunnel.mode,
-        tunnelAddr: tunnel.tunnelAddr,
-        targetAddr: tunnel.targetAddr,
-        logLevel: tunnel.logLevel,
-        tlsMode: tunnel.tlsMode,
-        certFile: tunnel.certFile || null,
-        keyFile: tunnel.keyFile || null
-      }
 
-      const processId = await invoke<number>('start_nodepass', { 
-        config: nodePassConfig,
-        tunnelId: tunnel.id
-      })
-      
-      await configManager.updateTunnel(tunnel.id, { 
-        status: 'running',
-        processId: processId,
-        lastStarted: new Date().toISOString()
-      })
-      
+      const processId = await startTunnel(tunnel.id)
+
       message.success(`隧道 ${tunnel.name} 启动成功`)
       addLog('info', `隧道 ${tunnel.name} 启动成功，进程ID: ${processId}`, 'TunnelDetail')
-      setTunnel((prev: TunnelConfig | null) => prev ? { ...prev, status: 'running', processId } : null)
+      setTunnel(await getTunnel(tunnel.id))
     } catch (error) {
-      const errorMsg = `启动隧道失败: ${error}`
+      const errorMsg = `启动隧道失败: ${(error as any)?.message ?? error}`
       message.error(errorMsg)
       addLog('error', errorMsg, 'TunnelDetail')
     }
@@ -170,19 +152,14 @@ const TunnelDetail: React.FC = () => {
 
   // 停止隧道
   const handleStop = async () => {
-    if (!tunnel || !tunnel.processId) return
+    if (!tunnel) return
     try {
       addLog('info', `开始停止隧道: ${tunnel.name}`, 'TunnelDetail')
-      await invoke('stop_nodepass_by_pid', { processId: tunnel.processId })
-      
-      await configManager.updateTunnel(tunnel.id, { 
-        status: 'stopped',
-        processId: undefined
-      })
-      
+      await stopTunnel(tunnel.id)
+
       message.success(`隧道 ${tunnel.name} 已停止`)
       addLog('info', `隧道 ${tunnel.name} 已停止`, 'TunnelDetail')
-      setTunnel((prev: TunnelConfig | null) => prev ? { ...prev, status: 'stopped', processId: undefined } : null)
+      setTunnel(await getTunnel(tunnel.id))
     } catch (error) {
       const errorMsg = `停止隧道失败: ${error}`
       message.error(errorMsg)
@@ -5,7 +5,8 @@ import { FontAwesomeIcon } from '@fortawesome/react-fontawesome'
 import { faArrowLeft, faTrash } from '@fortawesome/free-solid-svg-icons'
 import { listen } from '@tauri-apps/api/event'
 import { invoke } from '@tauri-apps/api/core'
-import { configManager, TunnelConfig } from '../utils/config'
+import { TunnelConfig } from '../utils/config'
+import { listTunnels } from '../utils/tunnels'
 import { useLog } from '../context/LogContext'
 import { useSettings } from '../context/SettingsContext'
 
@@ -81,7 +82,7 @@ const TunnelLog: React.FC = () => {
   useEffect(() => {
     const loadTunnelAndLogs = async () => {
       try {
-        const tunnelList = configManager.getTunnels()
+        const tunnelList = await listTunnels()
         const currentTunnel = tunnelList.find(t => t.id === id)
         
         if (!currentTunnel) {
@@ -93,10 +94,10 @@ const TunnelLog: React.FC = () => {
         setTunnel(currentTunnel)
 
         // 如果隧道正在运行，获取历史日志
-        if (currentTunnel.status === 'running' && currentTunnel.processId) {
+        if (currentTunnel.status === 'running') {
           try {
             const historyLogs = await invoke<string[]>('get_tunnel_logs', { 
-              processId: currentTunnel.processId 
+              tunnelId: currentTunnel.id
             })
             setLogs(historyLogs)
           } catch (error) {
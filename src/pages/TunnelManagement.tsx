@@ -9,8 +9,8 @@ import {
 import { FontAwesomeIcon } from '@fortawesome/react-fontawesome'
 import { faPlay, faEye, faPause, faTrash, faTh, faList, faSync, faSearch, faPlus } from '@fortawesome/free-solid-svg-icons'
 import { useNavigate } from 'react-router-dom'
-import { configManager, TunnelConfig } from '../utils/config'
-import { invoke } from '@tauri-apps/api/core'
+import { TunnelConfig } from '../utils/config'
+import { listTunnels, startTunnel, stopTunnel, deleteTunnel, getTunnelStoreError } from '../utils/tunnels'
 import { listen } from '@tauri-apps/api/event'
 import { useLog } from '../context/LogContext'
 import { useSettings } from '../context/SettingsContext'
@@ -86,23 +86,6 @@ const StatCard: React.FC<{
 
 
 
-interface NodePassLogEvent {
-  tunnel_id: string
-  message: string
-}
-
-interface ProcessExitEvent {
-  tunnel_id: string
-  process_id: number
-  exit_code: number
-}
-
-interface ProcessErrorEvent {
-  tunnel_id: string
-  process_id: number
-  error: string
-}
-
 interface TunnelManagementProps {
   // 移除 onRegisterRefresh 属性
 }
@@ -120,7 +103,6 @@ const TunnelManagement: React.FC<TunnelManagementProps> = () => {
     stopped: 0,
     error: 0
   })
-  const [tunnelStartTimes, setTunnelStartTimes] = useState<Map<string, number>>(new Map())
   const [searchText, setSearchText] = useState('')
   const [viewMode, setViewMode] = useState<'card' | 'table'>('card')
 
@@ -132,119 +114,6 @@ const TunnelManagement: React.FC<TunnelManagementProps> = () => {
     tunnel.mode.toLowerCase().includes(searchText.toLowerCase())
   )
 
-  // 监听NodePass日志
-  useEffect(() => {
-    const unlistenLog = listen<NodePassLogEvent>('nodepass-log', (event) => {
-      const { tunnel_id, message } = event.payload
-      console.log('NodePass日志:', tunnel_id, message)
-      
-      // 检测日志中的错误信息
-      const isError = message.includes('[ERROR]') || message.includes('ERROR')
-      
-      // 检查隧道启动时间，避免在启动后立即检测错误（给5秒缓冲时间）
-      const tunnelStartTime = tunnelStartTimes.get(tunnel_id)
-      const currentTime = Date.now()
-      const isWithinStartupPeriod = tunnelStartTime && (currentTime - tunnelStartTime) < 5000 // 5秒内
-      
-      // 检测是否为致命错误 - 只检测 "ERROR Resolve failed"
-      const isFatalError = isError && !isWithinStartupPeriod && (
-        message.toLowerCase().includes('resolve failed')
-      )
-      
-      // 如果检测到致命错误，更新隧道状态为错误
-      if (isFatalError) {
-        console.log('检测到致命错误日志，停止隧道进程:', tunnel_id, message)
-        
-        // 异步处理停止进程和更新状态
-        const handleFatalError = async () => {
-          // 先获取当前隧道信息
-          const currentTunnel = configManager.getTunnels().find(t => t.id === tunnel_id)
-          
-          // 如果隧道正在运行且有进程ID，先停止进程
-          if (currentTunnel && currentTunnel.status === 'running' && currentTunnel.processId) {
-            try {
-              console.log('停止错误隧道进程:', currentTunnel.processId)
-              await invoke('stop_nodepass_by_pid', { processId: currentTunnel.processId })
-              addLog('info', `隧道 ${tunnel_id} 因致命错误已自动停止`, 'TunnelManagement')
-            } catch (stopError) {
-              console.error('停止错误隧道进程失败:', stopError)
-              addLog('error', `停止错误隧道进程失败: ${stopError}`, 'TunnelManagement')
-            }
-          }
-          
-          // 更新隧道状态为错误
-          try {
-            await configManager.updateTunnel(tunnel_id, { 
-              status: 'error',
-              processId: undefined
-            })
-            loadTunnels()
-            addLog('error', `隧道 ${tunnel_id} 检测到致命错误: ${message}`, 'TunnelManagement')
-            // 清除启动时间记录
-            setTunnelStartTimes(prev => {
-              const newMap = new Map(prev)
-              newMap.delete(tunnel_id)
-              return newMap
-            })
-          } catch (err) {
-            console.error('更新隧道状态失败:', err)
-          }
-        }
-        
-        // 执行异步处理
-        handleFatalError()
-      }
-    })
-
-    const unlistenExit = listen<ProcessExitEvent>('nodepass-process-exit', (event) => {
-      const { tunnel_id, process_id, exit_code } = event.payload
-      console.log('进程退出:', tunnel_id, process_id, exit_code)
-      
-      // 清除启动时间记录
-      setTunnelStartTimes(prev => {
-        const newMap = new Map(prev)
-        newMap.delete(tunnel_id)
-        return newMap
-      })
-      
-      // 更新隧道状态
-      configManager.updateTunnel(tunnel_id, { 
-        status: 'stopped',
-        processId: undefined
-      }).then(() => {
-        loadTunnels()
-        addLog('info', `隧道 ${tunnel_id} 进程已退出，状态码: ${exit_code}`, 'TunnelManagement')
-      })
-    })
-
-    const unlistenError = listen<ProcessErrorEvent>('nodepass-process-error', (event) => {
-      const { tunnel_id, process_id, error } = event.payload
-      console.log('进程错误:', tunnel_id, process_id, error)
-      
-      // 清除启动时间记录
-      setTunnelStartTimes(prev => {
-        const newMap = new Map(prev)
-        newMap.delete(tunnel_id)
-        return newMap
-      })
-      
-      // 更新隧道状态
-      configManager.updateTunnel(tunnel_id, { 
-        status: 'error',
-        processId: undefined
-      }).then(() => {
-        loadTunnels()
-        addLog('error', `隧道 ${tunnel_id} 进程错误: ${error}`, 'TunnelManagement')
-      })
-    })
-
-    return () => {
-      unlistenLog.then(fn => fn())
-      unlistenExit.then(fn => fn())
-      unlistenError.then(fn => fn())
-    }
-  }, [])
-
   // 监听隧道状态变化
   useEffect(() => {
     let unlistenStatus: () => void;
@@ -252,64 +121,33 @@ const TunnelManagement: React.FC<TunnelManagementProps> = () => {
     let unlistenAllStopped: () => void;
     let unlistenFatalError: () => void;
 
-    // 监听致命错误事件
-    listen('fatal-error-detected', async (event) => {
+    // 监听致命错误事件，隧道已由后端停止
+    listen('fatal-error-detected', (event) => {
       const { tunnel_id, pid, error } = event.payload as any;
       console.log(`检测到致命错误: 隧道 ${tunnel_id} (PID: ${pid}) - ${error}`);
-      
-      try {
-        // 调用后端处理致命错误
-        await invoke('handle_fatal_error', {
-          tunnelId: tunnel_id,
-          processId: pid,
-          errorMessage: error
-        });
-        
-        addLog('error', `隧道 ${tunnel_id} 检测到致命错误并已自动停止: ${error}`, 'FatalErrorHandler');
-      } catch (handleError) {
-        console.error('处理致命错误失败:', handleError);
-        addLog('error', `处理致命错误失败: ${handleError}`, 'FatalErrorHandler');
-      }
+      addLog('error', `隧道 ${tunnel_id} 检测到致命错误并已自动停止: ${error}`, 'FatalErrorHandler');
     }).then(fn => {
       unlistenFatalError = fn;
     });
 
-    // 监听隧道状态变化
-    listen('tunnel-status-changed', async (event) => {
+    // 监听隧道状态变化，状态由后端注册表维护
+    listen('tunnel-status-changed', (event) => {
       const { tunnel_id, status, process_id, exit_code, error } = event.payload as any;
       console.log(`隧道状态变化: ${tunnel_id} -> ${status}`, { process_id, exit_code, error });
-      
-      try {
-        // 更新配置文件中的隧道状态
-        await configManager.updateTunnel(tunnel_id, { 
-          status: status,
-          processId: process_id || undefined
-        });
-        
-        // 更新前端状态
-        setTunnels(prev => prev.map(t => 
-          t.id === tunnel_id 
-            ? { ...t, status, processId: process_id || undefined }
-            : t
-        ));
-        
-        // 记录日志
-        if (status === 'stopped') {
-          if (exit_code !== undefined) {
-            addLog('info', `隧道 ${tunnel_id} 已停止，退出码: ${exit_code}`, 'TunnelManagement');
-          } else {
-            addLog('info', `隧道 ${tunnel_id} 已停止`, 'TunnelManagement');
-          }
-        } else if (status === 'error') {
-          addLog('error', `隧道 ${tunnel_id} 发生错误: ${error || '未知错误'}`, 'TunnelManagement');
+
+      // 记录日志
+      if (status === 'stopped') {
+        if (exit_code !== undefined) {
+          addLog('info', `隧道 ${tunnel_id} 已停止，退出码: ${exit_code}`, 'TunnelManagement');
+        } else {
+          addLog('info', `隧道 ${tunnel_id} 已停止`, 'TunnelManagement');
         }
-        
-        // 重新加载隧道列表以确保状态同步
-        loadTunnels();
-      } catch (updateError) {
-        console.error('更新隧道状态失败:', updateError);
-        addLog('error', `更新隧道状态失败: ${updateError}`, 'TunnelManagement');
+      } else if (status === 'error') {
+        addLog('error', `隧道 ${tunnel_id} 发生错误: ${error || '未知错误'}`, 'TunnelManagement');
       }
+
+      // 重新加载隧道列表以确保状态同步
+      loadTunnels();
     }).then(fn => {
       unlistenStatus = fn;
     });
@@ -324,29 +162,11 @@ const TunnelManagement: React.FC<TunnelManagementProps> = () => {
     });
 
     // 监听所有隧道停止事件
-    listen('all-tunnels-stopped', async (event) => {
+    listen('all-tunnels-stopped', (event) => {
       const { message: logMessage, stopped_count } = event.payload as any;
       console.log(`所有隧道已停止: ${logMessage}, 停止数量: ${stopped_count}`);
       addLog('info', `${logMessage}, 停止数量: ${stopped_count}`, 'ExitHandler');
-      
-      // 更新所有隧道状态为已停止
-      try {
-        const currentTunnels = configManager.getTunnels();
-        for (const tunnel of currentTunnels) {
-          if (tunnel.status === 'running') {
-            await configManager.updateTunnel(tunnel.id, { 
-              status: 'stopped',
-              processId: undefined
-            });
-          }
-        }
-        // 重新加载隧道列表以反映状态变化
-        loadTunnels();
-        addLog('info', '所有隧道状态已更新为已停止', 'TunnelManagement');
-      } catch (error) {
-        console.error('更新隧道状态失败:', error);
-        addLog('error', `更新隧道状态失败: ${error}`, 'TunnelManagement');
-      }
+      loadTunnels();
     }).then(fn => {
       unlistenAllStopped = fn;
     });
@@ -359,11 +179,11 @@ const TunnelManagement: React.FC<TunnelManagementProps> = () => {
     };
   }, [addLog]);
 
-    // 加载隧道列表
+  // 从后端加载隧道列表
   const loadTunnels = async () => {
     setLoading(true)
     try {
-      const tunnelList = configManager.getTunnels()
+      const tunnelList = await listTunnels()
       setTunnels(tunnelList)
       
       // 更新统计数据
@@ -381,14 +201,16 @@ const TunnelManagement: React.FC<TunnelManagementProps> = () => {
     }
   }
 
-  // 组件挂载时加载数据
+  // 组件挂载时加载数据，隧道定义文件有问题时提示用户
   useEffect(() => {
     loadTunnels()
-
-    return () => {
-      // 清理函数，如果有其他监听器需要清理，可以在这里添加
-    }
-  }, [tunnels])
+    getTunnelStoreError().then(error => {
+      if (error) {
+        message.error(error, 10)
+        addLog('error', error, 'TunnelManagement')
+      }
+    }).catch(() => {})
+  }, [])
 
   // 监听刷新触发器
   useEffect(() => {
@@ -412,45 +234,16 @@ const TunnelManagement: React.FC<TunnelManagementProps> = () => {
     try {
       addLog('info', `开始启动隧道: ${tunnel.name}`, 'TunnelManagement')
       
-      // 构建NodePass配置
-      const nodePassConfig = {
-        mode: tunnel.mode,
-        tunnelAddr: tunnel.tunnelAddr,
-        targetAddr: tunnel.targetAddr,
-        logLevel: tunnel.logLevel,
-        tlsMode: tunnel.tlsMode,
-        certFile: tunnel.certFile || null,
-        keyFile: tunnel.keyFile || null
-      }
-
-      // 调用Rust后端启动NodePass，传入隧道ID
-      const processId = await invoke<number>('start_nodepass', { 
-        config: nodePassConfig,
-        tunnelId: tunnel.id
-      })
-      
-      // 更新隧道状态
-      await configManager.updateTunnel(tunnel.id, { 
-        status: 'running',
-        processId: processId,
-        lastStarted: new Date().toISOString()
-      })
-      
-      // 记录隧道启动时间
-      setTunnelStartTimes(prev => new Map(prev.set(tunnel.id, Date.now())))
-      
-
+      // 后端按隧道ID读取配置并启动
+      const processId = await startTunnel(tunnel.id)
 
       message.success(`隧道 ${tunnel.name} 启动成功，进程ID: ${processId}`)
       addLog('info', `隧道 ${tunnel.name} 启动成功，进程ID: ${processId}`, 'TunnelManagement')
-      loadTunnels()
     } catch (error) {
-      const errorMsg = `启动隧道失败: ${error}`
+      const errorMsg = `启动隧道失败: ${(error as any)?.message ?? error}`
       message.error(errorMsg)
       addLog('error', errorMsg, 'TunnelManagement')
-      
-      // 更新隧道状态为错误
-      await configManager.updateTunnel(tunnel.id, { status: 'error' })
+    } finally {
       loadTunnels()
     }
   }
@@ -459,13 +252,7 @@ const TunnelManagement: React.FC<TunnelManagementProps> = () => {
   const handleStop = async (tunnel: TunnelConfig) => {
     try {
       addLog('info', `开始停止隧道: ${tunnel.name}`, 'TunnelManagement')
-      
-      if (!tunnel.processId) {
-        throw new Error('隧道进程ID不存在')
-      }
-      
-      // 调用Rust后端按PID停止NodePass
-      await invoke('stop_nodepass_by_pid', { processId: tunnel.processId })
+      await stopTunnel(tunnel.id)
       
       message.success(`隧道 ${tunnel.name} 已停止`)
       addLog('info', `隧道 ${tunnel.name} 已停止`, 'TunnelManagement')
@@ -474,27 +261,7 @@ const TunnelManagement: React.FC<TunnelManagementProps> = () => {
       message.error(errorMsg)
       addLog('error', errorMsg, 'TunnelManagement')
     } finally {
-      // 无论停止成功还是失败，都将隧道状态设置为已停止
-      try {
-        await configManager.updateTunnel(tunnel.id, { 
-          status: 'stopped',
-          processId: undefined
-        })
-        
-
-        
-        // 清除启动时间记录
-        setTunnelStartTimes(prev => {
-          const newMap = new Map(prev)
-          newMap.delete(tunnel.id)
-          return newMap
-        })
-
-        loadTunnels()
-      } catch (updateError) {
-        console.error('更新隧道状态失败:', updateError)
-        addLog('error', `更新隧道状态失败: ${updateError}`, 'TunnelManagement')
-      }
+      loadTunnels()
     }
   }
 
@@ -502,15 +269,16 @@ const TunnelManagement: React.FC<TunnelManagementProps> = () => {
   const handleDelete = async (tunnel: TunnelConfig) => {
     try {
       // 如果隧道正在运行，先停止
-      if (tunnel.status === 'running' && tunnel.processId) {
-        await handleStop(tunnel)
+      if (tunnel.processId) {
+        await stopTunnel(tunnel.id)
       }
       
-      await configManager.deleteTunnel(tunnel.id)
+      await deleteTunnel(tunnel.id)
       message.success(`隧道 ${tunnel.name} 已删除`)
-      loadTunnels()
     } catch (error) {
       message.error(`删除隧道失败: ${error}`)
+    } finally {
+      loadTunnels()
     }
   }
 
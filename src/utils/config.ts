@@ -1,4 +1,9 @@
-// 隧道配置接口
+import { invoke } from '@tauri-apps/api/core'
+
+// NodePass 的日志级别 (log 参数)，与后端校验的取值一致
+export type NodePassLogLevel = 'debug' | 'info' | 'warn' | 'error' | 'event' | 'none'
+
+// 隧道配置接口，定义保存在后端 tunnels.json，运行状态来自后端注册表
 export interface TunnelConfig {
   id: string
   name: string
@@ -6,13 +11,51 @@ export interface TunnelConfig {
   tunnelAddr: string
   targetAddr: string
   tlsMode: '0' | '1' | '2'
-  logLevel: 'error' | 'info' | 'debug'
+  logLevel: NodePassLogLevel
   certFile?: string
   keyFile?: string
-  status: 'stopped' | 'running' | 'error'
+  // NodePass 扩展参数
+  minPool?: number
+  maxPool?: number
+  runMode?: 0 | 1 | 2
+  readTimeout?: string
+  rateLimit?: number
+  slotLimit?: number
+  proxyProtocol?: boolean
+  disableTcp?: boolean
+  disableUdp?: boolean
+  extraParams?: Record<string, string>
+  // 固定使用的 NodePass 版本，为空时使用当前版本
+  nodepassVersion?: string
+  status: 'stopped' | 'running' | 'restarting' | 'stopping' | 'error'
   processId?: number
   createdAt: string
   lastStarted?: string
+  // 最近一次启动或运行失败的原因
+  error?: string
+}
+
+// 生成后端 NodePassConfig，连接池、限速等扩展参数一并传递
+export function toNodePassConfig(tunnel: Omit<TunnelConfig, 'id' | 'status' | 'createdAt'>) {
+  return {
+    mode: tunnel.mode,
+    tunnelAddr: tunnel.tunnelAddr,
+    targetAddr: tunnel.targetAddr,
+    logLevel: tunnel.logLevel,
+    tlsMode: tunnel.tlsMode,
+    certFile: tunnel.certFile || null,
+    keyFile: tunnel.keyFile || null,
+    minPool: tunnel.minPool,
+    maxPool: tunnel.maxPool,
+    runMode: tunnel.runMode,
+    readTimeout: tunnel.readTimeout,
+    rateLimit: tunnel.rateLimit,
+    slotLimit: tunnel.slotLimit,
+    proxyProtocol: tunnel.proxyProtocol,
+    disableTcp: tunnel.disableTcp,
+    disableUdp: tunnel.disableUdp,
+    extraParams: tunnel.extraParams
+  }
 }
 
 // 代理设置接口
@@ -22,7 +65,7 @@ export interface ProxySettings {
   port: string
   username: string
   password: string
-  type: 'http' | 'socks5'
+  type: 'http' | 'https' | 'socks5' | 'socks5h'
 }
 
 // 系统设置接口
@@ -33,7 +76,9 @@ export interface SystemSettings {
   minimizeToTray: boolean
   startMinimized: boolean
   checkUpdates: boolean
-  logLevel: 'error' | 'info' | 'debug'
+  // 后台检查更新的间隔（小时）
+  updateCheckIntervalHours?: number
+  logLevel: NodePassLogLevel
   maxLogFiles: number
   logRetentionDays: number
   // 导航设置
@@ -45,7 +90,6 @@ export interface SystemSettings {
 
 // 应用配置接口
 export interface AppConfig {
-  tunnels: TunnelConfig[]
   settings: SystemSettings
   version: string
   lastUpdated: string
@@ -53,7 +97,6 @@ export interface AppConfig {
 
 // 默认配置
 const DEFAULT_CONFIG: AppConfig = {
-  tunnels: [],
   settings: {
     theme: 'light',
     language: 'zh',
@@ -79,7 +122,8 @@ const DEFAULT_CONFIG: AppConfig = {
   lastUpdated: new Date().toISOString()
 }
 
-const CONFIG_KEY = 'nodepass-config'
+// 旧版本保存在 localStorage 中的配置，首次启动时导入到后端
+const LEGACY_CONFIG_KEY = 'nodepass-config'
 
 export class ConfigManager {
   private static instance: ConfigManager
@@ -97,12 +141,20 @@ export class ConfigManager {
   // 初始化配置，程序启动时调用
   public async initialize(): Promise<void> {
     try {
-      const savedConfig = localStorage.getItem(CONFIG_KEY)
-      
+      const savedConfig = await invoke<AppConfig | null>('get_app_config')
+
       if (savedConfig) {
-        await this.loadConfig(savedConfig)
+        this.loadConfig(savedConfig)
       } else {
-        await this.saveConfig()
+        const legacyConfig = localStorage.getItem(LEGACY_CONFIG_KEY)
+        if (legacyConfig) {
+          // 导入成功后删除旧配置，之后只使用后端保存的配置
+          const imported = await invoke<AppConfig>('import_legacy_app_config', { content: legacyConfig })
+          this.loadConfig(imported)
+          localStorage.removeItem(LEGACY_CONFIG_KEY)
+        } else {
+          await this.saveConfig()
+        }
       }
     } catch (error) {
       console.error('配置初始化失败:', error)
@@ -113,10 +165,8 @@ export class ConfigManager {
   }
 
   // 加载配置
-  private async loadConfig(configContent: string): Promise<void> {
+  private loadConfig(loadedConfig: AppConfig): void {
     try {
-      const loadedConfig = JSON.parse(configContent) as AppConfig
-      
       // 合并配置，确保新增的字段有默认值
       this.config = {
         ...DEFAULT_CONFIG,
@@ -141,64 +191,13 @@ export class ConfigManager {
   private async saveConfig(): Promise<void> {
     try {
       this.config.lastUpdated = new Date().toISOString()
-      const configContent = JSON.stringify(this.config, null, 2)
-      localStorage.setItem(CONFIG_KEY, configContent)
+      await invoke('update_app_config', { config: this.config })
     } catch (error) {
       console.error('保存配置失败:', error)
       throw error
     }
   }
 
-  // 获取所有隧道
-  public getTunnels(): TunnelConfig[] {
-    return this.config.tunnels
-  }
-
-  // 添加隧道
-  public async addTunnel(tunnel: Omit<TunnelConfig, 'id' | 'status' | 'createdAt'>): Promise<string> {
-    const newTunnel: TunnelConfig = {
-      ...tunnel,
-      id: this.generateId(),
-      status: 'stopped',
-      createdAt: new Date().toISOString()
-    }
-    
-    this.config.tunnels.push(newTunnel)
-    await this.saveConfig()
-    return newTunnel.id
-  }
-
-  // 更新隧道
-  public async updateTunnel(id: string, updates: Partial<TunnelConfig>): Promise<void> {
-    const index = this.config.tunnels.findIndex(t => t.id === id)
-    if (index === -1) {
-      throw new Error(`隧道 ${id} 不存在`)
-    }
-    
-    this.config.tunnels[index] = {
-      ...this.config.tunnels[index],
-      ...updates
-    }
-    
-    await this.saveConfig()
-  }
-
-  // 删除隧道
-  public async deleteTunnel(id: string): Promise<void> {
-    const index = this.config.tunnels.findIndex(t => t.id === id)
-    if (index === -1) {
-      throw new Error(`隧道 ${id} 不存在`)
-    }
-    
-    this.config.tunnels.splice(index, 1)
-    await this.saveConfig()
-  }
-
-  // 获取隧道详情
-  public getTunnel(id: string): TunnelConfig | undefined {
-    return this.config.tunnels.find(t => t.id === id)
-  }
-
   // 获取系统设置
   public getSettings(): SystemSettings {
     return this.config.settings
@@ -211,6 +210,36 @@ export class ConfigManager {
       ...settings
     }
     await this.saveConfig()
+
+    // 日志保留设置由后端执行
+    if (settings.maxLogFiles !== undefined || settings.logRetentionDays !== undefined) {
+      try {
+        const logSettings = await invoke<Record<string, number>>('get_log_settings')
+        await invoke('update_log_settings', {
+          settings: {
+            ...logSettings,
+            maxLogFiles: this.config.settings.maxLogFiles,
+            logRetentionDays: this.config.settings.logRetentionDays
+          }
+        })
+      } catch (error) {
+        console.error('同步日志设置失败:', error)
+      }
+    }
+
+    // 更新检查由后端定时执行
+    if (settings.checkUpdates !== undefined || settings.updateCheckIntervalHours !== undefined) {
+      try {
+        await invoke('update_update_check_settings', {
+          settings: {
+            checkUpdates: this.config.settings.checkUpdates,
+            intervalHours: this.config.settings.updateCheckIntervalHours ?? 6
+          }
+        })
+      } catch (error) {
+        console.error('同步更新检查设置失败:', error)
+      }
+    }
   }
 
   // 获取完整配置
@@ -218,11 +247,6 @@ export class ConfigManager {
     return this.config
   }
 
-  // 生成唯一ID
-  private generateId(): string {
-    return Date.now().toString(36) + Math.random().toString(36).substr(2)
-  }
-
   // 导出配置
   public async exportConfig(): Promise<string> {
     return JSON.stringify(this.config, null, 2)
@@ -234,7 +258,8 @@ export class ConfigManager {
       const importedConfig = JSON.parse(configJson) as AppConfig
       
       // 验证配置格式
-      if (!importedConfig.tunnels || !importedConfig.settings) {
+      // 旧配置中的隧道列表保存后由后端导入 tunnels.json
+      if (!importedConfig.settings) {
         throw new Error('配置文件格式无效')
       }
       
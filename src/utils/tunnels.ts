@@ -0,0 +1,73 @@
+import { invoke } from '@tauri-apps/api/core'
+import { TunnelConfig, toNodePassConfig } from './config'
+
+// 后端注册表返回的隧道快照
+interface TunnelSnapshot extends Omit<TunnelConfig, 'status' | 'processId' | 'lastStarted' | 'error' | 'createdAt'> {
+  createdAt: string | null
+  runtime: {
+    status: TunnelConfig['status']
+    pid: number | null
+    started_at: number | null
+    exit_code: number | null
+    error: string | null
+  }
+}
+
+function fromSnapshot({ runtime, ...definition }: TunnelSnapshot): TunnelConfig {
+  return {
+    ...definition,
+    createdAt: definition.createdAt ?? '',
+    status: runtime.status,
+    processId: runtime.pid ?? undefined,
+    lastStarted: runtime.started_at ? new Date(runtime.started_at).toISOString() : undefined,
+    error: runtime.error ?? undefined
+  }
+}
+
+// 生成唯一ID
+function generateId(): string {
+  return Date.now().toString(36) + Math.random().toString(36).substr(2)
+}
+
+export async function listTunnels(): Promise<TunnelConfig[]> {
+  const snapshots = await invoke<TunnelSnapshot[]>('list_tunnels')
+  return snapshots.map(fromSnapshot)
+}
+
+// 隧道定义文件损坏等加载问题，没有问题时返回 null
+export async function getTunnelStoreError(): Promise<string | null> {
+  return invoke<string | null>('get_tunnel_store_error')
+}
+
+// 隧道不存在时后端返回错误
+export async function getTunnel(id: string): Promise<TunnelConfig> {
+  return fromSnapshot(await invoke<TunnelSnapshot>('get_tunnel', { tunnelId: id }))
+}
+
+// 登记新隧道，返回隧道ID
+export async function createTunnel(tunnel: Omit<TunnelConfig, 'id' | 'status' | 'createdAt'>): Promise<string> {
+  const id = generateId()
+  await invoke('save_tunnel', {
+    tunnel: {
+      ...toNodePassConfig(tunnel),
+      id,
+      name: tunnel.name,
+      nodepassVersion: tunnel.nodepassVersion,
+      createdAt: new Date().toISOString()
+    }
+  })
+  return id
+}
+
+export async function deleteTunnel(id: string): Promise<void> {
+  await invoke('delete_tunnel', { tunnelId: id })
+}
+
+// 启动隧道，返回进程ID
+export async function startTunnel(id: string): Promise<number> {
+  return invoke<number>('start_tunnel', { tunnelId: id })
+}
+
+export async function stopTunnel(id: string): Promise<void> {
+  await invoke('stop_tunnel', { tunnelId: id })
+}